@@ -0,0 +1,318 @@
+//! One-dimensional barcodes, rendered like the QR codes in [`crate::qrcode`]
+
+use barcoders::sym::code128::Code128;
+use image::{DynamicImage, Rgb, RgbImage};
+use std::fmt;
+
+/// Width of the blank margin on each side, in modules
+pub const BARCODE_QUIET_ZONE_MODULES: u32 = 10;
+
+// barcoders' markers for switching Code128 character sets
+const SET_B: char = '\u{0181}';
+const SET_C: char = '\u{0106}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BarcodeKind {
+    /// Any printable ASCII; runs of digits are packed two per symbol
+    #[default]
+    Code128,
+}
+
+/// An encoded barcode: a row of dark and light modules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Barcode {
+    kind: BarcodeKind,
+    data: String,
+    modules: Vec<bool>,
+}
+
+impl Barcode {
+    pub fn kind(&self) -> BarcodeKind {
+        self.kind
+    }
+
+    /// The encoded text
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// `true` for each dark module, left to right, without the quiet zone
+    pub fn modules(&self) -> &[bool] {
+        &self.modules
+    }
+
+    /// Width in modules, without the quiet zone
+    pub fn width(&self) -> usize {
+        self.modules.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BarcodeImageConfig {
+    /// Pixels per module
+    pub scale: u32,
+    /// Bar height in pixels
+    pub height: u32,
+    pub dark_color: [u8; 3],
+    pub light_color: [u8; 3],
+}
+
+impl Default for BarcodeImageConfig {
+    fn default() -> Self {
+        Self {
+            scale: 2,
+            height: 80,
+            dark_color: [0, 0, 0],
+            light_color: [255, 255, 255],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BarcodeError {
+    EmptyData,
+    InvalidCharacter {
+        kind: BarcodeKind,
+        ch: char,
+    },
+    EncodingFailed(String),
+    /// The image would be wider than `u32::MAX` pixels at the requested scale
+    ImageTooLarge {
+        scale: u32,
+    },
+}
+
+impl fmt::Display for BarcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BarcodeError::EmptyData => {
+                write!(f, "Barcode data cannot be empty")
+            }
+            BarcodeError::InvalidCharacter { kind, ch } => {
+                write!(f, "{:?} cannot encode {:?}", kind, ch)
+            }
+            BarcodeError::EncodingFailed(msg) => {
+                write!(f, "Barcode encoding failed: {}", msg)
+            }
+            BarcodeError::ImageTooLarge { scale } => {
+                write!(f, "Barcode image is too large at scale {}", scale)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BarcodeError {}
+
+pub fn generate_barcode(kind: BarcodeKind, data: &str) -> Result<Barcode, BarcodeError> {
+    match kind {
+        BarcodeKind::Code128 => generate_code128(data),
+    }
+}
+
+/// Encode printable ASCII as Code128
+///
+/// Digit-only data uses character set C (two digits per symbol), with a
+/// trailing odd digit in set B; anything else uses set B.
+pub fn generate_code128(data: &str) -> Result<Barcode, BarcodeError> {
+    if data.is_empty() {
+        return Err(BarcodeError::EmptyData);
+    }
+    if let Some(ch) = data.chars().find(|c| !(' '..='~').contains(c)) {
+        return Err(BarcodeError::InvalidCharacter {
+            kind: BarcodeKind::Code128,
+            ch,
+        });
+    }
+
+    let marked = if data.len() >= 2 && data.bytes().all(|b| b.is_ascii_digit()) {
+        let (pairs, rest) = data.split_at(data.len() / 2 * 2);
+        if rest.is_empty() {
+            format!("{}{}", SET_C, pairs)
+        } else {
+            format!("{}{}{}{}", SET_C, pairs, SET_B, rest)
+        }
+    } else {
+        format!("{}{}", SET_B, data)
+    };
+
+    let code = Code128::new(&marked).map_err(|e| BarcodeError::EncodingFailed(e.to_string()))?;
+    Ok(Barcode {
+        kind: BarcodeKind::Code128,
+        data: data.to_string(),
+        modules: code.encode().into_iter().map(|bit| bit == 1).collect(),
+    })
+}
+
+/// Render with the quiet zone on both sides
+pub fn render_barcode_to_image(
+    barcode: &Barcode,
+    config: &BarcodeImageConfig,
+) -> Result<DynamicImage, BarcodeError> {
+    let scale = config.scale.max(1);
+    let modules = u32::try_from(barcode.width())
+        .ok()
+        .and_then(|bars| bars.checked_add(BARCODE_QUIET_ZONE_MODULES.checked_mul(2)?));
+    let Some(width) = modules.and_then(|modules| modules.checked_mul(scale)) else {
+        return Err(BarcodeError::ImageTooLarge { scale });
+    };
+    // Every offset below is within `width`, so it can't overflow either
+    let quiet = BARCODE_QUIET_ZONE_MODULES * scale;
+    let height = config.height.max(1);
+
+    let mut image = RgbImage::from_pixel(width, height, Rgb(config.light_color));
+    for (i, _) in barcode
+        .modules()
+        .iter()
+        .enumerate()
+        .filter(|(_, &dark)| dark)
+    {
+        let x0 = quiet + i as u32 * scale;
+        for x in x0..x0 + scale {
+            for y in 0..height {
+                image.put_pixel(x, y, Rgb(config.dark_color));
+            }
+        }
+    }
+
+    Ok(DynamicImage::ImageRgb8(image))
+}
+
+/// Render as an SVG with one rectangle per bar, sized in modules
+pub fn render_barcode_to_svg(barcode: &Barcode, config: &BarcodeImageConfig) -> String {
+    let hex = |c: [u8; 3]| format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2]);
+    let scale = config.scale.max(1);
+    let quiet = BARCODE_QUIET_ZONE_MODULES as usize;
+    let width = barcode.width() + quiet * 2;
+    let height = config.height.max(1) as f32 / scale as f32;
+
+    let mut svg = format!(
+        "<?xml version=\"1.0\" standalone=\"yes\"?>\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">\
+         <rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        width * scale as usize,
+        config.height.max(1),
+        width,
+        height,
+        width,
+        height,
+        hex(config.light_color)
+    );
+
+    // One rect per run of dark modules
+    let modules = barcode.modules();
+    let mut x = 0;
+    while x < modules.len() {
+        if !modules[x] {
+            x += 1;
+            continue;
+        }
+        let run = modules[x..].iter().take_while(|&&dark| dark).count();
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            x + quiet,
+            run,
+            height,
+            hex(config.dark_color)
+        ));
+        x += run;
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    // Code128 start codes B and C
+    const START_B: &str = "11010010000";
+    const START_C: &str = "11010011100";
+
+    fn bits(barcode: &Barcode) -> String {
+        barcode
+            .modules()
+            .iter()
+            .map(|&dark| if dark { '1' } else { '0' })
+            .collect()
+    }
+
+    #[test]
+    fn test_code128_known_values() {
+        // Start, five symbols, checksum: 11 modules each, plus the 13-module stop
+        let hello = generate_code128("Hello").unwrap();
+        assert_eq!(hello.width(), 11 * 7 + 13);
+        assert!(bits(&hello).starts_with(START_B));
+        assert!(bits(&hello).ends_with("1100011101011"));
+        assert_eq!(hello.data(), "Hello");
+
+        // Digits pack in pairs: start, three symbols, checksum
+        let digits = generate_code128("123456").unwrap();
+        assert_eq!(digits.width(), 11 * 5 + 13);
+        assert!(bits(&digits).starts_with(START_C));
+
+        // An odd digit switches to set B for the last one
+        let odd = generate_code128("12345").unwrap();
+        assert_eq!(odd.width(), 11 * 6 + 13);
+    }
+
+    #[test]
+    fn test_code128_rejects_invalid_data() {
+        assert!(matches!(generate_code128(""), Err(BarcodeError::EmptyData)));
+        assert!(matches!(
+            generate_code128("café"),
+            Err(BarcodeError::InvalidCharacter { ch: 'é', .. })
+        ));
+        assert!(matches!(
+            generate_code128("tab\there"),
+            Err(BarcodeError::InvalidCharacter { ch: '\t', .. })
+        ));
+    }
+
+    #[test]
+    fn test_image_width_scales_with_data_length() {
+        let config = BarcodeImageConfig::default();
+        let short = render_barcode_to_image(&generate_code128("ABC").unwrap(), &config).unwrap();
+        let long = render_barcode_to_image(&generate_code128("ABCDEF").unwrap(), &config).unwrap();
+
+        // Three more symbols at 11 modules each, 2px per module
+        assert_eq!(long.width() - short.width(), 3 * 11 * 2);
+        assert_eq!(
+            short.width(),
+            ((11 * 5 + 13) + 2 * BARCODE_QUIET_ZONE_MODULES) * 2
+        );
+        assert_eq!(short.height(), 80);
+
+        // Quiet zone is light and the first bar is dark
+        let quiet = BARCODE_QUIET_ZONE_MODULES * 2;
+        assert_eq!(short.get_pixel(quiet - 1, 40).0, [255, 255, 255, 255]);
+        assert_eq!(short.get_pixel(quiet, 40).0, [0, 0, 0, 255]);
+
+        let huge = BarcodeImageConfig {
+            scale: 4_000_000_000,
+            ..config
+        };
+        assert!(matches!(
+            render_barcode_to_image(&generate_code128("ABC").unwrap(), &huge),
+            Err(BarcodeError::ImageTooLarge {
+                scale: 4_000_000_000
+            })
+        ));
+    }
+
+    #[test]
+    fn test_svg_has_one_rect_per_bar() {
+        let barcode = generate_code128("Hello").unwrap();
+        let svg = render_barcode_to_svg(&barcode, &BarcodeImageConfig::default());
+        let bars = bits(&barcode)
+            .split('0')
+            .filter(|run| !run.is_empty())
+            .count();
+        // Plus the background
+        assert_eq!(svg.matches("<rect").count(), bars + 1);
+        assert!(svg.contains("viewBox=\"0 0 110 40\""));
+        assert!(svg.ends_with("</svg>"));
+    }
+}
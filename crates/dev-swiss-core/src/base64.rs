@@ -0,0 +1,127 @@
+use ::base64::alphabet::{self, Alphabet};
+use ::base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use ::base64::Engine;
+use std::fmt;
+
+/// Base64 alphabets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Base64Alphabet {
+    /// RFC 4648 standard alphabet (`+` and `/`)
+    #[default]
+    Standard,
+    /// RFC 4648 URL- and filename-safe alphabet (`-` and `_`)
+    UrlSafe,
+}
+
+#[derive(Debug, Clone)]
+pub struct Base64Config {
+    pub alphabet: Base64Alphabet,
+    /// Emit `=` padding when encoding (decoding accepts either form)
+    pub padding: bool,
+}
+
+impl Default for Base64Config {
+    fn default() -> Self {
+        Self {
+            alphabet: Base64Alphabet::Standard,
+            padding: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Base64Error {
+    InvalidInput(String),
+}
+
+impl fmt::Display for Base64Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Base64Error::InvalidInput(msg) => write!(f, "Invalid base64 input: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Base64Error {}
+
+fn engine(config: &Base64Config) -> GeneralPurpose {
+    let alphabet: &Alphabet = match config.alphabet {
+        Base64Alphabet::Standard => &alphabet::STANDARD,
+        Base64Alphabet::UrlSafe => &alphabet::URL_SAFE,
+    };
+    let engine_config = GeneralPurposeConfig::new()
+        .with_encode_padding(config.padding)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    GeneralPurpose::new(alphabet, engine_config)
+}
+
+/// Encode bytes as base64
+pub fn encode_base64(data: &[u8], config: &Base64Config) -> String {
+    engine(config).encode(data)
+}
+
+/// Decode base64 text; ASCII whitespace (e.g. line wrapping) is ignored
+pub fn decode_base64(input: &str, config: &Base64Config) -> Result<Vec<u8>, Base64Error> {
+    let compact: String = input.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    engine(config)
+        .decode(compact)
+        .map_err(|e| Base64Error::InvalidInput(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_known_values() {
+        let config = Base64Config::default();
+        assert_eq!(encode_base64(b"hello", &config), "aGVsbG8=");
+
+        let config = Base64Config {
+            padding: false,
+            ..Default::default()
+        };
+        assert_eq!(encode_base64(b"hello", &config), "aGVsbG8");
+    }
+
+    #[test]
+    fn test_round_trip_binary_both_alphabets() {
+        let data: Vec<u8> = (0..=255).collect();
+        for alphabet in [Base64Alphabet::Standard, Base64Alphabet::UrlSafe] {
+            for padding in [true, false] {
+                let config = Base64Config { alphabet, padding };
+                let encoded = encode_base64(&data, &config);
+                assert_eq!(decode_base64(&encoded, &config).unwrap(), data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_alphabet_characters() {
+        let data = [0xfb, 0xff, 0xbf];
+        assert_eq!(encode_base64(&data, &Base64Config::default()), "+/+/");
+        let url_safe = Base64Config {
+            alphabet: Base64Alphabet::UrlSafe,
+            ..Default::default()
+        };
+        assert_eq!(encode_base64(&data, &url_safe), "-_-_");
+    }
+
+    #[test]
+    fn test_decode_ignores_whitespace_and_padding() {
+        let config = Base64Config::default();
+        assert_eq!(decode_base64("aGVs\nbG8=\n", &config).unwrap(), b"hello");
+        assert_eq!(decode_base64("aGVsbG8", &config).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let config = Base64Config::default();
+        assert!(matches!(
+            decode_base64("not*base64", &config),
+            Err(Base64Error::InvalidInput(_))
+        ));
+        // URL-safe characters are rejected by the standard alphabet
+        assert!(decode_base64("-_-_", &config).is_err());
+    }
+}
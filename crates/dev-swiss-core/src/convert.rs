@@ -1,14 +1,30 @@
-use docx_rs::{Docx, Paragraph, Run};
+use chardetng::EncodingDetector;
+use docx_rs::{
+    Break, BreakType, Docx, Hyperlink, HyperlinkType, LineSpacing, LineSpacingType, PageMargin,
+    Paragraph, ParagraphChild, Run, RunChild, RunFonts, Table, TableCell, TableRow,
+};
+use encoding_rs::{Encoding, UTF_8};
 use std::fmt;
 use std::fs::File;
-use std::io::BufWriter;
-use std::path::PathBuf;
+use std::io::{BufWriter, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::utc::utc_timestamp;
+
+mod html;
+mod layout;
+
+use layout::{guess_table, line_text, split_cells, TableGuess, Word};
 
 /// Supported conversion formats
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Format {
     Pdf,
     Docx,
+    Txt,
+    Html,
 }
 
 impl fmt::Display for Format {
@@ -16,6 +32,8 @@ impl fmt::Display for Format {
         match self {
             Format::Pdf => write!(f, "PDF"),
             Format::Docx => write!(f, "DOCX"),
+            Format::Txt => write!(f, "TXT"),
+            Format::Html => write!(f, "HTML"),
         }
     }
 }
@@ -23,11 +41,29 @@ impl fmt::Display for Format {
 /// Conversion errors
 #[derive(Debug)]
 pub enum ConvertError {
-    UnsupportedConversion { from: Format, to: Format },
+    UnsupportedConversion {
+        from: Format,
+        to: Format,
+    },
     InputNotFound(PathBuf),
     OutputExists(PathBuf),
     PdfReadError(String),
+    NoExtractableText(PathBuf),
+    UnknownEncoding(String),
+    InvalidBaseUrl(String),
+    PdfWriteError(String),
+    DocxReadError(String),
     DocxWriteError(String),
+    InvalidStyle(String),
+    /// `ConvertConfig::append` was set for a target other than DOCX
+    AppendRequiresDocx(Format),
+    /// A warning raised while `ConvertConfig::strict` was set
+    WarningAsError(String),
+    /// The input is larger than `ConvertConfig::max_input_bytes`
+    InputTooLarge {
+        size: u64,
+        limit: u64,
+    },
     IoError(std::io::Error),
 }
 
@@ -46,9 +82,44 @@ impl fmt::Display for ConvertError {
             ConvertError::PdfReadError(msg) => {
                 write!(f, "Failed to read PDF: {}", msg)
             }
+            ConvertError::NoExtractableText(path) => {
+                write!(
+                    f,
+                    "PDF has no extractable text (may be image-based): {}",
+                    path.display()
+                )
+            }
+            ConvertError::UnknownEncoding(label) => {
+                write!(f, "Unknown text encoding: {}", label)
+            }
+            ConvertError::InvalidBaseUrl(msg) => {
+                write!(f, "Invalid base URL: {}", msg)
+            }
+            ConvertError::PdfWriteError(msg) => {
+                write!(f, "Failed to write PDF: {}", msg)
+            }
+            ConvertError::DocxReadError(msg) => {
+                write!(f, "Failed to read DOCX: {}", msg)
+            }
             ConvertError::DocxWriteError(msg) => {
                 write!(f, "Failed to write DOCX: {}", msg)
             }
+            ConvertError::InvalidStyle(msg) => {
+                write!(f, "Invalid DOCX style: {}", msg)
+            }
+            ConvertError::AppendRequiresDocx(format) => {
+                write!(
+                    f,
+                    "Appending is only supported for DOCX output, not {}",
+                    format
+                )
+            }
+            ConvertError::WarningAsError(msg) => {
+                write!(f, "{} (strict mode)", msg)
+            }
+            ConvertError::InputTooLarge { size, limit } => {
+                write!(f, "Input is {} bytes, over the {}-byte limit", size, limit)
+            }
             ConvertError::IoError(e) => {
                 write!(f, "IO error: {}", e)
             }
@@ -64,6 +135,84 @@ impl From<std::io::Error> for ConvertError {
     }
 }
 
+/// Font sizes accepted by `DocxStyle`, in points
+pub const DOCX_FONT_SIZE_RANGE: RangeInclusive<f32> = 4.0..=144.0;
+
+/// Largest page margin accepted by `DocxStyle`, in points (4 inches)
+pub const DOCX_MAX_MARGIN: f32 = 288.0;
+
+/// Largest line spacing accepted by `DocxStyle`, in lines
+pub const DOCX_MAX_LINE_SPACING: f32 = 10.0;
+
+/// Largest paragraph spacing accepted by `DocxStyle`, in points (Word's limit)
+pub const DOCX_MAX_PARAGRAPH_SPACING: f32 = 1584.0;
+
+/// Appearance of DOCX output; `None` fields keep the Word defaults
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocxStyle {
+    /// Font family for every run, e.g. `Arial`
+    pub font_name: Option<String>,
+    /// Font size in points, rounded to the nearest half point
+    pub font_size_pt: Option<f32>,
+    /// Page margin on all four sides, in points
+    pub margin: Option<f32>,
+    /// Line spacing as a multiple of single spacing, e.g. `1.5`
+    pub line_spacing: Option<f32>,
+    /// Space after each paragraph, in points; in layout-preserving output
+    /// every line is a paragraph
+    pub paragraph_spacing: Option<f32>,
+}
+
+impl DocxStyle {
+    /// Check the font name is non-empty and sizes are within sane limits
+    pub fn validate(&self) -> Result<(), ConvertError> {
+        if self
+            .font_name
+            .as_deref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            return Err(ConvertError::InvalidStyle(
+                "font name cannot be empty".to_string(),
+            ));
+        }
+        if let Some(size) = self.font_size_pt {
+            if !DOCX_FONT_SIZE_RANGE.contains(&size) {
+                return Err(ConvertError::InvalidStyle(format!(
+                    "font size {}pt is outside {}-{}pt",
+                    size,
+                    DOCX_FONT_SIZE_RANGE.start(),
+                    DOCX_FONT_SIZE_RANGE.end()
+                )));
+            }
+        }
+        if let Some(margin) = self.margin {
+            if !(0.0..=DOCX_MAX_MARGIN).contains(&margin) {
+                return Err(ConvertError::InvalidStyle(format!(
+                    "margin {}pt is outside 0-{}pt",
+                    margin, DOCX_MAX_MARGIN
+                )));
+            }
+        }
+        if let Some(spacing) = self.line_spacing {
+            if !(spacing > 0.0 && spacing <= DOCX_MAX_LINE_SPACING) {
+                return Err(ConvertError::InvalidStyle(format!(
+                    "line spacing {} must be positive and at most {}",
+                    spacing, DOCX_MAX_LINE_SPACING
+                )));
+            }
+        }
+        if let Some(spacing) = self.paragraph_spacing {
+            if !(0.0..=DOCX_MAX_PARAGRAPH_SPACING).contains(&spacing) {
+                return Err(ConvertError::InvalidStyle(format!(
+                    "paragraph spacing {}pt is outside 0-{}pt",
+                    spacing, DOCX_MAX_PARAGRAPH_SPACING
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Configuration for file conversion
 #[derive(Debug, Clone)]
 pub struct ConvertConfig {
@@ -73,6 +222,60 @@ pub struct ConvertConfig {
     pub to_format: Format,
     pub force: bool,
     pub verbose: bool,
+    /// Fail before extraction if the PDF has no text operators
+    pub require_text: bool,
+    /// Run validation and extraction but skip writing the output file
+    pub dry_run: bool,
+    /// Encoding label for text inputs (e.g. `windows-1252`); detected when `None`
+    pub encoding: Option<String>,
+    /// Use layout-aware PDF extraction to keep hyperlinks
+    pub preserve_formatting: bool,
+    /// Detect column-aligned PDF text and emit it as DOCX tables
+    pub detect_tables: bool,
+    /// Base URL for resolving relative links in HTML input
+    pub base_url: Option<String>,
+    /// Use fixed IDs and timestamps so identical input gives byte-identical DOCX output
+    pub deterministic: bool,
+    /// Measure extraction and output building separately (see `ConvertResult`)
+    pub timing: bool,
+    /// Font and page layout for DOCX output
+    pub docx_style: DocxStyle,
+    /// Fail on the first warning instead of writing possibly degraded output
+    pub strict: bool,
+    /// Existing DOCX whose content goes first in the output, followed by a
+    /// page break and the converted input
+    pub append: Option<PathBuf>,
+    /// Append the values of filled-in PDF form fields, labeled with their
+    /// names, after the last page
+    pub include_form_fields: bool,
+    /// Refuse inputs larger than this many bytes before reading them
+    pub max_input_bytes: Option<u64>,
+}
+
+impl Default for ConvertConfig {
+    fn default() -> Self {
+        Self {
+            input_path: PathBuf::new(),
+            output_path: PathBuf::new(),
+            from_format: Format::Pdf,
+            to_format: Format::Docx,
+            force: false,
+            verbose: false,
+            require_text: false,
+            dry_run: false,
+            encoding: None,
+            preserve_formatting: false,
+            detect_tables: false,
+            base_url: None,
+            deterministic: false,
+            timing: false,
+            docx_style: DocxStyle::default(),
+            strict: false,
+            append: None,
+            include_form_fields: false,
+            max_input_bytes: None,
+        }
+    }
 }
 
 /// Result of a successful conversion
@@ -80,17 +283,15 @@ pub struct ConvertConfig {
 pub struct ConvertResult {
     pub pages_processed: usize,
     pub warnings: Vec<String>,
+    /// Milliseconds spent reading and extracting the input, when timing
+    pub extract_ms: Option<u64>,
+    /// Milliseconds spent building and writing the output, when timing
+    pub build_ms: Option<u64>,
 }
 
 /// Convert a file from one format to another
 pub fn convert(config: &ConvertConfig) -> Result<ConvertResult, ConvertError> {
-    // Validate conversion is supported
-    if config.from_format != Format::Pdf || config.to_format != Format::Docx {
-        return Err(ConvertError::UnsupportedConversion {
-            from: config.from_format,
-            to: config.to_format,
-        });
-    }
+    check_request(config.from_format, config.to_format, config)?;
 
     // Check input exists
     if !config.input_path.exists() {
@@ -102,115 +303,2212 @@ pub fn convert(config: &ConvertConfig) -> Result<ConvertResult, ConvertError> {
         return Err(ConvertError::OutputExists(config.output_path.clone()));
     }
 
-    convert_pdf_to_docx(config)
+    let input = File::open(&config.input_path)?;
+    if let Some(limit) = config.max_input_bytes {
+        let size = input.metadata()?.len();
+        if size > limit {
+            return Err(ConvertError::InputTooLarge { size, limit });
+        }
+    }
+    let mut output = LazyFile::new(&config.output_path);
+    let result = convert_stream(
+        input,
+        &mut output,
+        config.from_format,
+        config.to_format,
+        config,
+    );
+    // Don't leave a partly written file behind
+    if result.is_err() && output.file.is_some() {
+        let _ = std::fs::remove_file(&config.output_path);
+    }
+    result
 }
 
-fn convert_pdf_to_docx(config: &ConvertConfig) -> Result<ConvertResult, ConvertError> {
+/// Convert the content of `reader` from one format to another, writing the
+/// result to `writer`, for in-memory buffers, pipes or network streams
+///
+/// The input is read whole before conversion starts, while PDF pages still
+/// reach `writer` one at a time. With `max_input_bytes` set, reading stops
+/// one byte past the limit, so an oversized stream is never held in memory.
+/// The path, format and `force` fields of `opts` are ignored (`append` is
+/// still read from disk), and nothing is written on a dry run. If the
+/// conversion fails, `writer` may have received part of the output.
+pub fn convert_stream<R: Read, W: Write>(
+    mut reader: R,
+    writer: W,
+    from: Format,
+    to: Format,
+    opts: &ConvertConfig,
+) -> Result<ConvertResult, ConvertError> {
+    check_request(from, to, opts)?;
+
+    let started = opts.timing.then(Instant::now);
+    let mut input = Vec::new();
+    match opts.max_input_bytes {
+        Some(limit) => {
+            reader
+                .take(limit.saturating_add(1))
+                .read_to_end(&mut input)?;
+            if input.len() as u64 > limit {
+                return Err(ConvertError::InputTooLarge {
+                    size: input.len() as u64,
+                    limit,
+                });
+            }
+        }
+        None => {
+            reader.read_to_end(&mut input)?;
+        }
+    }
+
+    if from == Format::Html {
+        return convert_html_to_pdf(&input, writer, opts, started);
+    }
+
     let mut warnings = Vec::new();
 
-    // Extract text from PDF
-    let text = pdf_extract::extract_text(&config.input_path)
-        .map_err(|e| ConvertError::PdfReadError(e.to_string()))?;
+    // Read up front, so an unreadable document fails before any extraction
+    // (and before the output is written, in case it is the same file)
+    let base = match &opts.append {
+        Some(path) if !path.exists() => return Err(ConvertError::InputNotFound(path.clone())),
+        Some(path) => Some(parse_docx(&std::fs::read(path)?)?),
+        None => None,
+    };
 
-    // Split into pages (pdf-extract doesn't preserve page boundaries well,
-    // so we treat the whole document as one "page" for now)
-    let pages: Vec<&str> = if text.contains('\x0C') {
-        // Form feed character sometimes used as page separator
-        text.split('\x0C').collect()
+    let inner: Box<dyn PageSink + '_> = if opts.dry_run {
+        Box::new(DiscardSink)
     } else {
-        vec![&text]
+        match to {
+            Format::Docx => Box::new(DocxSink::new(
+                writer,
+                base,
+                opts.deterministic,
+                &opts.docx_style,
+            )),
+            Format::Txt => Box::new(TextSink::new(writer)),
+            Format::Pdf | Format::Html => {
+                unreachable!("{} output is not supported from this source", to)
+            }
+        }
     };
+    // PDFs are streamed page by page, so extraction and building interleave;
+    // the sink's share of the time is counted as building
+    let mut sink = TimedSink::new(inner, opts.timing);
 
-    if pages.len() == 1 && pages[0].trim().is_empty() {
-        warnings.push("PDF appears to contain no extractable text (may be image-based)".to_string());
+    let pages_processed = match from {
+        Format::Pdf => {
+            let doc = load_pdf(&input)?;
+            let pages = if opts.preserve_formatting || opts.detect_tables {
+                extract_pdf_layout(&doc, opts, &mut warnings, &mut sink)?
+            } else {
+                extract_pdf(&doc, opts, &mut warnings, &mut sink)?
+            };
+            if opts.include_form_fields {
+                write_form_fields(&doc, &mut warnings, &mut sink)?;
+            }
+            pages
+        }
+        Format::Docx => write_pages(docx_pages(&parse_docx(&input)?), &mut sink)?,
+        Format::Txt => write_pages(read_text(&input, opts, &mut warnings)?, &mut sink)?,
+        Format::Html => unreachable!("HTML input is handled by convert_html_to_pdf"),
+    };
+    check_strict(opts, &warnings)?;
+    let extract = started.map(|start| start.elapsed());
+    let build = sink.finish()?;
+
+    Ok(ConvertResult {
+        pages_processed,
+        warnings,
+        extract_ms: extract
+            .zip(build)
+            .map(|(total, build)| millis(total.saturating_sub(build))),
+        build_ms: build.map(millis),
+    })
+}
+
+/// Reject unsupported conversions and invalid options before reading anything
+fn check_request(from: Format, to: Format, opts: &ConvertConfig) -> Result<(), ConvertError> {
+    if !is_supported(from, to) {
+        return Err(ConvertError::UnsupportedConversion { from, to });
     }
 
-    // Create DOCX document
-    let mut docx = Docx::new();
+    opts.docx_style.validate()?;
 
-    for (i, page_text) in pages.iter().enumerate() {
-        // Add page content as paragraphs (split by newlines)
-        for line in page_text.lines() {
-            let trimmed = line.trim();
-            if !trimmed.is_empty() {
-                let paragraph = Paragraph::new().add_run(Run::new().add_text(trimmed));
-                docx = docx.add_paragraph(paragraph);
-            }
-        }
+    if opts.append.is_some() && to != Format::Docx {
+        return Err(ConvertError::AppendRequiresDocx(to));
+    }
+    Ok(())
+}
 
-        // Add page break between pages (except after last page)
-        if i < pages.len() - 1 {
-            let page_break = Paragraph::new().add_run(Run::new().add_break(docx_rs::BreakType::Page));
-            docx = docx.add_paragraph(page_break);
+/// Output file that is only created on the first write or flush, so a
+/// conversion that fails early leaves nothing behind
+struct LazyFile {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl LazyFile {
+    fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            file: None,
         }
     }
 
-    // Write DOCX file
-    let file = File::create(&config.output_path)?;
-    let mut writer = BufWriter::new(file);
-    docx.build()
-        .pack(&mut writer)
-        .map_err(|e| ConvertError::DocxWriteError(e.to_string()))?;
+    fn file(&mut self) -> std::io::Result<&mut File> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => File::create(&self.path)?,
+        };
+        Ok(self.file.insert(file))
+    }
+}
+
+impl Write for LazyFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file()?.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file()?.flush()
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// Every `(from, to)` conversion `convert` implements
+const SUPPORTED_CONVERSIONS: &[(Format, Format)] = &[
+    (Format::Pdf, Format::Docx),
+    (Format::Pdf, Format::Txt),
+    (Format::Docx, Format::Txt),
+    (Format::Txt, Format::Docx),
+    (Format::Html, Format::Pdf),
+];
+
+/// Every `(from, to)` pair `convert` can handle; anything else fails with
+/// `UnsupportedConversion`
+pub fn supported_conversions() -> &'static [(Format, Format)] {
+    SUPPORTED_CONVERSIONS
+}
+
+/// Whether a `from` → `to` conversion is implemented
+fn is_supported(from: Format, to: Format) -> bool {
+    SUPPORTED_CONVERSIONS.contains(&(from, to))
+}
+
+/// In strict mode, turn the first warning into an error
+fn check_strict(config: &ConvertConfig, warnings: &[String]) -> Result<(), ConvertError> {
+    match warnings.first() {
+        Some(warning) if config.strict => Err(ConvertError::WarningAsError(warning.clone())),
+        _ => Ok(()),
+    }
+}
+
+/// Render a basic HTML document (headings, paragraphs, lists, emphasis, links) to PDF
+fn convert_html_to_pdf<W: Write>(
+    bytes: &[u8],
+    writer: W,
+    config: &ConvertConfig,
+    started: Option<Instant>,
+) -> Result<ConvertResult, ConvertError> {
+    let mut warnings = Vec::new();
+
+    let base_url = config
+        .base_url
+        .as_deref()
+        .map(|u| {
+            url::Url::parse(u).map_err(|e| ConvertError::InvalidBaseUrl(format!("{}: {}", u, e)))
+        })
+        .transpose()?;
+
+    let source = decode_text(bytes, config.encoding.as_deref(), &mut warnings)?;
+    let blocks = html::parse_html(&source, base_url.as_ref());
+    if blocks.is_empty() {
+        warnings.push("HTML document contains no renderable text".to_string());
+    }
+
+    let extract = started.map(|start| start.elapsed());
+
+    let title = config
+        .input_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (doc, pages) = html::render_pdf(&blocks, &title)
+        .map_err(|e| ConvertError::PdfWriteError(e.to_string()))?;
+    check_strict(config, &warnings)?;
+
+    if !config.dry_run {
+        doc.save(&mut BufWriter::new(writer))
+            .map_err(|e| ConvertError::PdfWriteError(e.to_string()))?;
+    }
 
     Ok(ConvertResult {
-        pages_processed: pages.len(),
+        pages_processed: pages,
         warnings,
+        extract_ms: extract.map(millis),
+        build_ms: started
+            .zip(extract)
+            .map(|(start, extract)| millis(start.elapsed() - extract)),
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// A run of text, optionally linked to a URI
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    text: String,
+    link: Option<String>,
+}
 
-    #[test]
-    fn test_format_display() {
-        assert_eq!(format!("{}", Format::Pdf), "PDF");
-        assert_eq!(format!("{}", Format::Docx), "DOCX");
+/// A unit of extracted content
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    /// Paragraph text; embedded `\n` separate its lines
+    Paragraph(String),
+    /// A single line made of runs, some of which are hyperlinks
+    Line(Vec<Span>),
+    /// Rows of cell text, all with the same number of columns
+    Table(Vec<Vec<String>>),
+}
+
+impl Block {
+    fn text(&self) -> String {
+        match self {
+            Block::Paragraph(text) => text.clone(),
+            Block::Line(spans) => spans.iter().map(|s| s.text.as_str()).collect(),
+            Block::Table(rows) => rows
+                .iter()
+                .map(|row| row.join("\t"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
     }
+}
 
-    #[test]
-    fn test_unsupported_conversion() {
-        let config = ConvertConfig {
-            input_path: PathBuf::from("test.docx"),
-            output_path: PathBuf::from("test.pdf"),
-            from_format: Format::Docx,
-            to_format: Format::Pdf,
-            force: false,
-            verbose: false,
-        };
+/// Extracted document content: one entry per page, each a list of blocks.
+/// Only used for inputs that are read whole (DOCX, TXT); PDFs are streamed.
+type Pages = Vec<Vec<Block>>;
 
-        let result = convert(&config);
-        assert!(matches!(
-            result,
-            Err(ConvertError::UnsupportedConversion { .. })
-        ));
+/// Receives extracted pages one at a time, in document order
+trait PageSink {
+    fn write_page(&mut self, blocks: Vec<Block>) -> Result<(), ConvertError>;
+
+    /// Complete the output once every page has been written
+    fn finish(self: Box<Self>) -> Result<(), ConvertError>;
+}
+
+/// Hand already-read pages to `sink`, returning the page count
+fn write_pages(pages: Pages, sink: &mut dyn PageSink) -> Result<usize, ConvertError> {
+    let count = pages.len();
+    for page in pages {
+        sink.write_page(page)?;
     }
+    Ok(count)
+}
 
-    #[test]
-    fn test_input_not_found() {
-        let config = ConvertConfig {
-            input_path: PathBuf::from("/nonexistent/path/file.pdf"),
-            output_path: PathBuf::from("output.docx"),
-            from_format: Format::Pdf,
-            to_format: Format::Docx,
-            force: false,
-            verbose: false,
+/// Load a PDF, decrypting it with the empty password when needed
+fn load_pdf(bytes: &[u8]) -> Result<lopdf::Document, ConvertError> {
+    let mut doc =
+        lopdf::Document::load_mem(bytes).map_err(|e| ConvertError::PdfReadError(e.to_string()))?;
+    if doc.is_encrypted() {
+        doc.decrypt("")
+            .map_err(|e| ConvertError::PdfReadError(e.to_string()))?;
+    }
+    Ok(doc)
+}
+
+/// Split a page of plain text into paragraph blocks
+fn text_page(page_text: &str) -> Vec<Block> {
+    split_paragraphs(page_text)
+        .into_iter()
+        .map(Block::Paragraph)
+        .collect()
+}
+
+/// Number of leading pages [`pdf_has_text`] inspects
+const TEXT_SAMPLE_PAGES: usize = 10;
+
+/// Quick check whether a PDF contains any text-showing operators.
+///
+/// Samples the content streams of the first few pages instead of running full
+/// extraction, so scanned (image-only) documents can be detected cheaply.
+pub fn pdf_has_text(path: &Path) -> Result<bool, ConvertError> {
+    let doc = lopdf::Document::load(path).map_err(|e| ConvertError::PdfReadError(e.to_string()))?;
+    Ok(doc_has_text(&doc))
+}
+
+/// Whether any of the first [`TEXT_SAMPLE_PAGES`] pages of `doc` shows text
+fn doc_has_text(doc: &lopdf::Document) -> bool {
+    for page_id in doc.get_pages().values().take(TEXT_SAMPLE_PAGES) {
+        let Ok(content) = doc.get_page_content(*page_id) else {
+            continue;
         };
+        let Ok(content) = lopdf::content::Content::decode(&content) else {
+            continue;
+        };
+        let shows_text = content
+            .operations
+            .iter()
+            .any(|op| matches!(op.operator.as_str(), "Tj" | "TJ" | "'" | "\""));
+        if shows_text {
+            return true;
+        }
+    }
 
-        let result = convert(&config);
-        assert!(matches!(result, Err(ConvertError::InputNotFound(_))));
+    false
+}
+
+/// Extract plain text page by page, handing each page to `sink` before the next
+/// is extracted, so only one page of text is held in memory at a time
+fn extract_pdf(
+    doc: &lopdf::Document,
+    config: &ConvertConfig,
+    warnings: &mut Vec<String>,
+    sink: &mut dyn PageSink,
+) -> Result<usize, ConvertError> {
+    let has_text = check_pdf_text(doc, config, warnings)?;
+
+    let page_numbers: Vec<u32> = doc.get_pages().keys().copied().collect();
+    let mut found_text = false;
+    let pages = write_extracted_pages(&page_numbers, warnings, sink, |page_num, _| {
+        let mut text = String::new();
+        {
+            let mut output = pdf_extract::PlainTextOutput::new(&mut text);
+            pdf_extract::output_doc_page(doc, &mut output, page_num)
+                .map_err(|e| ConvertError::PdfReadError(e.to_string()))?;
+        }
+        found_text |= !text.trim().is_empty();
+        Ok(text_page(&text))
+    })?;
+
+    if has_text && !found_text {
+        warnings.push("PDF appears to contain no extractable text (may be image-based)".to_string());
     }
 
-    #[test]
-    fn test_error_display() {
-        let err = ConvertError::InputNotFound(PathBuf::from("test.pdf"));
-        assert!(err.to_string().contains("test.pdf"));
+    Ok(pages)
+}
 
-        let err = ConvertError::UnsupportedConversion {
-            from: Format::Docx,
-            to: Format::Pdf,
+/// Extract each page with `extract` and hand it to `sink`, returning the
+/// number of pages written
+///
+/// A page that fails to extract is skipped with a warning rather than losing
+/// the rest of the document; only when every page fails is the first error
+/// returned. Errors from `sink` are always fatal.
+fn write_extracted_pages<F>(
+    page_numbers: &[u32],
+    warnings: &mut Vec<String>,
+    sink: &mut dyn PageSink,
+    mut extract: F,
+) -> Result<usize, ConvertError>
+where
+    F: FnMut(u32, &mut Vec<String>) -> Result<Vec<Block>, ConvertError>,
+{
+    let mut written = 0;
+    let mut first_error = None;
+    for &page_num in page_numbers {
+        match extract(page_num, warnings) {
+            Ok(blocks) => {
+                sink.write_page(blocks)?;
+                written += 1;
+            }
+            Err(e) => {
+                warnings.push(format!("Page {}: skipped ({})", page_num, e));
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match first_error {
+        Some(e) if written == 0 => Err(e),
+        _ => Ok(written),
+    }
+}
+
+/// Check for text, erroring or warning per `config.require_text`
+fn check_pdf_text(
+    doc: &lopdf::Document,
+    config: &ConvertConfig,
+    warnings: &mut Vec<String>,
+) -> Result<bool, ConvertError> {
+    let has_text = doc_has_text(doc);
+    if !has_text {
+        if config.require_text {
+            return Err(ConvertError::NoExtractableText(config.input_path.clone()));
+        }
+        warnings
+            .push("PDF appears to contain no extractable text (may be image-based)".to_string());
+    }
+    Ok(has_text)
+}
+
+/// Layout-aware extraction: one block per text line, with link annotations
+/// attached to the words they cover and (optionally) aligned lines as tables
+fn extract_pdf_layout(
+    doc: &lopdf::Document,
+    config: &ConvertConfig,
+    warnings: &mut Vec<String>,
+    sink: &mut dyn PageSink,
+) -> Result<usize, ConvertError> {
+    check_pdf_text(doc, config, warnings)?;
+
+    let pages = doc.get_pages();
+    let page_numbers: Vec<u32> = pages.keys().copied().collect();
+    write_extracted_pages(&page_numbers, warnings, sink, |page_num, warnings| {
+        let layout = layout::extract_page_layout(doc, page_num)
+            .map_err(|e| ConvertError::PdfReadError(e.to_string()))?;
+        let links = if config.preserve_formatting {
+            link_annotations(doc, pages[&page_num])
+        } else {
+            Vec::new()
         };
-        assert!(err.to_string().contains("DOCX"));
-        assert!(err.to_string().contains("PDF"));
+        let line_block = |words: &[Word]| {
+            if links.is_empty() {
+                Block::Paragraph(line_text(words))
+            } else {
+                Block::Line(link_spans(words, &links))
+            }
+        };
+
+        if !config.detect_tables {
+            return Ok(layout.lines.iter().map(|words| line_block(words)).collect());
+        }
+
+        // Runs of consecutive multi-cell lines are table candidates
+        let mut blocks = Vec::new();
+        let mut candidate: Vec<&Vec<Word>> = Vec::new();
+        let lines = layout.lines.iter().map(Some).chain(std::iter::once(None));
+        for line in lines {
+            if let Some(words) = line {
+                if split_cells(words).len() >= 2 {
+                    candidate.push(words);
+                    continue;
+                }
+            }
+
+            if candidate.len() >= 2 {
+                let rows: Vec<_> = candidate.iter().map(|words| split_cells(words)).collect();
+                match guess_table(&rows) {
+                    TableGuess::Table(table) => blocks.push(Block::Table(table)),
+                    TableGuess::LowConfidence { confidence } => {
+                        warnings.push(format!(
+                            "Page {}: possible table kept as paragraphs (only {:.0}% of rows align to columns)",
+                            page_num,
+                            confidence * 100.0
+                        ));
+                        blocks.extend(candidate.iter().map(|words| line_block(words)));
+                    }
+                }
+            } else {
+                blocks.extend(candidate.iter().map(|words| line_block(words)));
+            }
+            candidate.clear();
+
+            if let Some(words) = line {
+                blocks.push(line_block(words));
+            }
+        }
+        Ok(blocks)
+    })
+}
+
+/// A terminal field of a PDF's interactive (AcroForm) form
+#[derive(Debug, Clone, PartialEq)]
+struct FormField {
+    /// Fully qualified name: the names of its ancestors and its own, joined with `.`
+    name: String,
+    /// `None` when the field is empty
+    value: Option<String>,
+}
+
+/// Deepest field nesting followed, so a reference cycle can't recurse forever
+const MAX_FORM_DEPTH: usize = 32;
+
+/// Every terminal form field in `doc`, in form order
+fn form_fields(doc: &lopdf::Document) -> Vec<FormField> {
+    let fields = doc
+        .catalog()
+        .and_then(|catalog| catalog.get_deref(b"AcroForm", doc))
+        .and_then(|form| form.as_dict())
+        .and_then(|form| form.get_deref(b"Fields", doc))
+        .and_then(|fields| fields.as_array());
+    let mut found = Vec::new();
+    for field in fields.into_iter().flatten() {
+        collect_form_fields(doc, field, "", None, 0, &mut found);
+    }
+    found
+}
+
+/// Add `field` to `found`, or its named children when it has any
+///
+/// Kids without a `/T` name are the field's widgets (its boxes on the
+/// page), not fields of their own. `/V` is inherited from ancestors.
+fn collect_form_fields<'a>(
+    doc: &'a lopdf::Document,
+    field: &'a lopdf::Object,
+    parent: &str,
+    inherited: Option<&'a lopdf::Object>,
+    depth: usize,
+    found: &mut Vec<FormField>,
+) {
+    if depth > MAX_FORM_DEPTH {
+        return;
+    }
+    let Ok(dict) = doc
+        .dereference(field)
+        .and_then(|(_, field)| field.as_dict())
+    else {
+        return;
+    };
+    let name = match dict
+        .get_deref(b"T", doc)
+        .and_then(lopdf::decode_text_string)
+    {
+        Ok(own) if parent.is_empty() => own,
+        Ok(own) => format!("{}.{}", parent, own),
+        Err(_) => parent.to_string(),
+    };
+    let value = dict.get_deref(b"V", doc).ok().or(inherited);
+
+    let kids = dict
+        .get_deref(b"Kids", doc)
+        .and_then(|kids| kids.as_array());
+    let named_kids: Vec<&lopdf::Object> = kids
+        .into_iter()
+        .flatten()
+        .filter(|kid| {
+            doc.dereference(kid)
+                .and_then(|(_, kid)| kid.as_dict())
+                .is_ok_and(|kid| kid.has(b"T"))
+        })
+        .collect();
+    if named_kids.is_empty() {
+        found.push(FormField {
+            name,
+            value: value.and_then(|value| form_value(doc, value)),
+        });
+    } else {
+        for kid in named_kids {
+            collect_form_fields(doc, kid, &name, value, depth + 1, found);
+        }
+    }
+}
+
+/// A field value as text: strings as written, names (checkbox and radio
+/// states) without the slash, and list selections joined with commas
+fn form_value(doc: &lopdf::Document, value: &lopdf::Object) -> Option<String> {
+    let text = match value {
+        lopdf::Object::String(..) => lopdf::decode_text_string(value).ok()?,
+        lopdf::Object::Name(name) => String::from_utf8_lossy(name).into_owned(),
+        lopdf::Object::Array(items) => items
+            .iter()
+            .filter_map(|item| {
+                doc.dereference(item)
+                    .ok()
+                    .and_then(|(_, item)| form_value(doc, item))
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => return None,
+    };
+    Some(text).filter(|text| !text.trim().is_empty())
+}
+
+/// Hand the filled form fields to `sink` as a final page: a "Form fields"
+/// heading and a table of names and values. Empty fields are left out with
+/// a warning.
+fn write_form_fields(
+    doc: &lopdf::Document,
+    warnings: &mut Vec<String>,
+    sink: &mut dyn PageSink,
+) -> Result<(), ConvertError> {
+    let fields = form_fields(doc);
+    if fields.is_empty() {
+        warnings.push("PDF has no form fields".to_string());
+        return Ok(());
+    }
+
+    let mut rows = Vec::new();
+    for field in fields {
+        match field.value {
+            Some(value) => rows.push(vec![field.name, value]),
+            None => warnings.push(format!("Form field {} is not filled in", field.name)),
+        }
+    }
+    if rows.is_empty() {
+        return Ok(());
+    }
+    sink.write_page(vec![
+        Block::Paragraph("Form fields".to_string()),
+        Block::Table(rows),
+    ])
+}
+
+/// Link rectangle (`[x0, y0, x1, y1]` in user space) and its target URI
+type LinkArea = ([f64; 4], String);
+
+/// URI link annotations on a page; other annotation types are ignored
+fn link_annotations(doc: &lopdf::Document, page_id: lopdf::ObjectId) -> Vec<LinkArea> {
+    let Ok(annotations) = doc.get_page_annotations(page_id) else {
+        return Vec::new();
+    };
+
+    annotations
+        .into_iter()
+        .filter_map(|annot| {
+            if annot.get(b"Subtype").and_then(|s| s.as_name()).ok()? != b"Link" {
+                return None;
+            }
+            let action = annot.get_deref(b"A", doc).and_then(|a| a.as_dict()).ok()?;
+            if action.get(b"S").and_then(|s| s.as_name()).ok()? != b"URI" {
+                return None;
+            }
+            let uri = action
+                .get_deref(b"URI", doc)
+                .and_then(|u| u.as_str())
+                .ok()?;
+            let rect = annot
+                .get_deref(b"Rect", doc)
+                .and_then(|r| r.as_array())
+                .ok()?;
+            let coords: Vec<f64> = rect
+                .iter()
+                .filter_map(|v| v.as_float().ok())
+                .map(f64::from)
+                .collect();
+            let [x0, y0, x1, y1] = coords[..] else {
+                return None;
+            };
+            let area = [x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)];
+            Some((area, String::from_utf8_lossy(uri).into_owned()))
+        })
+        .collect()
+}
+
+/// Split a line into spans, merging neighbouring words that share a link target
+fn link_spans(words: &[Word], links: &[LinkArea]) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+
+    for word in words {
+        let (cx, cy) = word.center();
+        let link = links
+            .iter()
+            .find(|([x0, y0, x1, y1], _)| (*x0..=*x1).contains(&cx) && (*y0..=*y1).contains(&cy))
+            .map(|(_, uri)| uri.clone());
+
+        match spans.last_mut() {
+            Some(last) if last.link == link => {
+                last.text.push(' ');
+                last.text.push_str(&word.text);
+            }
+            Some(last) => {
+                last.text.push(' ');
+                spans.push(Span {
+                    text: word.text.clone(),
+                    link,
+                });
+            }
+            None => spans.push(Span {
+                text: word.text.clone(),
+                link,
+            }),
+        }
+    }
+
+    spans
+}
+
+/// Group non-empty lines into paragraphs, using blank lines as separators
+fn split_paragraphs(page_text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for line in page_text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join("\n"));
+                current.clear();
+            }
+        } else {
+            current.push(trimmed);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n"));
+    }
+
+    paragraphs
+}
+
+/// Read a plain text file, transcoding it to UTF-8 first
+fn read_text(
+    bytes: &[u8],
+    config: &ConvertConfig,
+    warnings: &mut Vec<String>,
+) -> Result<Pages, ConvertError> {
+    let text = decode_text(bytes, config.encoding.as_deref(), warnings)?;
+    Ok(text.split('\x0C').map(text_page).collect())
+}
+
+/// Decode `bytes` using the `forced` encoding label, or a detected one.
+///
+/// A BOM wins over detection; valid UTF-8 is taken as-is, otherwise the
+/// encoding is guessed with `chardetng`. Non-UTF-8 results are noted in `warnings`.
+fn decode_text(
+    bytes: &[u8],
+    forced: Option<&str>,
+    warnings: &mut Vec<String>,
+) -> Result<String, ConvertError> {
+    let encoding = match forced {
+        Some(label) => Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| ConvertError::UnknownEncoding(label.to_string()))?,
+        None => match Encoding::for_bom(bytes) {
+            Some((encoding, _)) => encoding,
+            None if std::str::from_utf8(bytes).is_ok() => UTF_8,
+            None => {
+                let mut detector = EncodingDetector::new();
+                detector.feed(bytes, true);
+                let encoding = detector.guess(None, true);
+                warnings.push(format!(
+                    "Detected encoding: {} (transcoded to UTF-8)",
+                    encoding.name()
+                ));
+                encoding
+            }
+        },
+    };
+
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        warnings.push(format!(
+            "Input contains bytes that are invalid in {}; they were replaced with U+FFFD",
+            encoding.name()
+        ));
+    }
+    Ok(text.into_owned())
+}
+
+/// Parse DOCX file contents into a docx-rs document
+fn parse_docx(bytes: &[u8]) -> Result<Docx, ConvertError> {
+    docx_rs::read_docx(bytes).map_err(|e| ConvertError::DocxReadError(e.to_string()))
+}
+
+/// Paragraph text of a DOCX document, starting a new page at each page break
+fn docx_pages(docx: &Docx) -> Pages {
+    let mut pages = vec![Vec::new()];
+    for child in &docx.document.children {
+        match child {
+            docx_rs::DocumentChild::Paragraph(paragraph) => {
+                let mut text = String::new();
+                for run in paragraph_runs(&paragraph.children) {
+                    for run_child in &run.children {
+                        match run_child {
+                            RunChild::Text(t) => text.push_str(&t.text),
+                            RunChild::Tab(_) => text.push('\t'),
+                            RunChild::Break(br) if *br == Break::new(BreakType::Page) => {
+                                flush_paragraph(&mut pages, &mut text);
+                                pages.push(Vec::new());
+                            }
+                            RunChild::Break(_) => text.push('\n'),
+                            _ => {}
+                        }
+                    }
+                }
+                flush_paragraph(&mut pages, &mut text);
+            }
+            docx_rs::DocumentChild::Table(table) => {
+                if let Some(page) = pages.last_mut() {
+                    page.push(Block::Table(table_text(table)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pages
+}
+
+/// Runs of a paragraph, including those nested in hyperlinks and insertions
+fn paragraph_runs(children: &[ParagraphChild]) -> Vec<&Run> {
+    let mut runs = Vec::new();
+    for child in children {
+        match child {
+            ParagraphChild::Run(run) => runs.push(run.as_ref()),
+            ParagraphChild::Hyperlink(link) => runs.extend(paragraph_runs(&link.children)),
+            ParagraphChild::Insert(insert) => {
+                for c in &insert.children {
+                    if let docx_rs::InsertChild::Run(run) = c {
+                        runs.push(run.as_ref());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    runs
+}
+
+/// Cell text of a DOCX table; paragraphs inside a cell are joined with spaces
+fn table_text(table: &docx_rs::Table) -> Vec<Vec<String>> {
+    table
+        .rows
+        .iter()
+        .map(|docx_rs::TableChild::TableRow(row)| {
+            row.cells
+                .iter()
+                .map(|docx_rs::TableRowChild::TableCell(cell)| {
+                    cell.children
+                        .iter()
+                        .filter_map(|content| match content {
+                            docx_rs::TableCellContent::Paragraph(p) => Some(
+                                paragraph_runs(&p.children)
+                                    .iter()
+                                    .flat_map(|run| &run.children)
+                                    .filter_map(|c| match c {
+                                        RunChild::Text(t) => Some(t.text.as_str()),
+                                        _ => None,
+                                    })
+                                    .collect::<String>(),
+                            ),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn flush_paragraph(pages: &mut Pages, text: &mut String) {
+    let paragraph = std::mem::take(text);
+    if !paragraph.trim().is_empty() {
+        if let Some(page) = pages.last_mut() {
+            page.push(Block::Paragraph(paragraph));
+        }
+    }
+}
+
+/// Plain text of one page: blank lines between paragraphs
+fn page_text(blocks: &[Block]) -> String {
+    let mut text = blocks
+        .iter()
+        .map(Block::text)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+/// Wraps the output sink, adding up the time spent in it when timing is on
+struct TimedSink<'a> {
+    inner: Box<dyn PageSink + 'a>,
+    spent: Option<Duration>,
+}
+
+impl<'a> TimedSink<'a> {
+    fn new(inner: Box<dyn PageSink + 'a>, timing: bool) -> Self {
+        Self {
+            inner,
+            spent: timing.then_some(Duration::ZERO),
+        }
+    }
+
+    /// Finish the output, returning the total time spent in the sink
+    fn finish(self) -> Result<Option<Duration>, ConvertError> {
+        let started = self.spent.map(|spent| (spent, Instant::now()));
+        self.inner.finish()?;
+        Ok(started.map(|(spent, start)| spent + start.elapsed()))
+    }
+}
+
+impl PageSink for TimedSink<'_> {
+    fn write_page(&mut self, blocks: Vec<Block>) -> Result<(), ConvertError> {
+        let start = self.spent.map(|_| Instant::now());
+        let result = self.inner.write_page(blocks);
+        if let (Some(spent), Some(start)) = (&mut self.spent, start) {
+            *spent += start.elapsed();
+        }
+        result
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), ConvertError> {
+        TimedSink::finish(*self).map(|_| ())
+    }
+}
+
+/// Used for dry runs: pages are extracted and dropped
+struct DiscardSink;
+
+impl PageSink for DiscardSink {
+    fn write_page(&mut self, _blocks: Vec<Block>) -> Result<(), ConvertError> {
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), ConvertError> {
+        Ok(())
+    }
+}
+
+/// Streams plain text to the output, separating pages with form feeds
+struct TextSink<W: Write> {
+    writer: BufWriter<W>,
+    pages: usize,
+}
+
+impl<W: Write> TextSink<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+            pages: 0,
+        }
+    }
+}
+
+impl<W: Write> PageSink for TextSink<W> {
+    fn write_page(&mut self, blocks: Vec<Block>) -> Result<(), ConvertError> {
+        if self.pages > 0 {
+            self.writer.write_all(b"\x0C")?;
+        }
+        self.pages += 1;
+        self.writer.write_all(page_text(&blocks).as_bytes())?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), ConvertError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Created/modified timestamp written by deterministic conversions
+const DETERMINISTIC_TIMESTAMP: &str = "1970-01-01T00:00:00Z";
+
+/// Replace docx-rs's process-global paragraph and hyperlink IDs with
+/// sequential ones, so they depend only on the document content
+fn stabilize_ids(docx: &mut Docx) {
+    let mut para_id = 0;
+    let mut link_id = 0;
+    let mut stabilize = |paragraph: &mut Paragraph| {
+        para_id += 1;
+        paragraph.id = format!("{:08X}", para_id);
+        for child in &mut paragraph.children {
+            if let ParagraphChild::Hyperlink(link) = child {
+                if let docx_rs::HyperlinkData::External { rid, .. } = &mut link.link {
+                    link_id += 1;
+                    *rid = format!("rIdHyperlink{}", link_id);
+                }
+            }
+        }
+    };
+
+    for child in &mut docx.document.children {
+        match child {
+            docx_rs::DocumentChild::Paragraph(paragraph) => stabilize(paragraph),
+            docx_rs::DocumentChild::Table(table) => {
+                for docx_rs::TableChild::TableRow(row) in &mut table.rows {
+                    for docx_rs::TableRowChild::TableCell(cell) in &mut row.cells {
+                        for content in &mut cell.children {
+                            if let docx_rs::TableCellContent::Paragraph(paragraph) = content {
+                                stabilize(paragraph);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Adds each page to a DOCX builder as it arrives, packing it into the
+/// output on finish
+struct DocxSink<W: Write> {
+    writer: W,
+    deterministic: bool,
+    style: DocxStyle,
+    docx: Docx,
+    pages: usize,
+}
+
+impl<W: Write> DocxSink<W> {
+    /// Start from `base` when appending, so its content comes first
+    fn new(writer: W, base: Option<Docx>, deterministic: bool, style: &DocxStyle) -> Self {
+        let mut docx = base.unwrap_or_default();
+        // A non-empty base counts as a page, so the first new page starts on a fresh one
+        let pages = usize::from(!docx.document.children.is_empty());
+        if let Some(margin) = style.margin {
+            // Twentieths of a point
+            let twips = (margin * 20.0).round() as i32;
+            docx = docx.page_margin(
+                PageMargin::new()
+                    .top(twips)
+                    .bottom(twips)
+                    .left(twips)
+                    .right(twips),
+            );
+        }
+        Self {
+            writer,
+            deterministic,
+            style: style.clone(),
+            docx,
+            pages,
+        }
+    }
+
+    /// A text run in the configured font and size
+    fn run(&self, text: &str) -> Run {
+        let mut run = Run::new().add_text(text);
+        if let Some(name) = &self.style.font_name {
+            run = run.fonts(
+                RunFonts::new()
+                    .ascii(name)
+                    .hi_ansi(name)
+                    .east_asia(name)
+                    .cs(name),
+            );
+        }
+        if let Some(size) = self.style.font_size_pt {
+            // Half points
+            run = run.size((size * 2.0).round() as usize);
+        }
+        run
+    }
+
+    /// An empty paragraph with the configured spacing; `last` marks the end
+    /// of a block, which gets the space after
+    fn paragraph(&self, last: bool) -> Paragraph {
+        let after = self.style.paragraph_spacing.filter(|_| last);
+        if self.style.line_spacing.is_none() && after.is_none() {
+            return Paragraph::new();
+        }
+        let mut spacing = LineSpacing::new();
+        if let Some(lines) = self.style.line_spacing {
+            // 240ths of a line
+            spacing = spacing
+                .line_rule(LineSpacingType::Auto)
+                .line((lines * 240.0).round() as i32);
+        }
+        if let Some(points) = after {
+            // Twentieths of a point
+            spacing = spacing.after((points * 20.0).round() as u32);
+        }
+        Paragraph::new().line_spacing(spacing)
+    }
+
+    fn add_paragraph(&mut self, paragraph: Paragraph) {
+        self.docx = std::mem::take(&mut self.docx).add_paragraph(paragraph);
+    }
+}
+
+impl<W: Write> PageSink for DocxSink<W> {
+    fn write_page(&mut self, blocks: Vec<Block>) -> Result<(), ConvertError> {
+        // Add page break between pages
+        if self.pages > 0 {
+            self.add_paragraph(Paragraph::new().add_run(Run::new().add_break(BreakType::Page)));
+        }
+        self.pages += 1;
+
+        for block in blocks {
+            match block {
+                // Add paragraph content as DOCX paragraphs (one per line)
+                Block::Paragraph(text) => {
+                    let lines = text.lines().count();
+                    for (i, line) in text.lines().enumerate() {
+                        self.add_paragraph(self.paragraph(i + 1 == lines).add_run(self.run(line)));
+                    }
+                }
+                Block::Line(spans) => {
+                    let mut paragraph = self.paragraph(true);
+                    for span in spans {
+                        let run = self.run(&span.text);
+                        paragraph = match &span.link {
+                            Some(uri) => paragraph.add_hyperlink(
+                                Hyperlink::new(uri, HyperlinkType::External).add_run(run),
+                            ),
+                            None => paragraph.add_run(run),
+                        };
+                    }
+                    self.add_paragraph(paragraph);
+                }
+                Block::Table(rows) => {
+                    let rows = rows
+                        .iter()
+                        .map(|row| {
+                            TableRow::new(
+                                row.iter()
+                                    .map(|cell| {
+                                        TableCell::new().add_paragraph(
+                                            self.paragraph(true).add_run(self.run(cell)),
+                                        )
+                                    })
+                                    .collect(),
+                            )
+                        })
+                        .collect();
+                    self.docx = std::mem::take(&mut self.docx).add_table(Table::new(rows));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), ConvertError> {
+        let DocxSink {
+            mut writer,
+            deterministic,
+            mut docx,
+            ..
+        } = *self;
+        let timestamp = if deterministic {
+            stabilize_ids(&mut docx);
+            DETERMINISTIC_TIMESTAMP.to_string()
+        } else {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            utc_timestamp(now)
+        };
+        docx = docx.created_at(&timestamp).updated_at(&timestamp);
+
+        // The zip writer needs to seek, so pack in memory first
+        let mut packed = std::io::Cursor::new(Vec::new());
+        docx.build()
+            .pack(&mut packed)
+            .map_err(|e| ConvertError::DocxWriteError(e.to_string()))?;
+        writer.write_all(packed.get_ref())?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Document, Object, Stream};
+
+    /// Write a single-page PDF whose page draws `content` with Helvetica as
+    /// /F1 and a 1x1 gray image as /Im1 available as resources
+    fn write_pdf(path: &Path, content: &str) {
+        write_pdf_with_annots(path, content, Vec::new());
+    }
+
+    /// Pages of text in a DOCX file
+    fn read_docx(path: &Path) -> Pages {
+        docx_pages(&parse_docx(&std::fs::read(path).unwrap()).unwrap())
+    }
+
+    /// Like [`write_pdf`], attaching `annots` to the page
+    fn write_pdf_with_annots(path: &Path, content: &str, annots: Vec<lopdf::Dictionary>) {
+        write_pdf_pages(path, vec![(content, annots)]);
+    }
+
+    /// Write a PDF with one page per `(content, annots)` entry
+    fn write_pdf_pages(path: &Path, pages: Vec<(&str, Vec<lopdf::Dictionary>)>) {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let image_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 1,
+                "Height" => 1,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            vec![0x80],
+        ));
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+            "XObject" => dictionary! { "Im1" => image_id },
+        });
+        let mut kids: Vec<Object> = Vec::new();
+        for (content, annots) in pages {
+            let content_id =
+                doc.add_object(Stream::new(dictionary! {}, content.as_bytes().to_vec()));
+            let annot_refs: Vec<Object> = annots
+                .into_iter()
+                .map(|a| doc.add_object(a).into())
+                .collect();
+            let page_id = doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+                "Annots" => annot_refs,
+            });
+            kids.push(page_id.into());
+        }
+        let count = kids.len() as i64;
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => count,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.save(path).unwrap();
+    }
+
+    const TEXT_CONTENT: &str = "BT /F1 12 Tf 72 720 Td (Hello PDF) Tj ET";
+    const IMAGE_CONTENT: &str = "q 200 0 0 200 72 500 cm /Im1 Do Q";
+
+    #[test]
+    fn test_format_display() {
+        assert_eq!(format!("{}", Format::Pdf), "PDF");
+        assert_eq!(format!("{}", Format::Docx), "DOCX");
+        assert_eq!(format!("{}", Format::Txt), "TXT");
+        assert_eq!(format!("{}", Format::Html), "HTML");
+    }
+
+    #[test]
+    fn test_unsupported_conversion() {
+        let config = ConvertConfig {
+            input_path: PathBuf::from("test.docx"),
+            output_path: PathBuf::from("test.pdf"),
+            from_format: Format::Docx,
+            to_format: Format::Pdf,
+            force: false,
+            verbose: false,
+            ..Default::default()
+        };
+
+        let result = convert(&config);
+        assert!(matches!(
+            result,
+            Err(ConvertError::UnsupportedConversion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_input_not_found() {
+        let config = ConvertConfig {
+            input_path: PathBuf::from("/nonexistent/path/file.pdf"),
+            output_path: PathBuf::from("output.docx"),
+            from_format: Format::Pdf,
+            to_format: Format::Docx,
+            force: false,
+            verbose: false,
+            ..Default::default()
+        };
+
+        let result = convert(&config);
+        assert!(matches!(result, Err(ConvertError::InputNotFound(_))));
+    }
+
+    #[test]
+    fn test_max_input_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("big.txt");
+        let output = dir.path().join("big.docx");
+        std::fs::write(&input, "x".repeat(2048)).unwrap();
+
+        let mut config = ConvertConfig {
+            input_path: input.clone(),
+            output_path: output.clone(),
+            from_format: Format::Txt,
+            to_format: Format::Docx,
+            max_input_bytes: Some(1024),
+            ..Default::default()
+        };
+        let err = convert(&config).unwrap_err();
+        assert!(matches!(
+            err,
+            ConvertError::InputTooLarge {
+                size: 2048,
+                limit: 1024
+            }
+        ));
+        assert!(err.to_string().contains("1024-byte limit"), "{}", err);
+        assert!(!output.exists());
+
+        // Streams stop being read just past the limit
+        let text = std::fs::read(&input).unwrap();
+        let result = convert_stream(
+            text.as_slice(),
+            Vec::new(),
+            Format::Txt,
+            Format::Docx,
+            &config,
+        );
+        assert!(matches!(
+            result,
+            Err(ConvertError::InputTooLarge {
+                size: 1025,
+                limit: 1024
+            })
+        ));
+
+        config.max_input_bytes = Some(2048);
+        assert_eq!(convert(&config).unwrap().pages_processed, 1);
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = ConvertError::InputNotFound(PathBuf::from("test.pdf"));
+        assert!(err.to_string().contains("test.pdf"));
+
+        let err = ConvertError::UnsupportedConversion {
+            from: Format::Docx,
+            to: Format::Pdf,
+        };
+        assert!(err.to_string().contains("DOCX"));
+        assert!(err.to_string().contains("PDF"));
+    }
+
+    #[test]
+    fn test_rejects_exactly_unsupported_pairs() {
+        let dir = tempfile::tempdir().unwrap();
+        let formats = [Format::Pdf, Format::Docx, Format::Txt, Format::Html];
+        for from in formats {
+            for to in formats {
+                // A missing input is only reported once the pair is accepted
+                let config = ConvertConfig {
+                    input_path: dir.path().join("missing"),
+                    output_path: dir.path().join("out"),
+                    from_format: from,
+                    to_format: to,
+                    ..Default::default()
+                };
+                let unsupported = matches!(
+                    convert(&config),
+                    Err(ConvertError::UnsupportedConversion { .. })
+                );
+                assert_eq!(
+                    unsupported,
+                    !supported_conversions().contains(&(from, to)),
+                    "{} to {}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_pdf_has_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let text_pdf = dir.path().join("text.pdf");
+        let image_pdf = dir.path().join("scan.pdf");
+        write_pdf(&text_pdf, TEXT_CONTENT);
+        write_pdf(&image_pdf, IMAGE_CONTENT);
+
+        assert!(pdf_has_text(&text_pdf).unwrap());
+        assert!(!pdf_has_text(&image_pdf).unwrap());
+    }
+
+    #[test]
+    fn test_require_text_fails_fast() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("scan.pdf");
+        let output = dir.path().join("scan.docx");
+        write_pdf(&input, IMAGE_CONTENT);
+
+        let config = ConvertConfig {
+            input_path: input.clone(),
+            output_path: output.clone(),
+            require_text: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            convert(&config),
+            Err(ConvertError::NoExtractableText(_))
+        ));
+        assert!(!output.exists());
+
+        let config = ConvertConfig {
+            require_text: false,
+            ..config
+        };
+        let result = convert(&config).unwrap();
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("image-based"));
+    }
+
+    #[test]
+    fn test_timing_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("text.pdf");
+        write_pdf(&input, TEXT_CONTENT);
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: dir.path().join("untimed.docx"),
+            ..Default::default()
+        };
+        let result = convert(&config).unwrap();
+        assert_eq!((result.extract_ms, result.build_ms), (None, None));
+
+        let config = ConvertConfig {
+            output_path: dir.path().join("timed.docx"),
+            timing: true,
+            ..config
+        };
+        let result = convert(&config).unwrap();
+        assert!(result.extract_ms.is_some());
+        assert!(result.build_ms.is_some());
+    }
+
+    #[test]
+    fn test_docx_to_txt() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("notes.docx");
+        let output = dir.path().join("notes.txt");
+
+        let file = File::create(&input).unwrap();
+        Docx::new()
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("First paragraph")))
+            .add_paragraph(
+                Paragraph::new()
+                    .add_run(Run::new().add_text("Second "))
+                    .add_run(Run::new().add_text("paragraph")),
+            )
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_break(BreakType::Page)))
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Next page")))
+            .build()
+            .pack(file)
+            .unwrap();
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            from_format: Format::Docx,
+            to_format: Format::Txt,
+            ..Default::default()
+        };
+        let result = convert(&config).unwrap();
+
+        assert_eq!(result.pages_processed, 2);
+        assert_eq!(
+            std::fs::read_to_string(&output).unwrap(),
+            "First paragraph\n\nSecond paragraph\n\x0CNext page\n"
+        );
+    }
+
+    #[test]
+    fn test_pdf_to_txt() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("hello.pdf");
+        let output = dir.path().join("hello.txt");
+        write_pdf(&input, TEXT_CONTENT);
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            to_format: Format::Txt,
+            ..Default::default()
+        };
+        let result = convert(&config).unwrap();
+
+        assert!(result.warnings.is_empty());
+        assert!(std::fs::read_to_string(&output)
+            .unwrap()
+            .contains("Hello PDF"));
+    }
+
+    #[test]
+    fn test_split_paragraphs() {
+        let paragraphs = split_paragraphs("  one\ntwo  \n\n\nthree\n");
+        assert_eq!(paragraphs, vec!["one\ntwo", "three"]);
+    }
+
+    #[test]
+    fn test_dry_run_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("hello.pdf");
+        let output = dir.path().join("hello.docx");
+        write_pdf(&input, TEXT_CONTENT);
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            dry_run: true,
+            ..Default::default()
+        };
+        let result = convert(&config).unwrap();
+
+        assert_eq!(result.pages_processed, 1);
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn test_decode_windows_1252() {
+        // “Smart quotes” and café in Windows-1252
+        let bytes = b"\x93Smart quotes\x94 at the caf\xe9, don\x92t you think?";
+        let mut warnings = Vec::new();
+        let text = decode_text(bytes, None, &mut warnings).unwrap();
+
+        assert_eq!(
+            text,
+            "\u{201C}Smart quotes\u{201D} at the caf\u{e9}, don\u{2019}t you think?"
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("windows-1252"));
+    }
+
+    #[test]
+    fn test_decode_utf8_and_forced() {
+        let mut warnings = Vec::new();
+        assert_eq!(
+            decode_text("caf\u{e9}".as_bytes(), None, &mut warnings).unwrap(),
+            "caf\u{e9}"
+        );
+        assert!(warnings.is_empty());
+
+        // Forcing Latin-1 reinterprets the UTF-8 bytes
+        let text = decode_text("caf\u{e9}".as_bytes(), Some("latin1"), &mut warnings).unwrap();
+        assert_eq!(text, "caf\u{c3}\u{a9}");
+
+        assert!(matches!(
+            decode_text(b"abc", Some("klingon"), &mut warnings),
+            Err(ConvertError::UnknownEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_txt_to_docx() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("legacy.txt");
+        let output = dir.path().join("legacy.docx");
+        std::fs::write(&input, b"\x93Quoted\x94 line\n\nSecond paragraph\n").unwrap();
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            from_format: Format::Txt,
+            to_format: Format::Docx,
+            ..Default::default()
+        };
+        let result = convert(&config).unwrap();
+        assert_eq!(result.pages_processed, 1);
+
+        let docx = docx_rs::read_docx(&std::fs::read(&output).unwrap()).unwrap();
+        let pages = read_docx(&output);
+        assert_eq!(docx.document.children.len(), 2);
+        assert_eq!(
+            pages,
+            vec![vec![
+                Block::Paragraph("\u{201C}Quoted\u{201D} line".to_string()),
+                Block::Paragraph("Second paragraph".to_string()),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_docx_style() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("report.txt");
+        let output = dir.path().join("report.docx");
+        std::fs::write(&input, "Quarterly report\n").unwrap();
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            from_format: Format::Txt,
+            to_format: Format::Docx,
+            docx_style: DocxStyle {
+                font_name: Some("Arial".to_string()),
+                font_size_pt: Some(11.0),
+                margin: Some(36.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        convert(&config).unwrap();
+
+        let docx = docx_rs::read_docx(&std::fs::read(&output).unwrap()).unwrap();
+        let docx_rs::DocumentChild::Paragraph(paragraph) = &docx.document.children[0] else {
+            panic!("expected a paragraph");
+        };
+        let run = paragraph_runs(&paragraph.children)[0];
+        assert_eq!(run.run_property.sz, Some(docx_rs::Sz::new(22)));
+        assert_eq!(
+            run.run_property.fonts,
+            Some(
+                RunFonts::new()
+                    .ascii("Arial")
+                    .hi_ansi("Arial")
+                    .east_asia("Arial")
+                    .cs("Arial")
+            )
+        );
+        assert_eq!(docx.document.section_property.page_margin.left, 720);
+    }
+
+    #[test]
+    fn test_docx_spacing() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("essay.txt");
+        let output = dir.path().join("essay.docx");
+        std::fs::write(&input, "First line\nsecond line\n\nNext paragraph\n").unwrap();
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            from_format: Format::Txt,
+            to_format: Format::Docx,
+            docx_style: DocxStyle {
+                line_spacing: Some(1.5),
+                paragraph_spacing: Some(12.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        convert(&config).unwrap();
+
+        let docx = docx_rs::read_docx(&std::fs::read(&output).unwrap()).unwrap();
+        let spacing: Vec<_> = docx
+            .document
+            .children
+            .iter()
+            .map(|child| match child {
+                docx_rs::DocumentChild::Paragraph(paragraph) => {
+                    paragraph.property.line_spacing.clone()
+                }
+                other => panic!("expected a paragraph, got {:?}", other),
+            })
+            .collect();
+        let line = LineSpacing::new()
+            .line_rule(LineSpacingType::Auto)
+            .line(360);
+        // Space after only ends a paragraph, not each of its lines
+        assert_eq!(
+            spacing,
+            [
+                Some(line.clone()),
+                Some(line.clone().after(240)),
+                Some(line.after(240))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_docx_style_validation() {
+        assert!(DocxStyle::default().validate().is_ok());
+        for style in [
+            DocxStyle {
+                font_size_pt: Some(2.0),
+                ..Default::default()
+            },
+            DocxStyle {
+                font_size_pt: Some(200.0),
+                ..Default::default()
+            },
+            DocxStyle {
+                font_size_pt: Some(f32::NAN),
+                ..Default::default()
+            },
+            DocxStyle {
+                font_name: Some(" ".to_string()),
+                ..Default::default()
+            },
+            DocxStyle {
+                margin: Some(-1.0),
+                ..Default::default()
+            },
+            DocxStyle {
+                line_spacing: Some(0.0),
+                ..Default::default()
+            },
+            DocxStyle {
+                line_spacing: Some(-1.5),
+                ..Default::default()
+            },
+            DocxStyle {
+                line_spacing: Some(f32::NAN),
+                ..Default::default()
+            },
+            DocxStyle {
+                paragraph_spacing: Some(-6.0),
+                ..Default::default()
+            },
+        ] {
+            assert!(
+                matches!(style.validate(), Err(ConvertError::InvalidStyle(_))),
+                "{:?}",
+                style
+            );
+        }
+
+        let config = ConvertConfig {
+            docx_style: DocxStyle {
+                font_size_pt: Some(0.5),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(matches!(
+            convert(&config),
+            Err(ConvertError::InvalidStyle(_))
+        ));
+    }
+
+    #[test]
+    fn test_pdf_hyperlink_to_docx() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("links.pdf");
+        let output = dir.path().join("links.docx");
+        let link = dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => vec![150.into(), 715.into(), 260.into(), 735.into()],
+            "A" => dictionary! {
+                "S" => "URI",
+                "URI" => Object::string_literal("https://example.com/"),
+            },
+        };
+        write_pdf_with_annots(
+            &input,
+            "BT /F1 12 Tf 72 720 Td (Visit) Tj 80 0 Td (example.com) Tj ET",
+            vec![link],
+        );
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            preserve_formatting: true,
+            ..Default::default()
+        };
+        convert(&config).unwrap();
+
+        let docx = docx_rs::read_docx(&std::fs::read(&output).unwrap()).unwrap();
+        assert!(docx
+            .hyperlinks
+            .iter()
+            .any(|(_, target, _)| target == "https://example.com/"));
+
+        let docx_rs::DocumentChild::Paragraph(paragraph) = &docx.document.children[0] else {
+            panic!("expected a paragraph");
+        };
+        let linked: Vec<&Run> = paragraph
+            .children
+            .iter()
+            .filter_map(|c| match c {
+                ParagraphChild::Hyperlink(h) => Some(paragraph_runs(&h.children)),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        assert_eq!(linked.len(), 1);
+        assert!(matches!(&linked[0].children[0], RunChild::Text(t) if t.text == "example.com"));
+
+        // Linked runs still read back as ordinary text
+        let pages = read_docx(&output);
+        assert_eq!(pages[0][0].text(), "Visit example.com");
+    }
+
+    #[test]
+    fn test_pdf_table_to_docx() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("prices.pdf");
+        let output = dir.path().join("prices.docx");
+        write_pdf(
+            &input,
+            "BT /F1 12 Tf 72 760 Td (Price list) Tj \
+             0 -40 Td (Item) Tj 228 0 Td (Price) Tj \
+             -228 -20 Td (Apple) Tj 228 0 Td (1.00) Tj \
+             -228 -20 Td (Pear) Tj 228 0 Td (12.50) Tj ET",
+        );
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            detect_tables: true,
+            ..Default::default()
+        };
+        let result = convert(&config).unwrap();
+        assert!(result.warnings.is_empty());
+
+        let pages = read_docx(&output);
+        assert_eq!(pages[0][0], Block::Paragraph("Price list".to_string()));
+        let Block::Table(rows) = &pages[0][1] else {
+            panic!("expected a table, got {:?}", pages[0][1]);
+        };
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.len() == 2));
+        assert_eq!(rows[2], vec!["Pear", "12.50"]);
+    }
+
+    #[test]
+    fn test_html_to_pdf() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("report.html");
+        let output = dir.path().join("report.pdf");
+        std::fs::write(
+            &input,
+            "<h1>Quarterly Report</h1>\
+             <p>Revenue grew in <b>every</b> region.</p>\
+             <ul><li>North</li><li>South</li></ul>",
+        )
+        .unwrap();
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            from_format: Format::Html,
+            to_format: Format::Pdf,
+            ..Default::default()
+        };
+        let result = convert(&config).unwrap();
+        assert_eq!(result.pages_processed, 1);
+
+        let text = pdf_extract::extract_text(&output).unwrap();
+        for expected in ["Quarterly", "Revenue", "every", "North", "South"] {
+            assert!(
+                text.contains(expected),
+                "missing {:?} in {:?}",
+                expected,
+                text
+            );
+        }
+        // Heading, paragraph and both list items land on separate lines
+        let layout =
+            layout::extract_page_layout(&lopdf::Document::load(&output).unwrap(), 1).unwrap();
+        assert!(layout.lines.len() >= 4);
+    }
+
+    #[test]
+    fn test_html_invalid_base_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("page.html");
+        std::fs::write(&input, "<p>hi</p>").unwrap();
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: dir.path().join("page.pdf"),
+            from_format: Format::Html,
+            to_format: Format::Pdf,
+            base_url: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            convert(&config),
+            Err(ConvertError::InvalidBaseUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_append_to_existing_docx() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("report.docx");
+        let input = dir.path().join("appendix.pdf");
+        let output = dir.path().join("combined.docx");
+
+        let file = File::create(&existing).unwrap();
+        Docx::new()
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Cover letter")))
+            .build()
+            .pack(file)
+            .unwrap();
+        write_pdf(&input, "BT /F1 12 Tf 72 720 Td (Appendix text) Tj ET");
+
+        let config = ConvertConfig {
+            input_path: input.clone(),
+            output_path: output.clone(),
+            append: Some(existing.clone()),
+            ..Default::default()
+        };
+        convert(&config).unwrap();
+
+        // The existing paragraph, then a page break, then the converted PDF
+        let pages = read_docx(&output);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0], vec![Block::Paragraph("Cover letter".to_string())]);
+        assert!(page_text(&pages[1]).contains("Appendix text"));
+
+        // Only DOCX output can be appended to
+        let config = ConvertConfig {
+            to_format: Format::Txt,
+            output_path: dir.path().join("combined.txt"),
+            ..config
+        };
+        assert!(matches!(
+            convert(&config),
+            Err(ConvertError::AppendRequiresDocx(Format::Txt))
+        ));
+    }
+
+    #[test]
+    fn test_pdf_form_fields_to_docx() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("form.pdf");
+        let output = dir.path().join("form.docx");
+        write_pdf(&input, "BT /F1 12 Tf 72 720 Td (Application form) Tj ET");
+
+        // One filled text field and one left empty
+        let mut doc = Document::load(&input).unwrap();
+        let name = doc.add_object(dictionary! {
+            "FT" => "Tx",
+            "T" => Object::string_literal("Name"),
+            "V" => Object::string_literal("Ada Lovelace"),
+        });
+        let email = doc.add_object(dictionary! {
+            "FT" => "Tx",
+            "T" => Object::string_literal("Email"),
+        });
+        let form = doc.add_object(dictionary! { "Fields" => vec![name.into(), email.into()] });
+        doc.catalog_mut().unwrap().set("AcroForm", form);
+        doc.save(&input).unwrap();
+
+        let config = ConvertConfig {
+            input_path: input.clone(),
+            output_path: output.clone(),
+            include_form_fields: true,
+            ..Default::default()
+        };
+        let result = convert(&config).unwrap();
+        assert_eq!(result.pages_processed, 1);
+        assert_eq!(result.warnings, ["Form field Email is not filled in"]);
+
+        let pages = read_docx(&output);
+        assert_eq!(pages.len(), 2);
+        assert!(page_text(&pages[0]).contains("Application form"));
+        assert_eq!(
+            pages[1],
+            vec![
+                Block::Paragraph("Form fields".to_string()),
+                Block::Table(vec![vec!["Name".to_string(), "Ada Lovelace".to_string()]]),
+            ]
+        );
+
+        // Without the flag the values are dropped as before
+        let config = ConvertConfig {
+            output_path: dir.path().join("plain.docx"),
+            include_form_fields: false,
+            ..config
+        };
+        convert(&config).unwrap();
+        assert_eq!(read_docx(&dir.path().join("plain.docx")).len(), 1);
+    }
+
+    #[test]
+    fn test_convert_stream_in_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("memo.pdf");
+        write_pdf(&input, TEXT_CONTENT);
+        let pdf = std::fs::read(&input).unwrap();
+
+        let mut docx = Vec::new();
+        let result = convert_stream(
+            pdf.as_slice(),
+            &mut docx,
+            Format::Pdf,
+            Format::Docx,
+            &ConvertConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(result.pages_processed, 1);
+
+        // Same document as the path-based conversion
+        let output = dir.path().join("memo.docx");
+        std::fs::write(&output, &docx).unwrap();
+        let expected = dir.path().join("expected.docx");
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: expected.clone(),
+            ..Default::default()
+        };
+        convert(&config).unwrap();
+        assert_eq!(read_docx(&output), read_docx(&expected));
+        assert!(page_text(&read_docx(&output)[0]).contains("Hello"));
+
+        // Unsupported pairs fail before reading anything
+        let result = convert_stream(
+            std::io::empty(),
+            Vec::new(),
+            Format::Txt,
+            Format::Pdf,
+            &ConvertConfig::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(ConvertError::UnsupportedConversion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_strict_fails_on_warnings() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("empty.html");
+        let output = dir.path().join("empty.pdf");
+        std::fs::write(&input, "<script>track()</script>").unwrap();
+
+        let config = ConvertConfig {
+            input_path: input,
+            output_path: output.clone(),
+            from_format: Format::Html,
+            to_format: Format::Pdf,
+            ..Default::default()
+        };
+        let result = convert(&config).unwrap();
+        assert_eq!(
+            result.warnings,
+            ["HTML document contains no renderable text"]
+        );
+        std::fs::remove_file(&output).unwrap();
+
+        let strict = ConvertConfig {
+            strict: true,
+            ..config
+        };
+        let err = convert(&strict).unwrap_err();
+        assert!(
+            matches!(&err, ConvertError::WarningAsError(msg) if msg.contains("no renderable text"))
+        );
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn test_deterministic_docx() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("links.pdf");
+        let link = dictionary! {
+            "Subtype" => "Link",
+            "Rect" => vec![150.into(), 715.into(), 260.into(), 735.into()],
+            "A" => dictionary! { "S" => "URI", "URI" => Object::string_literal("https://example.com/") },
+        };
+        write_pdf_with_annots(
+            &input,
+            "BT /F1 12 Tf 72 720 Td (Visit) Tj 80 0 Td (example.com) Tj 0 -20 Td (Thanks) Tj ET",
+            vec![link],
+        );
+
+        let outputs: Vec<Vec<u8>> = ["first.docx", "second.docx"]
+            .iter()
+            .map(|name| {
+                let output = dir.path().join(name);
+                let config = ConvertConfig {
+                    input_path: input.clone(),
+                    output_path: output.clone(),
+                    preserve_formatting: true,
+                    deterministic: true,
+                    ..Default::default()
+                };
+                convert(&config).unwrap();
+                std::fs::read(&output).unwrap()
+            })
+            .collect();
+
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    /// Records the first line of every page it receives
+    #[derive(Default)]
+    struct Recorder {
+        pages: Vec<String>,
+    }
+
+    impl PageSink for Recorder {
+        fn write_page(&mut self, blocks: Vec<Block>) -> Result<(), ConvertError> {
+            self.pages
+                .push(blocks.first().map(Block::text).unwrap_or_default());
+            Ok(())
+        }
+
+        fn finish(self: Box<Self>) -> Result<(), ConvertError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_pdf_pages_streamed_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("three.pdf");
+        let contents: Vec<String> = (1..=3)
+            .map(|n| format!("BT /F1 12 Tf 72 720 Td (Page {}) Tj ET", n))
+            .collect();
+        write_pdf_pages(
+            &input,
+            contents.iter().map(|c| (c.as_str(), Vec::new())).collect(),
+        );
+
+        let config = ConvertConfig {
+            input_path: input.clone(),
+            ..Default::default()
+        };
+        let mut recorder = Recorder::default();
+        let mut warnings = Vec::new();
+        let doc = load_pdf(&std::fs::read(&input).unwrap()).unwrap();
+        let pages = extract_pdf(&doc, &config, &mut warnings, &mut recorder).unwrap();
+
+        assert_eq!(pages, 3);
+        assert_eq!(recorder.pages, vec!["Page 1", "Page 2", "Page 3"]);
+
+        // The same page boundaries reach the text output as form feeds
+        let output = dir.path().join("three.txt");
+        let config = ConvertConfig {
+            output_path: output.clone(),
+            to_format: Format::Txt,
+            ..config
+        };
+        assert_eq!(convert(&config).unwrap().pages_processed, 3);
+        assert_eq!(
+            std::fs::read_to_string(&output)
+                .unwrap()
+                .matches('\x0C')
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_failed_page_is_skipped_with_warning() {
+        let mut recorder = Recorder::default();
+        let mut warnings = Vec::new();
+        let pages =
+            write_extracted_pages(&[1, 2, 3], &mut warnings, &mut recorder, |page_num, _| {
+                if page_num == 2 {
+                    return Err(ConvertError::PdfReadError("bad content stream".to_string()));
+                }
+                Ok(text_page(&format!("Page {}", page_num)))
+            })
+            .unwrap();
+
+        assert_eq!(pages, 2);
+        assert_eq!(recorder.pages, vec!["Page 1", "Page 3"]);
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0].starts_with("Page 2: skipped"),
+            "{}",
+            warnings[0]
+        );
+        assert!(warnings[0].contains("bad content stream"));
+    }
+
+    #[test]
+    fn test_all_pages_failing_is_fatal() {
+        let mut recorder = Recorder::default();
+        let mut warnings = Vec::new();
+        let result = write_extracted_pages(&[1, 2], &mut warnings, &mut recorder, |page_num, _| {
+            Err(ConvertError::PdfReadError(format!(
+                "page {} unreadable",
+                page_num
+            )))
+        });
+
+        assert!(
+            matches!(result, Err(ConvertError::PdfReadError(msg)) if msg == "page 1 unreadable")
+        );
+        assert!(recorder.pages.is_empty());
     }
 }
@@ -0,0 +1,412 @@
+//! Basic HTML to PDF rendering.
+//!
+//! Only document structure is honoured: headings, paragraphs, (nested) lists,
+//! bold/italic and links. CSS, scripts and images are ignored. Text is laid
+//! out with the built-in Helvetica faces, so no fonts need to be embedded.
+
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+};
+use scraper::{Html, Node};
+use url::Url;
+
+/// A run of text sharing one style
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TextRun {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BlockKind {
+    Heading(u8),
+    Paragraph,
+    /// List item at `depth` (1 = top level) with its bullet or number
+    ListItem {
+        depth: usize,
+        number: Option<usize>,
+    },
+}
+
+/// A block-level element flattened to styled runs
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HtmlBlock {
+    pub kind: BlockKind,
+    pub runs: Vec<TextRun>,
+}
+
+impl HtmlBlock {
+    #[cfg(test)]
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|r| r.text.as_str()).collect()
+    }
+}
+
+/// Parse `html` into blocks; link targets are appended to the link text,
+/// resolved against `base_url` when they are relative
+pub(crate) fn parse_html(html: &str, base_url: Option<&Url>) -> Vec<HtmlBlock> {
+    let document = Html::parse_document(html);
+    let mut walker = Walker {
+        base_url,
+        blocks: Vec::new(),
+        runs: Vec::new(),
+        kind: BlockKind::Paragraph,
+        lists: Vec::new(),
+    };
+    walker.walk(*document.root_element(), false, false);
+    walker.flush();
+    walker.blocks
+}
+
+struct Walker<'a> {
+    base_url: Option<&'a Url>,
+    blocks: Vec<HtmlBlock>,
+    runs: Vec<TextRun>,
+    kind: BlockKind,
+    /// Open lists, innermost last: `Some(count)` for `<ol>`, `None` for `<ul>`
+    lists: Vec<Option<usize>>,
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, node: ego_tree::NodeRef<'_, Node>, bold: bool, italic: bool) {
+        match node.value() {
+            Node::Text(text) => self.push_text(text, bold, italic),
+            Node::Element(element) => {
+                let name = element.name();
+                match name {
+                    "head" | "script" | "style" | "template" | "noscript" => {}
+                    "br" => self.flush(),
+                    "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                        let level = name.as_bytes()[1] - b'0';
+                        self.block(node, BlockKind::Heading(level), bold, italic);
+                    }
+                    "p" | "div" | "section" | "article" | "blockquote" | "pre" | "header"
+                    | "footer" => {
+                        self.block(node, BlockKind::Paragraph, bold, italic);
+                    }
+                    "ul" | "ol" => {
+                        self.flush();
+                        self.lists.push(if name == "ol" { Some(0) } else { None });
+                        self.children(node, bold, italic);
+                        self.flush();
+                        self.lists.pop();
+                    }
+                    "li" => {
+                        let number = match self.lists.last_mut() {
+                            Some(Some(count)) => {
+                                *count += 1;
+                                Some(*count)
+                            }
+                            _ => None,
+                        };
+                        let depth = self.lists.len().max(1);
+                        self.block(node, BlockKind::ListItem { depth, number }, bold, italic);
+                    }
+                    "b" | "strong" => self.children(node, true, italic),
+                    "i" | "em" => self.children(node, bold, true),
+                    "a" => {
+                        let before = self.current_text();
+                        self.children(node, bold, italic);
+                        if let Some(href) = element.attr("href") {
+                            let target = self.resolve(href);
+                            let label = self.current_text()[before.len()..].trim().to_string();
+                            // Skip the target when the link text already shows it
+                            let shown = label.trim_end_matches('/') == target.trim_end_matches('/');
+                            if !target.is_empty() && !shown {
+                                self.push_text(&format!(" ({})", target), bold, italic);
+                            }
+                        }
+                    }
+                    _ => self.children(node, bold, italic),
+                }
+            }
+            _ => self.children(node, bold, italic),
+        }
+    }
+
+    fn children(&mut self, node: ego_tree::NodeRef<'_, Node>, bold: bool, italic: bool) {
+        for child in node.children() {
+            self.walk(child, bold, italic);
+        }
+    }
+
+    /// Walk `node` as its own block of `kind`, restoring the outer kind afterwards
+    fn block(
+        &mut self,
+        node: ego_tree::NodeRef<'_, Node>,
+        kind: BlockKind,
+        bold: bool,
+        italic: bool,
+    ) {
+        self.flush();
+        let outer = std::mem::replace(&mut self.kind, kind);
+        self.children(node, bold, italic);
+        self.flush();
+        self.kind = outer;
+    }
+
+    fn resolve(&self, href: &str) -> String {
+        match self.base_url {
+            Some(base) => base
+                .join(href)
+                .map(String::from)
+                .unwrap_or_else(|_| href.to_string()),
+            None => href.to_string(),
+        }
+    }
+
+    fn current_text(&self) -> String {
+        self.runs.iter().map(|r| r.text.as_str()).collect()
+    }
+
+    /// Append text with HTML whitespace collapsing
+    fn push_text(&mut self, text: &str, bold: bool, italic: bool) {
+        let mut collapsed = String::new();
+        let mut last_space = self.runs.last().is_none_or(|r| r.text.ends_with(' '));
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !last_space {
+                    collapsed.push(' ');
+                }
+                last_space = true;
+            } else {
+                collapsed.push(c);
+                last_space = false;
+            }
+        }
+        if collapsed.is_empty() {
+            return;
+        }
+
+        match self.runs.last_mut() {
+            Some(run) if run.bold == bold && run.italic == italic => run.text.push_str(&collapsed),
+            _ => self.runs.push(TextRun {
+                text: collapsed,
+                bold,
+                italic,
+            }),
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(last) = self.runs.last_mut() {
+            let trimmed = last.text.trim_end().len();
+            last.text.truncate(trimmed);
+        }
+        self.runs.retain(|r| !r.text.is_empty());
+        if !self.runs.is_empty() {
+            self.blocks.push(HtmlBlock {
+                kind: self.kind,
+                runs: std::mem::take(&mut self.runs),
+            });
+        }
+    }
+}
+
+const PAGE_WIDTH_MM: f32 = 210.0;
+const PAGE_HEIGHT_MM: f32 = 297.0;
+const MARGIN_MM: f32 = 20.0;
+const LIST_INDENT_MM: f32 = 6.0;
+const PT_TO_MM: f32 = 0.3528;
+/// Rough average Helvetica glyph width in ems, used for line wrapping
+const AVG_GLYPH_EMS: f32 = 0.5;
+const BODY_SIZE_PT: f32 = 11.0;
+
+fn font_size(kind: BlockKind) -> f32 {
+    match kind {
+        BlockKind::Heading(1) => 22.0,
+        BlockKind::Heading(2) => 18.0,
+        BlockKind::Heading(3) => 15.0,
+        BlockKind::Heading(_) => 13.0,
+        _ => BODY_SIZE_PT,
+    }
+}
+
+fn text_width_mm(text: &str, size: f32, bold: bool) -> f32 {
+    let ems = if bold {
+        AVG_GLYPH_EMS * 1.1
+    } else {
+        AVG_GLYPH_EMS
+    };
+    text.chars().count() as f32 * size * ems * PT_TO_MM
+}
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    italic: IndirectFontRef,
+    bold_italic: IndirectFontRef,
+}
+
+impl Fonts {
+    fn get(&self, bold: bool, italic: bool) -> &IndirectFontRef {
+        match (bold, italic) {
+            (false, false) => &self.regular,
+            (true, false) => &self.bold,
+            (false, true) => &self.italic,
+            (true, true) => &self.bold_italic,
+        }
+    }
+}
+
+/// Lay out `blocks` on A4 pages, returning the document and its page count
+pub(crate) fn render_pdf(
+    blocks: &[HtmlBlock],
+    title: &str,
+) -> Result<(PdfDocumentReference, usize), printpdf::Error> {
+    let (doc, page, layer) =
+        PdfDocument::new(title, Mm(PAGE_WIDTH_MM), Mm(PAGE_HEIGHT_MM), "Layer 1");
+    let fonts = Fonts {
+        regular: doc.add_builtin_font(BuiltinFont::Helvetica)?,
+        bold: doc.add_builtin_font(BuiltinFont::HelveticaBold)?,
+        italic: doc.add_builtin_font(BuiltinFont::HelveticaOblique)?,
+        bold_italic: doc.add_builtin_font(BuiltinFont::HelveticaBoldOblique)?,
+    };
+
+    let mut cursor = Cursor {
+        doc: &doc,
+        layer: doc.get_page(page).get_layer(layer),
+        y: PAGE_HEIGHT_MM - MARGIN_MM,
+        pages: 1,
+    };
+
+    for block in blocks {
+        let size = font_size(block.kind);
+        let line_height = size * 1.4 * PT_TO_MM;
+        let heading = matches!(block.kind, BlockKind::Heading(_));
+        if heading {
+            cursor.y -= line_height * 0.4;
+        }
+
+        let mut left = MARGIN_MM;
+        let mut marker = None;
+        if let BlockKind::ListItem { depth, number } = block.kind {
+            let indent = LIST_INDENT_MM * depth as f32;
+            marker = Some((
+                MARGIN_MM + indent - LIST_INDENT_MM,
+                match number {
+                    Some(n) => format!("{}.", n),
+                    None => "-".to_string(),
+                },
+            ));
+            left = MARGIN_MM + indent;
+        }
+
+        // Greedy word wrap across styled runs
+        let words = block.runs.iter().flat_map(|run| {
+            run.text
+                .split(' ')
+                .filter(|w| !w.is_empty())
+                .map(move |w| (w, run.bold || heading, run.italic))
+        });
+        let space = text_width_mm(" ", size, false);
+        let mut x = left;
+        cursor.new_line(line_height);
+        if let Some((marker_x, marker)) = &marker {
+            cursor.layer.use_text(
+                marker.as_str(),
+                size,
+                Mm(*marker_x),
+                Mm(cursor.y),
+                &fonts.regular,
+            );
+        }
+        for (word, bold, italic) in words {
+            let width = text_width_mm(word, size, bold);
+            if x > left && x + width > PAGE_WIDTH_MM - MARGIN_MM {
+                cursor.new_line(line_height);
+                x = left;
+            }
+            cursor
+                .layer
+                .use_text(word, size, Mm(x), Mm(cursor.y), fonts.get(bold, italic));
+            x += width + space;
+        }
+
+        cursor.y -= line_height * 0.5;
+    }
+
+    let pages = cursor.pages;
+    Ok((doc, pages))
+}
+
+struct Cursor<'a> {
+    doc: &'a PdfDocumentReference,
+    layer: PdfLayerReference,
+    /// Baseline of the last line written, in mm from the bottom
+    y: f32,
+    pages: usize,
+}
+
+impl Cursor<'_> {
+    /// Move down one line, starting a new page when the bottom margin is reached
+    fn new_line(&mut self, line_height: f32) {
+        self.y -= line_height;
+        if self.y < MARGIN_MM {
+            let (page, layer) = self
+                .doc
+                .add_page(Mm(PAGE_WIDTH_MM), Mm(PAGE_HEIGHT_MM), "Layer 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT_MM - MARGIN_MM - line_height;
+            self.pages += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_structure() {
+        let blocks = parse_html(
+            "<html><head><title>T</title><style>p { color: red }</style></head><body>\
+             <h1>Report</h1><p>Some <b>bold</b> and <em>italic</em> text.</p>\
+             <script>alert('x')</script>\
+             <ol><li>First</li><li>Second<ul><li>Nested</li></ul></li></ol></body></html>",
+            None,
+        );
+
+        let kinds: Vec<BlockKind> = blocks.iter().map(|b| b.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                BlockKind::Heading(1),
+                BlockKind::Paragraph,
+                BlockKind::ListItem {
+                    depth: 1,
+                    number: Some(1)
+                },
+                BlockKind::ListItem {
+                    depth: 1,
+                    number: Some(2)
+                },
+                BlockKind::ListItem {
+                    depth: 2,
+                    number: None
+                },
+            ]
+        );
+        assert_eq!(blocks[1].text(), "Some bold and italic text.");
+        assert!(blocks[1].runs.iter().any(|r| r.bold && r.text == "bold"));
+        assert!(blocks[1]
+            .runs
+            .iter()
+            .any(|r| r.italic && r.text == "italic"));
+        assert!(!blocks.iter().any(|b| b.text().contains("alert")));
+    }
+
+    #[test]
+    fn test_links_resolved_against_base() {
+        let base = Url::parse("https://example.com/docs/").unwrap();
+        let blocks = parse_html(
+            r#"<p>See <a href="guide.html">the guide</a> or <a href="https://rust-lang.org">https://rust-lang.org</a>.</p>"#,
+            Some(&base),
+        );
+        assert_eq!(
+            blocks[0].text(),
+            "See the guide (https://example.com/docs/guide.html) or https://rust-lang.org."
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Coordinate-aware PDF text extraction.
+//!
+//! `pdf_extract::extract_text` flattens a page to a string. The layout-based
+//! features (hyperlinks, tables) need to know *where* each word sits, so this
+//! module collects positioned words per page through pdf-extract's `OutputDev`.
+
+use pdf_extract::{MediaBox, OutputDev, OutputError, Transform};
+
+/// A word positioned in PDF user space (origin at the bottom-left of the page)
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Word {
+    pub text: String,
+    /// Left edge of the first glyph
+    pub x0: f64,
+    /// Right edge of the last glyph
+    pub x1: f64,
+    /// Baseline
+    pub y: f64,
+    /// Effective font size after the text matrix is applied
+    pub size: f64,
+}
+
+impl Word {
+    /// Horizontal and vertical centre of the word's box
+    pub fn center(&self) -> (f64, f64) {
+        ((self.x0 + self.x1) / 2.0, self.y + self.size / 3.0)
+    }
+}
+
+/// Positioned words of one page, grouped into lines in content-stream order
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PageLayout {
+    pub lines: Vec<Vec<Word>>,
+}
+
+/// Join the words of a line with single spaces
+pub(crate) fn line_text(words: &[Word]) -> String {
+    words
+        .iter()
+        .map(|w| w.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Extract positioned words for page `page_num` (1-based) of `doc`
+pub(crate) fn extract_page_layout(
+    doc: &lopdf::Document,
+    page_num: u32,
+) -> Result<PageLayout, OutputError> {
+    let mut collector = Collector::default();
+    pdf_extract::output_doc_page(doc, &mut collector, page_num)?;
+    Ok(collector.pages.pop().unwrap_or_default())
+}
+
+#[derive(Default)]
+struct Collector {
+    pages: Vec<PageLayout>,
+    line: Vec<Word>,
+    word: Option<Word>,
+}
+
+impl Collector {
+    fn finish_word(&mut self) {
+        if let Some(word) = self.word.take() {
+            self.line.push(word);
+        }
+    }
+
+    fn finish_line(&mut self) {
+        self.finish_word();
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            if let Some(page) = self.pages.last_mut() {
+                page.lines.push(line);
+            }
+        }
+    }
+}
+
+impl OutputDev for Collector {
+    fn begin_page(
+        &mut self,
+        _page_num: u32,
+        _media_box: &MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        self.pages.push(PageLayout::default());
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        self.finish_line();
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        char: &str,
+    ) -> Result<(), OutputError> {
+        let (x, y) = (trm.m31, trm.m32);
+        // Same effective size estimate as pdf-extract's plain text output
+        let size = ((font_size * (trm.m11 + trm.m21)) * (font_size * (trm.m12 + trm.m22)))
+            .abs()
+            .sqrt();
+        let end = x + width * size;
+
+        // Moving off the current baseline starts a new line
+        let line_y = self
+            .line
+            .first()
+            .or(self.word.as_ref())
+            .map(|w| (w.y, w.size));
+        if let Some((line_y, line_size)) = line_y {
+            if (y - line_y).abs() > line_size.max(size) * 0.5 {
+                self.finish_line();
+            }
+        }
+
+        if char.trim().is_empty() {
+            self.finish_word();
+            return Ok(());
+        }
+
+        // A visible gap (or jumping backwards) ends the current word
+        if let Some(word) = &self.word {
+            if x > word.x1 + size * 0.1 || x < word.x0 {
+                self.finish_word();
+            }
+        }
+
+        match &mut self.word {
+            Some(word) => {
+                word.text.push_str(char);
+                word.x1 = end;
+            }
+            None => {
+                self.word = Some(Word {
+                    text: char.to_string(),
+                    x0: x,
+                    x1: end,
+                    y,
+                    size,
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+/// Gap between words, in ems, that separates table cells rather than words
+const CELL_GAP_EMS: f64 = 1.0;
+
+/// Cell starts closer than this many ems are treated as the same column
+const COLUMN_TOLERANCE_EMS: f64 = 1.5;
+
+/// Share of rows that must line up with the detected columns
+const MIN_TABLE_CONFIDENCE: f64 = 0.8;
+
+/// Split a line into cells wherever the gap between words is wider than a space
+pub(crate) fn split_cells(words: &[Word]) -> Vec<Vec<Word>> {
+    let mut cells: Vec<Vec<Word>> = Vec::new();
+    for word in words {
+        match cells.last_mut() {
+            Some(cell)
+                if cell
+                    .last()
+                    .is_some_and(|prev| word.x0 - prev.x1 <= word.size * CELL_GAP_EMS) =>
+            {
+                cell.push(word.clone());
+            }
+            _ => cells.push(vec![word.clone()]),
+        }
+    }
+    cells
+}
+
+/// Outcome of column detection over consecutive multi-cell lines
+#[derive(Debug, PartialEq)]
+pub(crate) enum TableGuess {
+    /// Rows of cell text, padded to the same number of columns
+    Table(Vec<Vec<String>>),
+    /// Columns did not line up well enough; `confidence` is the matching row share
+    LowConfidence { confidence: f64 },
+}
+
+/// Cluster cell start positions into columns and map each row onto them.
+///
+/// `rows` are lines already split with [`split_cells`].
+pub(crate) fn guess_table(rows: &[Vec<Vec<Word>>]) -> TableGuess {
+    let size = rows
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|w| w.size)
+        .fold(0.0, f64::max);
+    let tolerance = size * COLUMN_TOLERANCE_EMS;
+
+    let mut starts: Vec<f64> = rows.iter().flatten().map(|cell| cell[0].x0).collect();
+    starts.sort_by(|a, b| a.total_cmp(b));
+
+    // Each column is (first start, last start); a start joins the previous
+    // column when it is within tolerance of that column's last member
+    let mut columns: Vec<(f64, f64)> = Vec::new();
+    for x in starts {
+        match columns.last_mut() {
+            Some((_, last)) if x - *last <= tolerance => *last = x,
+            _ => columns.push((x, x)),
+        }
+    }
+
+    let column_of = |x: f64| {
+        columns
+            .iter()
+            .position(|(first, last)| x >= *first - tolerance && x <= *last + tolerance)
+            .unwrap_or(0)
+    };
+
+    let aligned = rows
+        .iter()
+        .filter(|cells| {
+            cells.len() == columns.len()
+                && cells
+                    .iter()
+                    .enumerate()
+                    .all(|(i, cell)| column_of(cell[0].x0) == i)
+        })
+        .count();
+    let confidence = aligned as f64 / rows.len() as f64;
+
+    if columns.len() < 2 || confidence < MIN_TABLE_CONFIDENCE {
+        return TableGuess::LowConfidence { confidence };
+    }
+
+    let table = rows
+        .iter()
+        .map(|cells| {
+            let mut row = vec![String::new(); columns.len()];
+            for cell in cells {
+                let slot = &mut row[column_of(cell[0].x0)];
+                if !slot.is_empty() {
+                    slot.push(' ');
+                }
+                slot.push_str(&line_text(cell));
+            }
+            row
+        })
+        .collect();
+    TableGuess::Table(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, x0: f64, y: f64) -> Word {
+        Word {
+            text: text.to_string(),
+            x0,
+            x1: x0 + text.len() as f64 * 6.0,
+            y,
+            size: 12.0,
+        }
+    }
+
+    #[test]
+    fn test_split_cells() {
+        let line = vec![
+            word("Unit", 72.0, 700.0),
+            word("price", 100.0, 700.0),
+            word("9.99", 300.0, 700.0),
+        ];
+        let cells = split_cells(&line);
+        assert_eq!(cells.len(), 2);
+        assert_eq!(line_text(&cells[0]), "Unit price");
+        assert_eq!(line_text(&cells[1]), "9.99");
+    }
+
+    #[test]
+    fn test_guess_table_aligned() {
+        let rows: Vec<_> = [
+            ("Item", "Price", 700.0),
+            ("Apple", "1.00", 680.0),
+            ("Pear", "12.50", 660.0),
+        ]
+        .iter()
+        .map(|(a, b, y)| split_cells(&[word(a, 72.0, *y), word(b, 302.0 - b.len() as f64, *y)]))
+        .collect();
+
+        let TableGuess::Table(table) = guess_table(&rows) else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.len(), 3);
+        assert_eq!(table[2], vec!["Pear", "12.50"]);
+    }
+
+    #[test]
+    fn test_guess_table_misaligned() {
+        let rows: Vec<_> = [(72.0, 200.0), (150.0, 400.0), (90.0, 300.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, (a, b))| {
+                split_cells(&[
+                    word("x", *a, 700.0 - i as f64 * 20.0),
+                    word("y", *b, 700.0 - i as f64 * 20.0),
+                ])
+            })
+            .collect();
+
+        assert!(matches!(
+            guess_table(&rows),
+            TableGuess::LowConfidence { .. }
+        ));
+    }
+}
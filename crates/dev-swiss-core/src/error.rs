@@ -0,0 +1,160 @@
+//! One error type covering every module, for callers that use several of
+//! them and want a single `?`-compatible result type
+
+use crate::base64::Base64Error;
+use crate::color::ColorError;
+use crate::hex::HexError;
+use crate::password::PasswordError;
+use crate::qrcode::QrError;
+use crate::urlencode::UrlEncodeError;
+use std::fmt;
+
+#[cfg(feature = "barcode")]
+use crate::barcode::BarcodeError;
+#[cfg(feature = "convert")]
+use crate::convert::ConvertError;
+#[cfg(feature = "jwt")]
+use crate::jwt::JwtError;
+#[cfg(feature = "time")]
+use crate::timestamp::TimeError;
+
+/// Any error from this crate, wrapping the module's own error type
+#[derive(Debug)]
+pub enum CoreError {
+    Base64(Base64Error),
+    Color(ColorError),
+    Hex(HexError),
+    Password(PasswordError),
+    Qr(QrError),
+    UrlEncode(UrlEncodeError),
+    #[cfg(feature = "barcode")]
+    Barcode(BarcodeError),
+    #[cfg(feature = "convert")]
+    Convert(ConvertError),
+    #[cfg(feature = "jwt")]
+    Jwt(JwtError),
+    #[cfg(feature = "time")]
+    Time(TimeError),
+}
+
+impl CoreError {
+    fn inner(&self) -> &(dyn std::error::Error + 'static) {
+        match self {
+            CoreError::Base64(e) => e,
+            CoreError::Color(e) => e,
+            CoreError::Hex(e) => e,
+            CoreError::Password(e) => e,
+            CoreError::Qr(e) => e,
+            CoreError::UrlEncode(e) => e,
+            #[cfg(feature = "barcode")]
+            CoreError::Barcode(e) => e,
+            #[cfg(feature = "convert")]
+            CoreError::Convert(e) => e,
+            #[cfg(feature = "jwt")]
+            CoreError::Jwt(e) => e,
+            #[cfg(feature = "time")]
+            CoreError::Time(e) => e,
+        }
+    }
+}
+
+// Display is transparent, so `source` skips the wrapped error and goes
+// straight to its cause; otherwise chain reporters print its message twice
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.inner())
+    }
+}
+
+impl std::error::Error for CoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner().source()
+    }
+}
+
+impl From<Base64Error> for CoreError {
+    fn from(e: Base64Error) -> Self {
+        CoreError::Base64(e)
+    }
+}
+
+impl From<ColorError> for CoreError {
+    fn from(e: ColorError) -> Self {
+        CoreError::Color(e)
+    }
+}
+
+impl From<HexError> for CoreError {
+    fn from(e: HexError) -> Self {
+        CoreError::Hex(e)
+    }
+}
+
+impl From<PasswordError> for CoreError {
+    fn from(e: PasswordError) -> Self {
+        CoreError::Password(e)
+    }
+}
+
+impl From<QrError> for CoreError {
+    fn from(e: QrError) -> Self {
+        CoreError::Qr(e)
+    }
+}
+
+impl From<UrlEncodeError> for CoreError {
+    fn from(e: UrlEncodeError) -> Self {
+        CoreError::UrlEncode(e)
+    }
+}
+
+#[cfg(feature = "barcode")]
+impl From<BarcodeError> for CoreError {
+    fn from(e: BarcodeError) -> Self {
+        CoreError::Barcode(e)
+    }
+}
+
+#[cfg(feature = "convert")]
+impl From<ConvertError> for CoreError {
+    fn from(e: ConvertError) -> Self {
+        CoreError::Convert(e)
+    }
+}
+
+#[cfg(feature = "jwt")]
+impl From<JwtError> for CoreError {
+    fn from(e: JwtError) -> Self {
+        CoreError::Jwt(e)
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<TimeError> for CoreError {
+    fn from(e: TimeError) -> Self {
+        CoreError::Time(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::password::{generate_password, PasswordConfig};
+    use std::error::Error;
+
+    #[test]
+    fn test_wraps_module_errors() {
+        let config = PasswordConfig {
+            length: 0,
+            ..Default::default()
+        };
+        let err: CoreError = generate_password(&config).unwrap_err().into();
+        assert!(matches!(err, CoreError::Password(_)));
+        assert_eq!(err.to_string(), "Password length must be at least 1");
+        // The wrapped error isn't repeated as the source
+        assert!(err.source().is_none());
+
+        let err = CoreError::from(QrError::EmptyContent);
+        assert_eq!(err.to_string(), "Content cannot be empty");
+    }
+}
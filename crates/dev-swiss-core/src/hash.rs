@@ -0,0 +1,112 @@
+use crate::to_hex;
+use sha2::Digest;
+use std::fmt;
+use std::io::Read;
+
+/// Supported digest algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashAlgorithm::Md5 => write!(f, "MD5"),
+            HashAlgorithm::Sha1 => write!(f, "SHA-1"),
+            HashAlgorithm::Sha256 => write!(f, "SHA-256"),
+            HashAlgorithm::Sha512 => write!(f, "SHA-512"),
+        }
+    }
+}
+
+fn digest_with<D: Digest>(mut reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut hasher = D::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Hash everything `reader` yields, reading in chunks so large files are not
+/// loaded into memory
+pub fn hash_reader(reader: impl Read, algorithm: HashAlgorithm) -> std::io::Result<Vec<u8>> {
+    match algorithm {
+        HashAlgorithm::Md5 => digest_with::<md5::Md5>(reader),
+        HashAlgorithm::Sha1 => digest_with::<sha1::Sha1>(reader),
+        HashAlgorithm::Sha256 => digest_with::<sha2::Sha256>(reader),
+        HashAlgorithm::Sha512 => digest_with::<sha2::Sha512>(reader),
+    }
+}
+
+/// Hash an in-memory byte slice
+pub fn hash_bytes(data: &[u8], algorithm: HashAlgorithm) -> Vec<u8> {
+    hash_reader(data, algorithm).expect("reading from a slice cannot fail")
+}
+
+/// Compare a digest against an expected value given as hex (any case) or base64
+pub fn digest_matches(digest: &[u8], expected: &str) -> bool {
+    let expected = expected.trim();
+    if expected.eq_ignore_ascii_case(&to_hex(digest)) {
+        return true;
+    }
+    let config = crate::Base64Config::default();
+    crate::decode_base64(expected, &config).is_ok_and(|bytes| bytes == digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_digests_of_abc() {
+        let cases = [
+            (HashAlgorithm::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (
+                HashAlgorithm::Sha1,
+                "a9993e364706816aba3e25717850c26c9cd0d89d",
+            ),
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Sha512,
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                 2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            ),
+        ];
+        for (algorithm, expected) in cases {
+            assert_eq!(
+                to_hex(&hash_bytes(b"abc", algorithm)),
+                expected,
+                "{}",
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_reader_matches_bytes() {
+        let data = vec![7u8; 200_000];
+        let streamed = hash_reader(std::io::Cursor::new(&data), HashAlgorithm::Sha256).unwrap();
+        assert_eq!(streamed, hash_bytes(&data, HashAlgorithm::Sha256));
+    }
+
+    #[test]
+    fn test_digest_matches() {
+        let digest = hash_bytes(b"abc", HashAlgorithm::Md5);
+        assert!(digest_matches(&digest, "900150983CD24FB0D6963F7D28E17F72"));
+        assert!(digest_matches(&digest, "kAFQmDzST7DWlj99KOF/cg=="));
+        assert!(!digest_matches(&digest, "900150983cd24fb0d6963f7d28e17f73"));
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum HexError {
+    OddLength,
+    InvalidDigit(char),
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::OddLength => write!(f, "Hex input has an odd number of digits"),
+            HexError::InvalidDigit(c) => write!(f, "Invalid hex digit: {:?}", c),
+        }
+    }
+}
+
+impl std::error::Error for HexError {}
+
+/// Lowercase hex encoding
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode hex digits (either case) into bytes
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, HexError> {
+    let digits: Vec<char> = hex.trim().chars().collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(HexError::OddLength);
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let hi = pair[0]
+                .to_digit(16)
+                .ok_or(HexError::InvalidDigit(pair[0]))?;
+            let lo = pair[1]
+                .to_digit(16)
+                .ok_or(HexError::InvalidDigit(pair[1]))?;
+            Ok((hi * 16 + lo) as u8)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = [0x00, 0x7f, 0xab, 0xff];
+        assert_eq!(to_hex(&bytes), "007fabff");
+        assert_eq!(from_hex("007FABff").unwrap(), bytes);
+        assert!(matches!(from_hex("abc"), Err(HexError::OddLength)));
+        assert!(matches!(from_hex("zz"), Err(HexError::InvalidDigit('z'))));
+    }
+}
@@ -1,20 +1,136 @@
+pub mod base64;
+pub mod color;
+pub mod error;
+pub mod hex;
+pub mod lorem;
 pub mod password;
+pub mod prelude;
 pub mod qrcode;
+pub mod random;
+pub mod slug;
+pub mod urlencode;
+pub mod uuid;
+
+#[cfg(feature = "barcode")]
+pub mod barcode;
 
 #[cfg(feature = "convert")]
 pub mod convert;
 
+#[cfg(feature = "hash")]
+pub mod hash;
+
+#[cfg(feature = "jwt")]
+pub mod jwt;
+
+#[cfg(feature = "time")]
+pub mod timestamp;
+
+#[cfg(any(feature = "convert", feature = "image-output"))]
+mod utc;
+
+#[cfg(feature = "barcode")]
+pub use barcode::{
+    generate_barcode, generate_code128, render_barcode_to_image, render_barcode_to_svg, Barcode,
+    BarcodeError, BarcodeImageConfig, BarcodeKind, BARCODE_QUIET_ZONE_MODULES,
+};
+
 #[cfg(feature = "convert")]
-pub use convert::{convert, ConvertConfig, ConvertError, ConvertResult, Format};
+pub use convert::{
+    convert, convert_stream, pdf_has_text, supported_conversions, ConvertConfig, ConvertError,
+    ConvertResult, DocxStyle, Format, DOCX_FONT_SIZE_RANGE, DOCX_MAX_LINE_SPACING, DOCX_MAX_MARGIN,
+    DOCX_MAX_PARAGRAPH_SPACING,
+};
+
+#[cfg(feature = "hash")]
+pub use hash::{digest_matches, hash_bytes, hash_reader, HashAlgorithm};
+
+#[cfg(feature = "jwt")]
+pub use jwt::{decode_jwt, time_claims, verify_jwt, DecodedJwt, JwtAlgorithm, JwtError, TimeClaim};
+
+#[cfg(feature = "time")]
+pub use timestamp::{
+    convert_time, detect_epoch_unit, epoch_to_rfc3339, parse_time_zone, rfc3339_to_epoch,
+    EpochUnit, TimeConfig, TimeDirection, TimeError, TimeZoneSpec,
+};
+
+pub use self::base64::{decode_base64, encode_base64, Base64Alphabet, Base64Config, Base64Error};
+pub use self::uuid::{generate_uuid, generate_uuids, UuidConfig, UuidVersion};
+pub use color::{
+    color_name, contrast_ratio, parse_css_color, relative_luminance, rgb_to_hsl, to_hex_color,
+    ColorError,
+};
+pub use error::CoreError;
+pub use hex::{from_hex, to_hex, HexError};
+pub use lorem::{generate_lorem, generate_lorem_with_rng, LoremConfig, LoremLength};
+pub use password::{
+    available_wordlists, char_class_histogram, class_probabilities, contains_keyboard_run,
+    default_charset, fully_excluded_classes, generate_leet_password, generate_passphrase,
+    generate_password, generate_password_audited, mask_secret, normalize_exclude_chars,
+    parse_class_weights, retry_with_bound, CharClass, ClassWeights, LeetConfig, PassphraseConfig,
+    PasswordAudit, PasswordConfig, PasswordError, MAX_GENERATION_ATTEMPTS, MAX_PASSWORD_LENGTH,
+    PASSWORD_POLICIES,
+};
+pub use qrcode::content::{
+    append_crc, auto_wrap_content, compress_content, decompress_content, detect_content_type,
+    expand_env, unset_env_vars, verify_crc, ContentKind, COMPRESSED_CONTENT_MARKER, CRC_DELIMITER,
+};
+pub use qrcode::{
+    auto_output_path, best_error_correction, capacity, content_slug, contrast_warning,
+    ec_for_use_case, fits, generate_micro_qr, generate_qr, generate_qr_bytes, palette_tone,
+    parse_color, qr_info, qr_modules, read_content_file, render_to_pbm, render_to_terminal,
+    render_to_terminal_checked, render_to_terminal_colored, render_to_unicode_string,
+    scale_for_target_px, BackgroundConfig, BackgroundFit, ErrorCorrectionLevel, ImageConfig,
+    LogoConfig, Mode, OutputFormat, PaletteMode, PbmConfig, QrCode, QrConfig, QrError, QrInfo,
+    SvgOptions, UnicodeConfig, UseCase, FILE_OUTPUT_FORMATS, MIN_QR_CONTRAST, QUIET_ZONE_MODULES,
+};
+pub use random::{random_bytes, random_bytes_with_rng, random_string, RandomEncoding};
+pub use slug::{slugify, SlugOptions};
+pub use urlencode::{url_decode, url_encode, UrlEncodeError, UrlEncodeMode};
 
-pub use password::{generate_password, PasswordConfig, PasswordError};
+#[cfg(feature = "image-output")]
 pub use qrcode::{
-    generate_qr, parse_color, render_to_terminal, ErrorCorrectionLevel, ImageConfig, LogoConfig,
-    OutputFormat, QrConfig, QrError,
+    add_border, add_label, encode_png, encode_png_optimized, finish_image, is_likely_scannable,
+    logo_warning, module_error_ratio, overlay_logo, overlay_on_background,
+    overlay_on_background_with_logo, render_to_image, render_to_image_with, render_to_pdf,
+    render_to_svg, render_to_svg_with, save_image, save_png_optimized, save_qr, with_quiet_zone,
+    LogoPlacement, PostProcess,
+};
+
+#[cfg(feature = "image-output")]
+pub use qrcode::batch::{
+    expand_template, output_action, parse_range, range_items, render_batch, BatchItem,
+    BatchSummary, ExistingOutput, OutputAction, RANGE_PLACEHOLDER,
 };
 
 #[cfg(feature = "image-output")]
-pub use qrcode::{overlay_logo, overlay_on_background, render_to_image, render_to_svg, save_image};
+pub use qrcode::sheet::{parse_sheet_entries, render_sheet, SheetConfig, SheetEntry};
+
+#[cfg(feature = "image-output")]
+pub use qrcode::damage::simulate_damage;
+
+#[cfg(feature = "image-output")]
+pub use qrcode::metadata::{
+    embed_png_metadata, qr_metadata, read_png_metadata, METADATA_CONTENT_HASH_KEY,
+    METADATA_CONTENT_KEY,
+};
+
+#[cfg(feature = "decode")]
+pub use qrcode::damage::reads_after_damage;
+
+#[cfg(feature = "decode")]
+pub use qrcode::decode::{
+    decode_qr, decode_qr_file, preprocess_for_decode, DecodeConfig, DecodeResult,
+};
+
+#[cfg(feature = "ai-generation")]
+pub use qrcode::ai::{
+    generate_ai_qr, AiConfig, AiQrResult, QrBackgroundProvider, RateLimit, RateLimitedProvider,
+    StabilityProvider,
+};
+
+#[cfg(feature = "ai-async")]
+pub use qrcode::ai::{generate_ai_qr_async, AsyncQrBackgroundProvider};
 
 #[cfg(feature = "ai-generation")]
-pub use qrcode::ai::generate_ai_qr;
+pub use qrcode::scratch::{is_remote_source, ScratchDir};
@@ -0,0 +1,195 @@
+use rand::Rng;
+
+const WORDLIST_LOREM: &str = include_str!("wordlists/lorem.txt");
+const CLASSIC_OPENING: &[&str] = &["lorem", "ipsum", "dolor", "sit", "amet"];
+const SENTENCE_WORDS: (usize, usize) = (6, 14);
+const PARAGRAPH_SENTENCES: (usize, usize) = (3, 6);
+
+/// How much placeholder text to generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoremLength {
+    Words(usize),
+    Sentences(usize),
+    Paragraphs(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct LoremConfig {
+    pub length: LoremLength,
+    /// Begin with the traditional "Lorem ipsum dolor sit amet"
+    pub classic_start: bool,
+}
+
+impl Default for LoremConfig {
+    fn default() -> Self {
+        Self {
+            length: LoremLength::Paragraphs(1),
+            classic_start: true,
+        }
+    }
+}
+
+fn wordlist() -> Vec<&'static str> {
+    WORDLIST_LOREM.lines().filter(|w| !w.is_empty()).collect()
+}
+
+struct Writer<'a, R: Rng> {
+    rng: &'a mut R,
+    words: Vec<&'static str>,
+    opening: std::slice::Iter<'static, &'static str>,
+}
+
+impl<R: Rng> Writer<'_, R> {
+    fn word(&mut self) -> &'static str {
+        match self.opening.next() {
+            Some(word) => word,
+            None => self.words[self.rng.gen_range(0..self.words.len())],
+        }
+    }
+
+    /// A capitalized sentence of exactly `len` words ending in a period
+    fn sentence(&mut self, len: usize) -> String {
+        let mut sentence = String::new();
+        for i in 0..len {
+            // Keep the classic opening intact
+            let in_opening = self.opening.len() > 0;
+            let word = self.word();
+            if i == 0 {
+                let mut chars = word.chars();
+                sentence.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+                sentence.push_str(chars.as_str());
+            } else {
+                // An occasional comma reads more naturally, but never right
+                // before the final word
+                if i + 1 < len && i > 2 && !in_opening && self.rng.gen_ratio(1, 8) {
+                    sentence.push(',');
+                }
+                sentence.push(' ');
+                sentence.push_str(word);
+            }
+        }
+        sentence.push('.');
+        sentence
+    }
+
+    fn sentence_len(&mut self) -> usize {
+        self.rng.gen_range(SENTENCE_WORDS.0..=SENTENCE_WORDS.1)
+    }
+
+    fn paragraph(&mut self, sentences: usize) -> String {
+        (0..sentences)
+            .map(|_| {
+                let len = self.sentence_len();
+                self.sentence(len)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Generate placeholder text with a caller-supplied RNG
+///
+/// Returns one string per paragraph. Word and sentence counts produce a single
+/// paragraph. Pass a seeded RNG for reproducible output.
+pub fn generate_lorem_with_rng<R: Rng>(config: &LoremConfig, rng: &mut R) -> Vec<String> {
+    let mut writer = Writer {
+        rng,
+        words: wordlist(),
+        opening: if config.classic_start {
+            CLASSIC_OPENING.iter()
+        } else {
+            [].iter()
+        },
+    };
+
+    match config.length {
+        LoremLength::Words(0) | LoremLength::Sentences(0) | LoremLength::Paragraphs(0) => {
+            Vec::new()
+        }
+        LoremLength::Words(mut remaining) => {
+            let mut sentences = Vec::new();
+            while remaining > 0 {
+                let len = writer.sentence_len().min(remaining);
+                sentences.push(writer.sentence(len));
+                remaining -= len;
+            }
+            vec![sentences.join(" ")]
+        }
+        LoremLength::Sentences(count) => vec![writer.paragraph(count)],
+        LoremLength::Paragraphs(count) => (0..count)
+            .map(|_| {
+                let sentences = writer
+                    .rng
+                    .gen_range(PARAGRAPH_SENTENCES.0..=PARAGRAPH_SENTENCES.1);
+                writer.paragraph(sentences)
+            })
+            .collect(),
+    }
+}
+
+/// Generate placeholder text
+pub fn generate_lorem(config: &LoremConfig) -> Vec<String> {
+    generate_lorem_with_rng(config, &mut rand::thread_rng())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn config(length: LoremLength) -> LoremConfig {
+        LoremConfig {
+            length,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_word_count() {
+        for n in [1, 5, 13, 50, 333] {
+            let text = generate_lorem(&config(LoremLength::Words(n)));
+            assert_eq!(text.len(), 1);
+            assert_eq!(text[0].split_whitespace().count(), n);
+            assert!(text[0].ends_with('.'));
+        }
+        assert!(generate_lorem(&config(LoremLength::Words(0))).is_empty());
+    }
+
+    #[test]
+    fn test_sentence_and_paragraph_counts() {
+        let text = generate_lorem(&config(LoremLength::Sentences(4)));
+        assert_eq!(text.len(), 1);
+        assert_eq!(text[0].matches('.').count(), 4);
+
+        let text = generate_lorem(&config(LoremLength::Paragraphs(3)));
+        assert_eq!(text.len(), 3);
+        assert!(text
+            .iter()
+            .all(|p| p.chars().next().unwrap().is_uppercase()));
+    }
+
+    #[test]
+    fn test_classic_start() {
+        let text = generate_lorem(&config(LoremLength::Words(8)));
+        assert!(text[0].starts_with("Lorem ipsum dolor sit amet"));
+
+        let plain = LoremConfig {
+            length: LoremLength::Words(200),
+            classic_start: false,
+        };
+        let mut rng = StdRng::seed_from_u64(1);
+        let text = generate_lorem_with_rng(&plain, &mut rng);
+        assert!(!text[0].starts_with("Lorem ipsum dolor sit amet"));
+    }
+
+    #[test]
+    fn test_seed_determinism() {
+        let cfg = config(LoremLength::Paragraphs(2));
+        let a = generate_lorem_with_rng(&cfg, &mut StdRng::seed_from_u64(42));
+        let b = generate_lorem_with_rng(&cfg, &mut StdRng::seed_from_u64(42));
+        let c = generate_lorem_with_rng(&cfg, &mut StdRng::seed_from_u64(43));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
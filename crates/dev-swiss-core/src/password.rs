@@ -1,4 +1,6 @@
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
+use std::collections::HashMap;
 use std::fmt;
 
 const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
@@ -6,6 +8,29 @@ const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
 const NUMBERS: &str = "0123456789";
 const SYMBOLS: &str = "!@#$%^&*()_+-=[]{}|;:,.<>?";
 const AMBIGUOUS: &str = "0O1lI";
+const WORDLIST_EN: &str = include_str!("wordlists/en.txt");
+const WORDLIST_DE: &str = include_str!("wordlists/de.txt");
+const WORDLIST_FR: &str = include_str!("wordlists/fr.txt");
+const WORDLIST_ES: &str = include_str!("wordlists/es.txt");
+const WORDLIST_LANGUAGES: &[&str] = &["en", "de", "fr", "es"];
+const LEET_SUBSTITUTIONS: &[(char, char)] = &[('a', '@'), ('e', '3'), ('o', '0')];
+
+/// QWERTY rows, unshifted and shifted, for spotting keyboard runs
+const KEYBOARD_ROWS: &[(&str, &str)] = &[
+    ("`1234567890-=", "~!@#$%^&*()_+"),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|"),
+    ("asdfghjkl;'", "ASDFGHJKL:\""),
+    ("zxcvbnm,./", "ZXCVBNM<>?"),
+];
+
+/// Candidates drawn before giving up on a config whose constraints are too
+/// tight to satisfy, passed to [`retry_with_bound`] by every constrained
+/// generator
+pub const MAX_GENERATION_ATTEMPTS: usize = 1000;
+
+/// Default upper bound on password length, so a typo like `--length 100000000`
+/// fails fast instead of allocating gigabytes
+pub const MAX_PASSWORD_LENGTH: usize = 4096;
 
 #[derive(Debug, Clone)]
 pub struct PasswordConfig {
@@ -15,7 +40,23 @@ pub struct PasswordConfig {
     pub numbers: bool,
     pub symbols: bool,
     pub exclude_ambiguous: bool,
+    /// Characters `exclude_ambiguous` removes, written like `exclude_chars`;
+    /// `None` uses `0O1lI`
+    pub ambiguous_chars: Option<String>,
     pub exclude_chars: String,
+    /// Reject lengths above this; `None` disables the check
+    pub max_length: Option<usize>,
+    /// Accept a length of 0 (an empty password) instead of treating it as a mistake
+    pub allow_empty: bool,
+    /// Regenerate passwords containing a keyboard run (e.g. `asdf`) of at
+    /// least this many keys; `None` disables the check
+    pub reject_keyboard_runs: Option<usize>,
+    /// Never put two characters from the same class (uppercase, lowercase,
+    /// number, symbol) next to each other
+    pub no_adjacent_same_class: bool,
+    /// How likely each class's characters are to be drawn, relative to the
+    /// others; the default weighs every character in the pool equally
+    pub class_weights: ClassWeights,
 }
 
 impl Default for PasswordConfig {
@@ -27,15 +68,203 @@ impl Default for PasswordConfig {
             numbers: true,
             symbols: true,
             exclude_ambiguous: false,
+            ambiguous_chars: None,
             exclude_chars: String::new(),
+            max_length: Some(MAX_PASSWORD_LENGTH),
+            allow_empty: false,
+            reject_keyboard_runs: None,
+            no_adjacent_same_class: false,
+            class_weights: ClassWeights::default(),
+        }
+    }
+}
+
+/// Per-character draw weights for each class
+///
+/// A character is drawn with probability proportional to its class's weight,
+/// so `lowercase: 3` makes each lowercase letter three times as likely as a
+/// character weighted 1. The class as a whole also scales with how many of
+/// its characters are in the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassWeights {
+    pub uppercase: u32,
+    pub lowercase: u32,
+    pub numbers: u32,
+    pub symbols: u32,
+}
+
+impl Default for ClassWeights {
+    fn default() -> Self {
+        Self {
+            uppercase: 1,
+            lowercase: 1,
+            numbers: 1,
+            symbols: 1,
+        }
+    }
+}
+
+impl ClassWeights {
+    /// Weight of `class`; characters outside the built-in sets weigh 1
+    pub fn of(&self, class: CharClass) -> u32 {
+        match class {
+            CharClass::Uppercase => self.uppercase,
+            CharClass::Lowercase => self.lowercase,
+            CharClass::Number => self.numbers,
+            CharClass::Symbol => self.symbols,
+            CharClass::Other => 1,
         }
     }
 }
 
+/// Parse weights like `lowercase=3,symbols=1`; classes left out keep weight 1
+///
+/// Class names are as in [`CharClass::name`]. Weights must be at least 1; to
+/// leave a class out, disable it instead. The weights of every character in
+/// the pool must add up to at most `u32::MAX`, which `PasswordConfig::validate`
+/// checks once the pool is known.
+pub fn parse_class_weights(spec: &str) -> Result<ClassWeights, PasswordError> {
+    let invalid = PasswordError::InvalidClassWeights;
+    let mut weights = ClassWeights::default();
+    for entry in spec
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (name, weight) = entry
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("expected CLASS=WEIGHT, got '{}'", entry)))?;
+        let weight: u32 = weight
+            .trim()
+            .parse()
+            .map_err(|_| invalid(format!("'{}' is not a whole number", weight.trim())))?;
+        let slot = match name.trim().to_ascii_lowercase().as_str() {
+            "uppercase" => &mut weights.uppercase,
+            "lowercase" => &mut weights.lowercase,
+            "numbers" => &mut weights.numbers,
+            "symbols" => &mut weights.symbols,
+            other => {
+                return Err(invalid(format!(
+                    "unknown class '{}' (expected uppercase, lowercase, numbers or symbols)",
+                    other
+                )))
+            }
+        };
+        *slot = weight;
+    }
+    Ok(weights)
+}
+
+/// Names accepted by `PasswordConfig::from_policy`
+pub const PASSWORD_POLICIES: &[&str] = &["pin", "nist", "strong", "pci"];
+
+impl PasswordConfig {
+    /// The characters `exclude_ambiguous` removes
+    pub fn ambiguous_set(&self) -> String {
+        match &self.ambiguous_chars {
+            Some(chars) => normalize_exclude_chars(chars),
+            None => AMBIGUOUS.to_string(),
+        }
+    }
+
+    /// A preset config for a common standard
+    ///
+    /// - `pin`: 6 digits
+    /// - `nist`: 15 characters from every class, per NIST SP 800-63B's
+    ///   recommended minimum for single-factor passwords
+    /// - `strong`: 32 characters from every class, with no keyboard runs of
+    ///   4 or more keys
+    /// - `pci`: 12 letters and digits, per PCI DSS 4.0
+    pub fn from_policy(name: &str) -> Result<Self, PasswordError> {
+        let defaults = Self::default();
+        let config = match name.to_ascii_lowercase().as_str() {
+            "pin" => Self {
+                length: 6,
+                uppercase: false,
+                lowercase: false,
+                symbols: false,
+                ..defaults
+            },
+            "nist" => Self {
+                length: 15,
+                ..defaults
+            },
+            "strong" => Self {
+                length: 32,
+                reject_keyboard_runs: Some(4),
+                ..defaults
+            },
+            "pci" => Self {
+                length: 12,
+                symbols: false,
+                ..defaults
+            },
+            _ => return Err(PasswordError::UnknownPolicy(name.to_string())),
+        };
+        Ok(config)
+    }
+
+    /// Check that this config can produce a password, without generating one
+    ///
+    /// Checks the length limits, that a character set is enabled and that the
+    /// exclusions leave at least one character, or one in each of two classes
+    /// when adjacent characters must alternate classes.
+    pub fn validate(&self) -> Result<(), PasswordError> {
+        if self.max_length.is_some_and(|max| self.length > max) {
+            return Err(PasswordError::LengthTooLarge(self.length));
+        }
+        if self.length == 0 && !self.allow_empty {
+            return Err(PasswordError::LengthTooShort);
+        }
+        let weights = self.class_weights;
+        if [
+            weights.uppercase,
+            weights.lowercase,
+            weights.numbers,
+            weights.symbols,
+        ]
+        .contains(&0)
+        {
+            return Err(PasswordError::InvalidClassWeights(
+                "weights must be at least 1; disable a class to leave it out".to_string(),
+            ));
+        }
+        let charset = build_charset(self)?;
+        charset
+            .iter()
+            .try_fold(0u32, |total, &c| {
+                total.checked_add(self.class_weights.of(CharClass::of(c)))
+            })
+            .ok_or_else(|| {
+                PasswordError::InvalidClassWeights(format!(
+                    "the weights of all {} characters must add up to at most {}",
+                    charset.len(),
+                    u32::MAX
+                ))
+            })?;
+        if self.no_adjacent_same_class && self.length > 1 {
+            let first = CharClass::of(charset[0]);
+            if charset.iter().all(|&c| CharClass::of(c) == first) {
+                return Err(PasswordError::SingleCharacterClass);
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub enum PasswordError {
     NoCharacterSets,
     EmptyCharacterPool,
+    LengthTooLarge(usize),
+    LengthTooShort,
+    /// [`retry_with_bound`] ran out of attempts
+    ConstraintsUnsatisfiable(usize),
+    UnknownPolicy(String),
+    SingleCharacterClass,
+    InvalidClassWeights(String),
+    UnknownLanguage(String),
+    NoWords,
 }
 
 impl fmt::Display for PasswordError {
@@ -47,13 +276,227 @@ impl fmt::Display for PasswordError {
             PasswordError::EmptyCharacterPool => {
                 write!(f, "No characters available after applying exclusions")
             }
+            PasswordError::LengthTooLarge(length) => {
+                write!(
+                    f,
+                    "Password length {} exceeds the maximum allowed length",
+                    length
+                )
+            }
+            PasswordError::LengthTooShort => {
+                write!(f, "Password length must be at least 1")
+            }
+            PasswordError::ConstraintsUnsatisfiable(attempts) => {
+                write!(
+                    f,
+                    "No password met the constraints after {} attempts; allow more characters or drop a constraint",
+                    attempts
+                )
+            }
+            PasswordError::UnknownPolicy(name) => {
+                write!(
+                    f,
+                    "Unknown password policy '{}' (available: {})",
+                    name,
+                    PASSWORD_POLICIES.join(", ")
+                )
+            }
+            PasswordError::SingleCharacterClass => {
+                write!(
+                    f,
+                    "Alternating character classes needs characters from at least two classes"
+                )
+            }
+            PasswordError::InvalidClassWeights(msg) => {
+                write!(f, "Invalid class weights: {}", msg)
+            }
+            PasswordError::UnknownLanguage(lang) => {
+                write!(
+                    f,
+                    "No wordlist for '{}' (available: {})",
+                    lang,
+                    WORDLIST_LANGUAGES.join(", ")
+                )
+            }
+            PasswordError::NoWords => {
+                write!(f, "A passphrase needs at least one word")
+            }
         }
     }
 }
 
 impl std::error::Error for PasswordError {}
 
-pub fn generate_password(config: &PasswordConfig) -> Result<String, PasswordError> {
+/// The character set a password character comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CharClass {
+    Uppercase,
+    Lowercase,
+    Number,
+    Symbol,
+    /// Anything outside the built-in sets
+    Other,
+}
+
+impl CharClass {
+    pub const ALL: [CharClass; 5] = [
+        CharClass::Uppercase,
+        CharClass::Lowercase,
+        CharClass::Number,
+        CharClass::Symbol,
+        CharClass::Other,
+    ];
+
+    pub fn of(c: char) -> Self {
+        match c {
+            _ if UPPERCASE.contains(c) => CharClass::Uppercase,
+            _ if LOWERCASE.contains(c) => CharClass::Lowercase,
+            _ if NUMBERS.contains(c) => CharClass::Number,
+            _ if SYMBOLS.contains(c) => CharClass::Symbol,
+            _ => CharClass::Other,
+        }
+    }
+
+    /// Plural name, as used in messages (`uppercase`, `numbers`, ...)
+    pub fn name(self) -> &'static str {
+        match self {
+            CharClass::Uppercase => "uppercase",
+            CharClass::Lowercase => "lowercase",
+            CharClass::Number => "numbers",
+            CharClass::Symbol => "symbols",
+            CharClass::Other => "other",
+        }
+    }
+}
+
+/// How many characters of each class appear across `passwords`
+///
+/// Classes that never appear are left out. Passing the pool from
+/// [`default_charset`] gives the expected composition to compare against.
+pub fn char_class_histogram(passwords: &[String]) -> HashMap<CharClass, usize> {
+    let mut histogram = HashMap::new();
+    for c in passwords.iter().flat_map(|password| password.chars()) {
+        *histogram.entry(CharClass::of(c)).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// Chance that a single drawn character comes from each class, given the
+/// pool and `config.class_weights`
+///
+/// Classes absent from the pool are left out; the values sum to 1. This is
+/// the share [`char_class_histogram`] approaches over many passwords, unless
+/// `no_adjacent_same_class` is set.
+pub fn class_probabilities(config: &PasswordConfig) -> HashMap<CharClass, f64> {
+    let mut weights: HashMap<CharClass, f64> = HashMap::new();
+    for c in default_charset(config).chars() {
+        let class = CharClass::of(c);
+        *weights.entry(class).or_insert(0.0) += f64::from(config.class_weights.of(class));
+    }
+    let total: f64 = weights.values().sum();
+    for weight in weights.values_mut() {
+        *weight /= total;
+    }
+    weights
+}
+
+/// Classes present in a generated password, for confirming policy compliance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordAudit {
+    pub has_uppercase: bool,
+    pub has_lowercase: bool,
+    pub has_numbers: bool,
+    pub has_symbols: bool,
+    /// Number of distinct characters the password was drawn from
+    pub pool_size: usize,
+}
+
+impl PasswordAudit {
+    fn of(password: &str, pool_size: usize) -> Self {
+        Self {
+            has_uppercase: password.chars().any(|c| UPPERCASE.contains(c)),
+            has_lowercase: password.chars().any(|c| LOWERCASE.contains(c)),
+            has_numbers: password.chars().any(|c| NUMBERS.contains(c)),
+            has_symbols: password.chars().any(|c| SYMBOLS.contains(c)),
+            pool_size,
+        }
+    }
+}
+
+/// Normalize a raw exclude list: whitespace is ignored, duplicates are
+/// dropped (keeping first occurrence), and the literal token `\s` stands for
+/// "exclude all whitespace".
+pub fn normalize_exclude_chars(raw: &str) -> String {
+    let mut normalized = String::new();
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let expanded: &[char] = if c == '\\' && chars.peek() == Some(&'s') {
+            chars.next();
+            &[' ', '\t', '\n', '\r']
+        } else if c.is_whitespace() {
+            &[]
+        } else {
+            &[c]
+        };
+        for &e in expanded {
+            if !normalized.contains(e) {
+                normalized.push(e);
+            }
+        }
+    }
+
+    normalized
+}
+
+/// Replace all but the first and last `reveal` characters with `*`, e.g.
+/// `mask_secret("Abcdefghijkz", 1)` is `A**********z`
+///
+/// The length stays visible. If revealing would leave nothing hidden between
+/// the two ends, every character is masked.
+pub fn mask_secret(secret: &str, reveal: usize) -> String {
+    let len = secret.chars().count();
+    if reveal.saturating_mul(2) >= len {
+        return "*".repeat(len);
+    }
+    secret
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if i < reveal || i >= len - reveal {
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+/// Names of enabled character classes that the exclusions remove entirely
+pub fn fully_excluded_classes(config: &PasswordConfig) -> Vec<&'static str> {
+    let excluded = normalize_exclude_chars(&config.exclude_chars);
+    let ambiguous = config.ambiguous_set();
+    let is_excluded =
+        |c: char| excluded.contains(c) || (config.exclude_ambiguous && ambiguous.contains(c));
+
+    [
+        (config.uppercase, "uppercase", UPPERCASE),
+        (config.lowercase, "lowercase", LOWERCASE),
+        (config.numbers, "numbers", NUMBERS),
+        (config.symbols, "symbols", SYMBOLS),
+    ]
+    .into_iter()
+    .filter(|(enabled, _, set)| *enabled && set.chars().all(is_excluded))
+    .map(|(_, name, _)| name)
+    .collect()
+}
+
+/// The characters a password will be drawn from, after applying the enabled
+/// sets and all exclusions
+///
+/// Empty when no sets are enabled or the exclusions remove everything. The
+/// length is the pool size used for entropy estimates.
+pub fn default_charset(config: &PasswordConfig) -> String {
     let mut charset = String::new();
 
     if config.uppercase {
@@ -69,43 +512,314 @@ pub fn generate_password(config: &PasswordConfig) -> Result<String, PasswordErro
         charset.push_str(SYMBOLS);
     }
 
-    if charset.is_empty() {
-        return Err(PasswordError::NoCharacterSets);
-    }
-
     // Remove ambiguous characters if requested
     if config.exclude_ambiguous {
+        let ambiguous = config.ambiguous_set();
         charset = charset
             .chars()
-            .filter(|c| !AMBIGUOUS.contains(*c))
+            .filter(|c| !ambiguous.contains(*c))
             .collect();
     }
 
     // Remove custom excluded characters
-    if !config.exclude_chars.is_empty() {
+    let exclude_chars = normalize_exclude_chars(&config.exclude_chars);
+    if !exclude_chars.is_empty() {
         charset = charset
             .chars()
-            .filter(|c| !config.exclude_chars.contains(*c))
+            .filter(|c| !exclude_chars.contains(*c))
             .collect();
     }
 
+    charset
+}
+
+fn build_charset(config: &PasswordConfig) -> Result<Vec<char>, PasswordError> {
+    if !(config.uppercase || config.lowercase || config.numbers || config.symbols) {
+        return Err(PasswordError::NoCharacterSets);
+    }
+
+    let charset = default_charset(config);
     if charset.is_empty() {
         return Err(PasswordError::EmptyCharacterPool);
     }
 
-    let charset: Vec<char> = charset.chars().collect();
-    let mut rng = rand::thread_rng();
+    Ok(charset.chars().collect())
+}
 
-    let password: String = (0..config.length)
-        .map(|_| {
-            let idx = rng.gen_range(0..charset.len());
-            charset[idx]
+/// Row and column of `c` on a QWERTY keyboard, ignoring shift
+fn key_position(c: char) -> Option<(usize, usize)> {
+    KEYBOARD_ROWS
+        .iter()
+        .enumerate()
+        .find_map(|(row, (plain, shifted))| {
+            plain
+                .chars()
+                .position(|k| k == c)
+                .or_else(|| shifted.chars().position(|k| k == c))
+                .map(|col| (row, col))
         })
-        .collect();
+}
+
+/// Whether `password` has `min_run` or more neighbouring keys from one QWERTY
+/// row in order, forwards or backwards (`asdf`, `FDSA`, `!@#$`)
+///
+/// Shift is ignored, so `qWeR` counts. Runs shorter than 2 keys are
+/// meaningless, so smaller values of `min_run` are treated as 2.
+pub fn contains_keyboard_run(password: &str, min_run: usize) -> bool {
+    let min_run = min_run.max(2);
+    let positions: Vec<_> = password.chars().map(key_position).collect();
+    let mut run = 1;
+    let mut step = 0;
+    for pair in positions.windows(2) {
+        match (pair[0], pair[1]) {
+            (Some((row_a, col_a)), Some((row_b, col_b)))
+                if row_a == row_b && col_a.abs_diff(col_b) == 1 =>
+            {
+                let this_step = col_b as isize - col_a as isize;
+                if this_step == step {
+                    run += 1;
+                } else {
+                    run = 2;
+                    step = this_step;
+                }
+            }
+            _ => {
+                run = 1;
+                step = 0;
+            }
+        }
+        if run >= min_run {
+            return true;
+        }
+    }
+    false
+}
+
+/// Call `attempt` until it returns a value, at most `max_attempts` times
+///
+/// `attempt` returns `Ok(None)` to be tried again; an error ends the retries
+/// and is returned as is. Running out of attempts is
+/// [`PasswordError::ConstraintsUnsatisfiable`], so a config that can't be met
+/// fails instead of looping forever.
+pub fn retry_with_bound<T>(
+    max_attempts: usize,
+    mut attempt: impl FnMut() -> Result<Option<T>, PasswordError>,
+) -> Result<T, PasswordError> {
+    for _ in 0..max_attempts {
+        if let Some(value) = attempt()? {
+            return Ok(value);
+        }
+    }
+    Err(PasswordError::ConstraintsUnsatisfiable(max_attempts))
+}
+
+/// The first password from `candidate` that passes the config's checks
+fn first_acceptable(
+    config: &PasswordConfig,
+    mut candidate: impl FnMut() -> Result<String, PasswordError>,
+) -> Result<String, PasswordError> {
+    let Some(min_run) = config.reject_keyboard_runs else {
+        return candidate();
+    };
+    retry_with_bound(MAX_GENERATION_ATTEMPTS, || {
+        candidate().map(|password| (!contains_keyboard_run(&password, min_run)).then_some(password))
+    })
+}
+
+pub fn generate_password(config: &PasswordConfig) -> Result<String, PasswordError> {
+    config.validate()?;
+    let charset: Vec<char> = default_charset(config).chars().collect();
+    let weights = charset
+        .iter()
+        .map(|&c| config.class_weights.of(CharClass::of(c)));
+    // validate() checked the pool and weights, so this only fails on a bug
+    let draw = WeightedIndex::new(weights)
+        .map_err(|e| PasswordError::InvalidClassWeights(e.to_string()))?;
+    let mut rng = rand::thread_rng();
+
+    first_acceptable(config, || {
+        let mut previous_class = None;
+        (0..config.length)
+            .map(|_| {
+                // Redraw rather than filter, so the allowed characters keep
+                // their relative weights; validate() ensured another class
+                // exists, but extreme weights can still make it unreachable
+                let c = retry_with_bound(MAX_GENERATION_ATTEMPTS, || {
+                    let c = charset[draw.sample(&mut rng)];
+                    let repeats =
+                        config.no_adjacent_same_class && previous_class == Some(CharClass::of(c));
+                    Ok((!repeats).then_some(c))
+                })?;
+                previous_class = Some(CharClass::of(c));
+                Ok(c)
+            })
+            .collect()
+    })
+}
+
+/// Generate a password and report which character classes actually appeared.
+///
+/// This only reports; it does not regenerate when an enabled class is missing.
+pub fn generate_password_audited(
+    config: &PasswordConfig,
+) -> Result<(String, PasswordAudit), PasswordError> {
+    let pool_size = build_charset(config)?.len();
+    let password = generate_password(config)?;
+    let audit = PasswordAudit::of(&password, pool_size);
+    Ok((password, audit))
+}
+
+/// Configuration for memorable passwords built from a dictionary word
+#[derive(Debug, Clone)]
+pub struct LeetConfig {
+    /// Apply leetspeak substitutions (a→@, e→3, o→0)
+    pub substitute: bool,
+    /// Capitalize the first letter of the word
+    pub capitalize: bool,
+    /// Append a random digit
+    pub numbers: bool,
+    /// Append a random symbol
+    pub symbols: bool,
+}
+
+impl Default for LeetConfig {
+    fn default() -> Self {
+        Self {
+            substitute: true,
+            capitalize: true,
+            numbers: true,
+            symbols: true,
+        }
+    }
+}
+
+impl LeetConfig {
+    /// Entropy in bits of passwords produced with this config.
+    ///
+    /// Substitutions and capitalization are deterministic, so they add nothing:
+    /// an attacker who knows the scheme only has to guess the word and suffix.
+    pub fn entropy_bits(&self) -> f64 {
+        let mut keyspace = wordlist().len() as f64;
+        if self.symbols {
+            keyspace *= SYMBOLS.chars().count() as f64;
+        }
+        if self.numbers {
+            keyspace *= NUMBERS.len() as f64;
+        }
+        keyspace.log2()
+    }
+}
+
+/// Languages with a bundled wordlist, for [`PassphraseConfig::lang`]
+pub fn available_wordlists() -> &'static [&'static str] {
+    WORDLIST_LANGUAGES
+}
+
+fn wordlist() -> Vec<&'static str> {
+    split_words(WORDLIST_EN)
+}
+
+fn wordlist_for(lang: &str) -> Result<Vec<&'static str>, PasswordError> {
+    let text = match lang {
+        "en" => WORDLIST_EN,
+        "de" => WORDLIST_DE,
+        "fr" => WORDLIST_FR,
+        "es" => WORDLIST_ES,
+        _ => return Err(PasswordError::UnknownLanguage(lang.to_string())),
+    };
+    Ok(split_words(text))
+}
+
+fn split_words(text: &'static str) -> Vec<&'static str> {
+    text.lines().filter(|w| !w.is_empty()).collect()
+}
+
+/// Generate a memorable password such as `Dr@g0n!7` from the bundled wordlist
+pub fn generate_leet_password(config: &LeetConfig) -> Result<String, PasswordError> {
+    let words = wordlist();
+    let mut rng = rand::thread_rng();
+    let word = words[rng.gen_range(0..words.len())];
+
+    // Capitalize first so words like "echo" become "Ech0" rather than "3ch0"
+    let mut password = String::new();
+    for (i, c) in word.chars().enumerate() {
+        if i == 0 && config.capitalize {
+            password.extend(c.to_uppercase());
+        } else if config.substitute {
+            let leet = LEET_SUBSTITUTIONS.iter().find(|(from, _)| *from == c);
+            password.push(leet.map_or(c, |(_, to)| *to));
+        } else {
+            password.push(c);
+        }
+    }
+
+    if config.symbols {
+        let symbols: Vec<char> = SYMBOLS.chars().collect();
+        password.push(symbols[rng.gen_range(0..symbols.len())]);
+    }
+    if config.numbers {
+        let digits: Vec<char> = NUMBERS.chars().collect();
+        password.push(digits[rng.gen_range(0..digits.len())]);
+    }
 
     Ok(password)
 }
 
+/// Configuration for passphrases of several random dictionary words
+#[derive(Debug, Clone)]
+pub struct PassphraseConfig {
+    /// Number of words
+    pub words: usize,
+    /// Placed between words; any text, including non-ASCII like "·"
+    pub separator: String,
+    /// Capitalize the first letter of each word, accented ones included
+    pub capitalize: bool,
+    /// Wordlist to draw from, one of [`available_wordlists`]
+    pub lang: String,
+}
+
+impl Default for PassphraseConfig {
+    fn default() -> Self {
+        Self {
+            words: 5,
+            separator: "-".to_string(),
+            capitalize: false,
+            lang: "en".to_string(),
+        }
+    }
+}
+
+impl PassphraseConfig {
+    /// Entropy in bits: each word is an independent draw from the wordlist
+    pub fn entropy_bits(&self) -> Result<f64, PasswordError> {
+        Ok(self.words as f64 * (wordlist_for(&self.lang)?.len() as f64).log2())
+    }
+}
+
+/// Generate a passphrase such as `forest-anchor-pebble-lantern-orbit`
+pub fn generate_passphrase(config: &PassphraseConfig) -> Result<String, PasswordError> {
+    if config.words == 0 {
+        return Err(PasswordError::NoWords);
+    }
+    let words = wordlist_for(&config.lang)?;
+    let mut rng = rand::thread_rng();
+
+    let mut chosen = Vec::with_capacity(config.words);
+    for _ in 0..config.words {
+        let word = words[rng.gen_range(0..words.len())];
+        if !config.capitalize {
+            chosen.push(word.to_string());
+            continue;
+        }
+        // By char, not byte, so "éclair" becomes "Éclair"
+        let mut chars = word.chars();
+        chosen.push(chars.next().map_or_else(String::new, |first| {
+            first.to_uppercase().chain(chars).collect()
+        }));
+    }
+    Ok(chosen.join(&config.separator))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,6 +834,7 @@ mod tests {
         assert!(config.symbols);
         assert!(!config.exclude_ambiguous);
         assert!(config.exclude_chars.is_empty());
+        assert_eq!(config.max_length, Some(MAX_PASSWORD_LENGTH));
     }
 
     #[test]
@@ -139,6 +854,117 @@ mod tests {
         assert_eq!(password.len(), 32);
     }
 
+    #[test]
+    fn test_validate() {
+        assert!(PasswordConfig::default().validate().is_ok());
+
+        let too_long = PasswordConfig {
+            length: MAX_PASSWORD_LENGTH + 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            too_long.validate(),
+            Err(PasswordError::LengthTooLarge(_))
+        ));
+
+        let empty = PasswordConfig {
+            length: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            empty.validate(),
+            Err(PasswordError::LengthTooShort)
+        ));
+
+        let no_sets = PasswordConfig {
+            uppercase: false,
+            lowercase: false,
+            numbers: false,
+            symbols: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            no_sets.validate(),
+            Err(PasswordError::NoCharacterSets)
+        ));
+
+        let all_excluded = PasswordConfig {
+            uppercase: false,
+            lowercase: false,
+            symbols: false,
+            exclude_chars: NUMBERS.to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            all_excluded.validate(),
+            Err(PasswordError::EmptyCharacterPool)
+        ));
+        // Generation fails the same way
+        assert!(matches!(
+            generate_password(&all_excluded),
+            Err(PasswordError::EmptyCharacterPool)
+        ));
+    }
+
+    #[test]
+    fn test_zero_length() {
+        let config = PasswordConfig {
+            length: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            generate_password(&config),
+            Err(PasswordError::LengthTooShort)
+        ));
+
+        let config = PasswordConfig {
+            length: 1,
+            ..config
+        };
+        assert_eq!(generate_password(&config).unwrap().len(), 1);
+
+        let config = PasswordConfig {
+            length: 0,
+            allow_empty: true,
+            ..Default::default()
+        };
+        assert_eq!(generate_password(&config).unwrap(), "");
+    }
+
+    #[test]
+    fn test_length_at_maximum() {
+        let config = PasswordConfig {
+            length: MAX_PASSWORD_LENGTH,
+            ..Default::default()
+        };
+        assert_eq!(
+            generate_password(&config).unwrap().len(),
+            MAX_PASSWORD_LENGTH
+        );
+    }
+
+    #[test]
+    fn test_length_above_maximum() {
+        let config = PasswordConfig {
+            length: MAX_PASSWORD_LENGTH + 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            generate_password(&config),
+            Err(PasswordError::LengthTooLarge(len)) if len == MAX_PASSWORD_LENGTH + 1
+        ));
+        assert!(generate_password_audited(&config).is_err());
+
+        let uncapped = PasswordConfig {
+            max_length: None,
+            ..config
+        };
+        assert_eq!(
+            generate_password(&uncapped).unwrap().len(),
+            MAX_PASSWORD_LENGTH + 1
+        );
+    }
+
     #[test]
     fn test_no_symbols() {
         let config = PasswordConfig {
@@ -149,6 +975,33 @@ mod tests {
         assert!(!password.chars().any(|c| SYMBOLS.contains(c)));
     }
 
+    #[test]
+    fn test_default_charset() {
+        let config = PasswordConfig::default();
+        let pool = default_charset(&config);
+        assert_eq!(pool.chars().count(), 26 + 26 + 10 + SYMBOLS.len());
+        assert!(pool.contains('!'));
+
+        let no_symbols = PasswordConfig {
+            symbols: false,
+            ..Default::default()
+        };
+        let smaller = default_charset(&no_symbols);
+        assert_eq!(smaller.len(), pool.len() - SYMBOLS.len());
+        assert!(!smaller.chars().any(|c| SYMBOLS.contains(c)));
+
+        let excluded = PasswordConfig {
+            symbols: false,
+            exclude_ambiguous: true,
+            exclude_chars: "xyz".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            default_charset(&excluded).len(),
+            smaller.len() - AMBIGUOUS.len() - 3
+        );
+    }
+
     #[test]
     fn test_exclude_ambiguous() {
         let config = PasswordConfig {
@@ -160,6 +1013,34 @@ mod tests {
         assert!(!password.chars().any(|c| AMBIGUOUS.contains(c)));
     }
 
+    #[test]
+    fn test_custom_ambiguous_chars() {
+        let config = PasswordConfig {
+            exclude_ambiguous: true,
+            ambiguous_chars: Some("5S 2Z 8B".to_string()),
+            length: 200,
+            ..Default::default()
+        };
+        assert_eq!(config.ambiguous_set(), "5S2Z8B");
+        let pool = default_charset(&config);
+        assert_eq!(
+            pool.len(),
+            default_charset(&PasswordConfig::default()).len() - 6
+        );
+        // The custom set replaces the default one rather than adding to it
+        assert!(pool.contains('0') && pool.contains('O') && pool.contains('l'));
+
+        let password = generate_password(&config).unwrap();
+        assert!(!password.chars().any(|c| "5S2Z8B".contains(c)));
+
+        // Only applies when ambiguous characters are excluded
+        let config = PasswordConfig {
+            exclude_ambiguous: false,
+            ..config
+        };
+        assert!(default_charset(&config).contains('S'));
+    }
+
     #[test]
     fn test_exclude_custom_chars() {
         let config = PasswordConfig {
@@ -198,4 +1079,619 @@ mod tests {
         let password = generate_password(&config).unwrap();
         assert!(password.chars().all(|c| c.is_ascii_digit()));
     }
+
+    #[test]
+    fn test_leet_substitutions_applied() {
+        let config = LeetConfig {
+            capitalize: false,
+            numbers: false,
+            symbols: false,
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let password = generate_leet_password(&config).unwrap();
+            assert!(!password.contains(['a', 'e', 'o']));
+            let restored: String = password
+                .chars()
+                .map(|c| match c {
+                    '@' => 'a',
+                    '3' => 'e',
+                    '0' => 'o',
+                    c => c,
+                })
+                .collect();
+            assert!(wordlist().contains(&restored.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_leet_suffix() {
+        let config = LeetConfig::default();
+        let password = generate_leet_password(&config).unwrap();
+        let mut tail = password.chars().rev();
+        assert!(tail.next().unwrap().is_ascii_digit());
+        assert!(SYMBOLS.contains(tail.next().unwrap()));
+        for _ in 0..100 {
+            let password = generate_leet_password(&config).unwrap();
+            assert!(password.chars().next().unwrap().is_uppercase());
+        }
+    }
+
+    #[test]
+    fn test_leet_no_symbols_drops_suffix() {
+        let config = LeetConfig {
+            substitute: false,
+            symbols: false,
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let password = generate_leet_password(&config).unwrap();
+            assert!(!password.chars().any(|c| SYMBOLS.contains(c)));
+            assert!(password.ends_with(|c: char| c.is_ascii_digit()));
+        }
+    }
+
+    #[test]
+    fn test_leet_entropy_reflects_keyspace() {
+        let words = wordlist().len() as f64;
+        let bare = LeetConfig {
+            numbers: false,
+            symbols: false,
+            ..Default::default()
+        };
+        assert!((bare.entropy_bits() - words.log2()).abs() < 1e-9);
+        // Substitutions add nothing to the keyspace
+        let plain = LeetConfig {
+            substitute: false,
+            ..bare.clone()
+        };
+        assert_eq!(plain.entropy_bits(), bare.entropy_bits());
+        assert!(LeetConfig::default().entropy_bits() > bare.entropy_bits());
+    }
+
+    #[test]
+    fn test_audit_matches_output() {
+        let configs = [
+            PasswordConfig::default(),
+            PasswordConfig {
+                symbols: false,
+                numbers: false,
+                ..Default::default()
+            },
+            PasswordConfig {
+                uppercase: false,
+                lowercase: false,
+                symbols: false,
+                length: 8,
+                ..Default::default()
+            },
+            PasswordConfig {
+                length: 1,
+                ..Default::default()
+            },
+        ];
+
+        for config in &configs {
+            let (password, audit) = generate_password_audited(config).unwrap();
+            assert_eq!(
+                audit.has_uppercase,
+                password.chars().any(|c| c.is_ascii_uppercase())
+            );
+            assert_eq!(
+                audit.has_lowercase,
+                password.chars().any(|c| c.is_ascii_lowercase())
+            );
+            assert_eq!(
+                audit.has_numbers,
+                password.chars().any(|c| c.is_ascii_digit())
+            );
+            assert_eq!(
+                audit.has_symbols,
+                password.chars().any(|c| SYMBOLS.contains(c))
+            );
+            assert!(!audit.has_uppercase || config.uppercase);
+            assert!(!audit.has_symbols || config.symbols);
+        }
+    }
+
+    #[test]
+    fn test_audit_pool_size() {
+        let config = PasswordConfig {
+            uppercase: false,
+            lowercase: false,
+            symbols: false,
+            exclude_chars: "09".to_string(),
+            ..Default::default()
+        };
+        let (password, audit) = generate_password_audited(&config).unwrap();
+        assert_eq!(audit.pool_size, 8);
+        assert!(audit.has_numbers);
+        assert!(!password.contains(['0', '9']));
+    }
+
+    #[test]
+    fn test_normalize_exclude_dedup() {
+        assert_eq!(normalize_exclude_chars("abcabc"), "abc");
+        assert_eq!(normalize_exclude_chars("a a"), "a");
+        assert_eq!(normalize_exclude_chars(""), "");
+    }
+
+    #[test]
+    fn test_normalize_exclude_whitespace_token() {
+        assert_eq!(normalize_exclude_chars("a\\sb"), "a \t\n\rb");
+        assert_eq!(normalize_exclude_chars("\\s\\s"), " \t\n\r");
+        // A lone backslash is still an ordinary excluded character
+        assert_eq!(normalize_exclude_chars("\\x"), "\\x");
+    }
+
+    #[test]
+    fn test_fully_excluded_classes() {
+        let config = PasswordConfig {
+            exclude_chars: "0123456789".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(fully_excluded_classes(&config), vec!["numbers"]);
+
+        let config = PasswordConfig {
+            numbers: false,
+            exclude_chars: "0123456789".to_string(),
+            ..Default::default()
+        };
+        assert!(fully_excluded_classes(&config).is_empty());
+
+        let config = PasswordConfig {
+            exclude_ambiguous: true,
+            exclude_chars: "23456789".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(fully_excluded_classes(&config), vec!["numbers"]);
+    }
+
+    #[test]
+    fn test_mask_secret() {
+        assert_eq!(mask_secret("Abcdefghijkz", 1), "A**********z");
+        assert_eq!(mask_secret("Abcdefghijkz", 3), "Abc******jkz");
+        assert_eq!(mask_secret("Abcdefghijkz", 0), "************");
+        assert_eq!(mask_secret("secret", 3), "******");
+        assert_eq!(mask_secret("secret", 100), "******");
+        assert_eq!(mask_secret("p\u{e4}ssw\u{f6}rd", 1), "p******d");
+        assert_eq!(mask_secret("", 1), "");
+    }
+
+    #[test]
+    fn test_contains_keyboard_run() {
+        for password in [
+            "xxasdfxx", "qwer", "REWQ", "qWeR", "1234", "!@#$", "zxcv", "jkl;", "p[]\\",
+        ] {
+            assert!(contains_keyboard_run(password, 4), "{:?}", password);
+        }
+        // Runs must stay on one row and keep one direction
+        for password in [
+            "asd",
+            "asdasd",
+            "qasw",
+            "aqsw",
+            "azsx",
+            "Kj#9mP$xL2nQ@8vR",
+            "",
+            "a",
+        ] {
+            assert!(!contains_keyboard_run(password, 4), "{:?}", password);
+        }
+        assert!(contains_keyboard_run("asd", 3));
+        assert!(contains_keyboard_run("as", 0));
+        assert!(!contains_keyboard_run("a", 0));
+    }
+
+    #[test]
+    fn test_keyboard_runs_are_regenerated() {
+        let config = PasswordConfig {
+            reject_keyboard_runs: Some(4),
+            ..Default::default()
+        };
+        let mut candidates = vec!["xxqwerxx", "fdsa1234", "Kj#9mP$x"].into_iter();
+        let password =
+            first_acceptable(&config, || Ok(candidates.next().unwrap().to_string())).unwrap();
+        assert_eq!(password, "Kj#9mP$x");
+
+        // Without the check the first candidate is kept
+        let mut candidates = vec!["xxqwerxx"].into_iter();
+        let password = first_acceptable(&PasswordConfig::default(), || {
+            Ok(candidates.next().unwrap().to_string())
+        });
+        assert_eq!(password.unwrap(), "xxqwerxx");
+    }
+
+    #[test]
+    fn test_unsatisfiable_keyboard_run_check() {
+        let config = PasswordConfig {
+            reject_keyboard_runs: Some(4),
+            ..Default::default()
+        };
+        let mut calls = 0;
+        let result = first_acceptable(&config, || {
+            calls += 1;
+            Ok("asdf".to_string())
+        });
+        assert!(matches!(
+            result,
+            Err(PasswordError::ConstraintsUnsatisfiable(
+                MAX_GENERATION_ATTEMPTS
+            ))
+        ));
+        assert_eq!(calls, MAX_GENERATION_ATTEMPTS);
+
+        let config = PasswordConfig {
+            length: 20,
+            ..config
+        };
+        for _ in 0..20 {
+            assert!(!contains_keyboard_run(
+                &generate_password(&config).unwrap(),
+                4
+            ));
+        }
+    }
+
+    #[test]
+    fn test_retry_with_bound() {
+        let mut calls = 0;
+        let found = retry_with_bound(5, || {
+            calls += 1;
+            Ok((calls == 3).then_some(calls))
+        });
+        assert_eq!(found.unwrap(), 3);
+
+        let mut calls = 0;
+        let exhausted: Result<(), _> = retry_with_bound(5, || {
+            calls += 1;
+            Ok(None)
+        });
+        assert!(matches!(
+            exhausted,
+            Err(PasswordError::ConstraintsUnsatisfiable(5))
+        ));
+        assert_eq!(calls, 5);
+
+        // Errors from an attempt stop the retries
+        let failed: Result<(), _> = retry_with_bound(5, || Err(PasswordError::NoWords));
+        assert!(matches!(failed, Err(PasswordError::NoWords)));
+    }
+
+    #[test]
+    fn test_unreachable_alternation_is_bounded() {
+        // Two classes, but the digit is drawn about once in four billion
+        // times, so alternating can't practically be met
+        let config = PasswordConfig {
+            length: 8,
+            uppercase: false,
+            symbols: false,
+            exclude_chars: "bcdefghijklmnopqrstuvwxyz012345689".to_string(),
+            no_adjacent_same_class: true,
+            class_weights: ClassWeights {
+                lowercase: u32::MAX - 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        let err = generate_password(&config).unwrap_err();
+        assert!(matches!(
+            err,
+            PasswordError::ConstraintsUnsatisfiable(MAX_GENERATION_ATTEMPTS)
+        ));
+        assert!(err.to_string().contains("drop a constraint"));
+    }
+
+    #[test]
+    fn test_no_adjacent_same_class() {
+        let config = PasswordConfig {
+            length: 64,
+            no_adjacent_same_class: true,
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            let password: Vec<char> = generate_password(&config).unwrap().chars().collect();
+            assert_eq!(password.len(), 64);
+            for pair in password.windows(2) {
+                assert_ne!(CharClass::of(pair[0]), CharClass::of(pair[1]), "{:?}", pair);
+            }
+        }
+
+        // Two classes can only alternate
+        let config = PasswordConfig {
+            uppercase: false,
+            symbols: false,
+            ..config
+        };
+        let password = generate_password(&config).unwrap();
+        let classes: Vec<CharClass> = password.chars().map(CharClass::of).collect();
+        assert!(classes.windows(2).all(|pair| pair[0] != pair[1]));
+        assert!(classes
+            .iter()
+            .all(|&class| matches!(class, CharClass::Lowercase | CharClass::Number)));
+    }
+
+    #[test]
+    fn test_no_adjacent_same_class_needs_two_classes() {
+        let digits_only = PasswordConfig {
+            uppercase: false,
+            lowercase: false,
+            symbols: false,
+            no_adjacent_same_class: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            generate_password(&digits_only),
+            Err(PasswordError::SingleCharacterClass)
+        ));
+
+        // Exclusions that empty a class count too
+        let letters_gone = PasswordConfig {
+            symbols: false,
+            numbers: false,
+            lowercase: true,
+            uppercase: true,
+            exclude_chars: LOWERCASE.to_string(),
+            no_adjacent_same_class: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            letters_gone.validate(),
+            Err(PasswordError::SingleCharacterClass)
+        ));
+
+        // A single character has no neighbours
+        let single = PasswordConfig {
+            length: 1,
+            ..digits_only
+        };
+        assert_eq!(generate_password(&single).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_char_class_histogram() {
+        let passwords = vec!["aB3!".to_string(), "xyz9".to_string(), "é".to_string()];
+        let histogram = char_class_histogram(&passwords);
+        assert_eq!(histogram[&CharClass::Lowercase], 4);
+        assert_eq!(histogram[&CharClass::Uppercase], 1);
+        assert_eq!(histogram[&CharClass::Number], 2);
+        assert_eq!(histogram[&CharClass::Symbol], 1);
+        assert_eq!(histogram[&CharClass::Other], 1);
+        assert!(char_class_histogram(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_class_frequencies_follow_pool_composition() {
+        let config = PasswordConfig::default();
+        let passwords: Vec<String> = (0..2000)
+            .map(|_| generate_password(&config).unwrap())
+            .collect();
+        let histogram = char_class_histogram(&passwords);
+        let pool = char_class_histogram(&[default_charset(&config)]);
+        let total = (2000 * config.length) as f64;
+        let pool_size = default_charset(&config).len() as f64;
+
+        // 32000 draws put the standard error near 0.25 percentage points
+        for class in [
+            CharClass::Uppercase,
+            CharClass::Lowercase,
+            CharClass::Number,
+            CharClass::Symbol,
+        ] {
+            let share = histogram[&class] as f64 / total;
+            let expected = pool[&class] as f64 / pool_size;
+            assert!(
+                (share - expected).abs() < 0.02,
+                "{:?}: {} vs {}",
+                class,
+                share,
+                expected
+            );
+        }
+        assert!(!histogram.contains_key(&CharClass::Other));
+    }
+
+    #[test]
+    fn test_class_weights_bias_the_draw() {
+        let config = PasswordConfig {
+            class_weights: ClassWeights {
+                lowercase: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let passwords: Vec<String> = (0..500)
+            .map(|_| generate_password(&config).unwrap())
+            .collect();
+        let histogram = char_class_histogram(&passwords);
+        let share = histogram[&CharClass::Lowercase] as f64 / (500 * config.length) as f64;
+
+        // 260 of the 322 weighted pool entries, against 26 of 88 unweighted
+        let expected = class_probabilities(&config)[&CharClass::Lowercase];
+        assert!((expected - 260.0 / 322.0).abs() < 1e-9);
+        assert!((share - expected).abs() < 0.03, "{} vs {}", share, expected);
+        assert!(share > 0.6);
+        // Other classes still appear
+        assert!(histogram[&CharClass::Symbol] > 0);
+    }
+
+    #[test]
+    fn test_class_probabilities_default_to_pool_composition() {
+        let config = PasswordConfig {
+            symbols: false,
+            ..Default::default()
+        };
+        let probabilities = class_probabilities(&config);
+        assert_eq!(probabilities.len(), 3);
+        assert!((probabilities[&CharClass::Number] - 10.0 / 62.0).abs() < 1e-9);
+        assert!((probabilities.values().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_class_weights() {
+        let weights = parse_class_weights("lowercase=3, Symbols=2").unwrap();
+        assert_eq!(
+            (
+                weights.uppercase,
+                weights.lowercase,
+                weights.numbers,
+                weights.symbols
+            ),
+            (1, 3, 1, 2)
+        );
+        assert_eq!(parse_class_weights("").unwrap(), ClassWeights::default());
+
+        for bad in ["lowercase", "vowels=2", "numbers=-1", "numbers=x"] {
+            assert!(
+                matches!(
+                    parse_class_weights(bad),
+                    Err(PasswordError::InvalidClassWeights(_))
+                ),
+                "{}",
+                bad
+            );
+        }
+
+        let config = PasswordConfig {
+            class_weights: parse_class_weights("symbols=0").unwrap(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(PasswordError::InvalidClassWeights(_))
+        ));
+
+        // 26 lowercase letters at this weight overflow the total
+        let config = PasswordConfig {
+            class_weights: parse_class_weights("lowercase=4000000000").unwrap(),
+            ..Default::default()
+        };
+        let err = generate_password(&config).unwrap_err();
+        assert!(
+            matches!(&err, PasswordError::InvalidClassWeights(msg) if msg.contains("add up")),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_from_policy() {
+        let pin = PasswordConfig::from_policy("pin").unwrap();
+        assert_eq!(pin.length, 6);
+        assert!(pin.numbers && !pin.uppercase && !pin.lowercase && !pin.symbols);
+        assert!(generate_password(&pin)
+            .unwrap()
+            .chars()
+            .all(|c| c.is_ascii_digit()));
+
+        let nist = PasswordConfig::from_policy("nist").unwrap();
+        assert_eq!(nist.length, 15);
+        assert!(nist.uppercase && nist.lowercase && nist.numbers && nist.symbols);
+
+        let strong = PasswordConfig::from_policy("strong").unwrap();
+        assert_eq!(strong.length, 32);
+        assert!(strong.uppercase && strong.lowercase && strong.numbers && strong.symbols);
+        assert_eq!(strong.reject_keyboard_runs, Some(4));
+
+        let pci = PasswordConfig::from_policy("PCI").unwrap();
+        assert_eq!(pci.length, 12);
+        assert!(pci.uppercase && pci.lowercase && pci.numbers && !pci.symbols);
+
+        for name in PASSWORD_POLICIES {
+            let config = PasswordConfig::from_policy(name).unwrap();
+            assert!(config.validate().is_ok(), "{}", name);
+            assert_eq!(config.max_length, Some(MAX_PASSWORD_LENGTH));
+            assert!(!config.exclude_ambiguous && config.exclude_chars.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_unknown_policy() {
+        let err = PasswordConfig::from_policy("hipaa").unwrap_err();
+        assert!(matches!(&err, PasswordError::UnknownPolicy(name) if name == "hipaa"));
+        assert!(err.to_string().contains("pin, nist, strong, pci"));
+    }
+
+    #[test]
+    fn test_passphrase_words_come_from_language_list() {
+        for &lang in available_wordlists() {
+            let list = wordlist_for(lang).unwrap();
+            let config = PassphraseConfig {
+                words: 6,
+                separator: "·".to_string(),
+                lang: lang.to_string(),
+                ..Default::default()
+            };
+            for _ in 0..50 {
+                let passphrase = generate_passphrase(&config).unwrap();
+                let words: Vec<&str> = passphrase.split('·').collect();
+                assert_eq!(words.len(), 6, "{}", passphrase);
+                assert!(
+                    words.iter().all(|w| list.contains(w)),
+                    "{}: {}",
+                    lang,
+                    passphrase
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_wordlists_are_clean() {
+        for &lang in available_wordlists() {
+            let list = wordlist_for(lang).unwrap();
+            assert!(list.len() >= 128, "{}", lang);
+            let unique: std::collections::HashSet<_> = list.iter().collect();
+            assert_eq!(unique.len(), list.len(), "{} has duplicates", lang);
+            assert!(
+                list.iter().all(|w| w.chars().all(char::is_lowercase)),
+                "{}",
+                lang
+            );
+        }
+    }
+
+    #[test]
+    fn test_passphrase_capitalizes_accented_words() {
+        let config = PassphraseConfig {
+            words: 1,
+            capitalize: true,
+            lang: "fr".to_string(),
+            ..Default::default()
+        };
+        let list = wordlist_for("fr").unwrap();
+        let mut seen_accented = false;
+        for _ in 0..2000 {
+            let word = generate_passphrase(&config).unwrap();
+            let mut chars = word.chars();
+            let first = chars.next().unwrap();
+            assert!(first.is_uppercase(), "{}", word);
+            let lowered: String = first.to_lowercase().chain(chars).collect();
+            assert!(list.contains(&lowered.as_str()), "{}", word);
+            seen_accented |= !first.is_ascii();
+        }
+        // "âne", "écharpe", "île" and friends
+        assert!(seen_accented);
+    }
+
+    #[test]
+    fn test_passphrase_rejects_bad_config() {
+        let unknown = PassphraseConfig {
+            lang: "xx".to_string(),
+            ..Default::default()
+        };
+        let err = generate_passphrase(&unknown).unwrap_err();
+        assert!(matches!(&err, PasswordError::UnknownLanguage(lang) if lang == "xx"));
+        assert!(err.to_string().contains("en, de, fr, es"));
+
+        let empty = PassphraseConfig {
+            words: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            generate_passphrase(&empty),
+            Err(PasswordError::NoWords)
+        ));
+    }
 }
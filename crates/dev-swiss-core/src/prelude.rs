@@ -0,0 +1,26 @@
+//! The items most programs need, in one import
+//!
+//! ```
+//! use dev_swiss_core::prelude::*;
+//!
+//! fn main() -> Result<(), CoreError> {
+//!     let password = generate_password(&PasswordConfig::default())?;
+//!     assert_eq!(password.chars().count(), PasswordConfig::default().length);
+//!
+//!     let code = generate_qr(&QrConfig {
+//!         content: "https://example.com".to_string(),
+//!         ..Default::default()
+//!     })?;
+//!     assert!(code.width() > 0);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Everything here is also exported from the crate root.
+
+pub use crate::error::CoreError;
+pub use crate::password::{generate_password, PasswordConfig};
+pub use crate::qrcode::{generate_qr, ImageConfig, QrConfig};
+
+#[cfg(feature = "convert")]
+pub use crate::convert::{convert, ConvertConfig};
@@ -1,25 +1,24 @@
 use qrcode::render::unicode;
-use qrcode::{EcLevel, QrCode};
+use qrcode::EcLevel;
+pub use qrcode::QrCode;
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[cfg(feature = "image-output")]
-use image::{DynamicImage, GenericImageView, Rgb};
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage, Rgba, RgbaImage};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Width of the quiet zone the renderers add on each side, in modules
+pub const QUIET_ZONE_MODULES: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ErrorCorrectionLevel {
     Low,
+    #[default]
     Medium,
     Quartile,
     High,
 }
 
-impl Default for ErrorCorrectionLevel {
-    fn default() -> Self {
-        Self::Medium
-    }
-}
-
 impl ErrorCorrectionLevel {
     fn to_qrcode_level(self) -> EcLevel {
         match self {
@@ -31,25 +30,89 @@ impl ErrorCorrectionLevel {
     }
 }
 
+/// Where a code will be scanned, for picking defaults without knowing the
+/// error correction levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UseCase {
+    /// Printed on paper, flyers or packaging
+    Print,
+    /// Shown on a display, where nothing smudges or tears
+    Screen,
+    /// Labels on equipment and parts that get dirty or scratched
+    Industrial,
+}
+
+/// Error correction level and quiet zone width (in modules) for `use_case`
+pub fn ec_for_use_case(use_case: UseCase) -> (ErrorCorrectionLevel, u32) {
+    match use_case {
+        UseCase::Print => (ErrorCorrectionLevel::Quartile, QUIET_ZONE_MODULES),
+        UseCase::Screen => (ErrorCorrectionLevel::Low, 2),
+        UseCase::Industrial => (ErrorCorrectionLevel::High, 8),
+    }
+}
+
+/// QR data encoding modes, from most to least compact per character
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Digits 0-9
+    Numeric,
+    /// Uppercase A-Z, digits, space and `$%*+-./:`
+    Alphanumeric,
+    /// Arbitrary bytes (UTF-8 text, URLs)
+    Byte,
+    /// Shift-JIS double-byte characters
+    Kanji,
+}
+
+impl Mode {
+    fn to_qrcode_mode(self) -> qrcode::types::Mode {
+        match self {
+            Mode::Numeric => qrcode::types::Mode::Numeric,
+            Mode::Alphanumeric => qrcode::types::Mode::Alphanumeric,
+            Mode::Byte => qrcode::types::Mode::Byte,
+            Mode::Kanji => qrcode::types::Mode::Kanji,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum OutputFormat {
+    #[default]
     Terminal,
+    Unicode,
     Png,
     Svg,
+    Pdf,
 }
 
-impl Default for OutputFormat {
-    fn default() -> Self {
-        Self::Terminal
+impl OutputFormat {
+    /// File extension for the formats that are saved to a file
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            OutputFormat::Terminal | OutputFormat::Unicode => None,
+            OutputFormat::Png => Some("png"),
+            OutputFormat::Svg => Some("svg"),
+            OutputFormat::Pdf => Some("pdf"),
+        }
     }
 }
 
+/// Every format that is saved to a file, in the order `--all-formats` writes them
+pub const FILE_OUTPUT_FORMATS: [OutputFormat; 3] =
+    [OutputFormat::Png, OutputFormat::Svg, OutputFormat::Pdf];
+
 #[derive(Debug, Clone)]
 pub struct QrConfig {
     pub content: String,
     pub error_correction: ErrorCorrectionLevel,
     pub quiet_zone: bool,
     pub invert: bool,
+    /// Pin the version (1-40, 21 + 4 modules per step) instead of using the
+    /// smallest that fits
+    pub version: Option<u8>,
+    /// Prefer a Micro QR code (M1-M4, 11-17 modules wide) when no version is
+    /// pinned, falling back to a standard code if the content doesn't fit
+    pub micro: bool,
 }
 
 impl Default for QrConfig {
@@ -59,6 +122,8 @@ impl Default for QrConfig {
             error_correction: ErrorCorrectionLevel::Medium,
             quiet_zone: true,
             invert: false,
+            version: None,
+            micro: false,
         }
     }
 }
@@ -68,6 +133,20 @@ pub struct ImageConfig {
     pub scale: u32,
     pub dark_color: [u8; 3],
     pub light_color: [u8; 3],
+    /// In SVG output, mark shapes with `class="qr-dark"`/`class="qr-light"`
+    /// instead of inline fills so the embedding page's CSS sets the colors
+    pub svg_use_classes: bool,
+    /// Colors for dark modules in SVG output, assigned by region according
+    /// to `svg_palette_mode`; empty draws every dark module in `dark_color`
+    pub svg_palette: Vec<[u8; 3]>,
+    pub svg_palette_mode: PaletteMode,
+    /// RGBA color of the quiet zone in images, when it should differ from
+    /// `light_color` (e.g. transparent); `None` uses `light_color`
+    pub quiet_zone_color: Option<[u8; 4]>,
+    /// Applied to the finished raster image just before it is saved; see
+    /// [`finish_image`]
+    #[cfg(feature = "image-output")]
+    pub post_process: Option<PostProcess>,
 }
 
 impl Default for ImageConfig {
@@ -76,21 +155,119 @@ impl Default for ImageConfig {
             scale: 8,
             dark_color: [0, 0, 0],       // black
             light_color: [255, 255, 255], // white
+            svg_use_classes: false,
+            svg_palette: Vec::new(),
+            svg_palette_mode: PaletteMode::default(),
+            quiet_zone_color: None,
+            #[cfg(feature = "image-output")]
+            post_process: None,
+        }
+    }
+}
+
+/// A caller-supplied step run on a rendered image before it is saved, for
+/// borders, shadows, watermarks and the like; an error stops the save
+#[cfg(feature = "image-output")]
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub struct PostProcess(
+    std::sync::Arc<dyn Fn(DynamicImage) -> Result<DynamicImage, QrError> + Send + Sync>,
+);
+
+#[cfg(feature = "image-output")]
+impl PostProcess {
+    pub fn new(
+        f: impl Fn(DynamicImage) -> Result<DynamicImage, QrError> + Send + Sync + 'static,
+    ) -> Self {
+        Self(std::sync::Arc::new(f))
+    }
+}
+
+#[cfg(feature = "image-output")]
+impl fmt::Debug for PostProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PostProcess(..)")
+    }
+}
+
+/// Options for `render_to_unicode_string`
+#[derive(Debug, Clone, Default)]
+pub struct UnicodeConfig {
+    /// Wrap the output in a ``` code fence so chat apps keep it monospaced
+    pub fence: bool,
+    /// Two full blocks per module, one row per line, for fonts whose cells
+    /// aren't twice as tall as they are wide
+    pub double_width: bool,
+    /// Draw light modules as blocks, for dark chat themes
+    pub invert: bool,
+}
+
+/// Options for `render_to_pbm`
+#[derive(Debug, Clone)]
+pub struct PbmConfig {
+    /// Pixels per module
+    pub scale: u32,
+    /// Width of the light border on each side, in modules
+    pub quiet_zone: u32,
+}
+
+impl Default for PbmConfig {
+    fn default() -> Self {
+        Self {
+            scale: 1,
+            quiet_zone: QUIET_ZONE_MODULES,
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct LogoConfig {
-    pub path: String,
+    pub path: PathBuf,
     pub size_percent: u8,
+    /// Put a white rounded backing behind a logo with transparent areas, so
+    /// modules don't show through it
+    pub backing: bool,
 }
 
 impl Default for LogoConfig {
     fn default() -> Self {
         Self {
-            path: String::new(),
+            path: PathBuf::new(),
             size_percent: 20,
+            backing: false,
+        }
+    }
+}
+
+/// How a background image is fitted around the code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackgroundFit {
+    /// Keep the background's size and fit the largest code in its middle
+    #[default]
+    Center,
+    /// Repeat the background to fill a canvas sized to the code
+    Tile,
+    /// Stretch the background to a canvas sized to the code
+    Stretch,
+    /// Scale the background to cover a canvas sized to the code, keeping its
+    /// aspect ratio and cropping the overflow
+    Scale,
+}
+
+#[derive(Debug, Clone)]
+pub struct BackgroundConfig {
+    pub path: PathBuf,
+    pub fit: BackgroundFit,
+    /// Enlarge a `Center` background that is too small instead of failing
+    pub upscale: bool,
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::new(),
+            fit: BackgroundFit::Center,
+            upscale: false,
         }
     }
 }
@@ -106,6 +283,33 @@ pub enum QrError {
     IoError(String),
     InvalidColor(String),
     BackgroundTooSmall,
+    InvalidAiConfig(String),
+    InvalidUtf8(String),
+    AiTimeout(std::time::Duration),
+    AiApiError {
+        status: u16,
+        body: String,
+    },
+    /// HTTP 429, with the server's `Retry-After` when it sent one in seconds
+    AiRateLimited {
+        retry_after: Option<std::time::Duration>,
+    },
+    InvalidSheet(String),
+    TooWide {
+        width: usize,
+        max_width: usize,
+    },
+    InvalidRange(String),
+    OutputExists(std::path::PathBuf),
+    InvalidEnvReference(String),
+    NoCodeFound,
+    InvalidCompressedContent(String),
+    /// The payload has no [`CRC_DELIMITER`](content::CRC_DELIMITER) suffix
+    MissingCrc,
+    CrcMismatch {
+        expected: u32,
+        actual: u32,
+    },
 }
 
 impl fmt::Display for QrError {
@@ -138,6 +342,69 @@ impl fmt::Display for QrError {
             QrError::BackgroundTooSmall => {
                 write!(f, "Background image is too small for QR code")
             }
+            QrError::InvalidAiConfig(msg) => {
+                write!(f, "Invalid AI configuration: {}", msg)
+            }
+            QrError::InvalidUtf8(path) => {
+                write!(f, "Content file is not valid UTF-8: {}", path)
+            }
+            QrError::AiTimeout(timeout) => {
+                write!(
+                    f,
+                    "AI request timed out after {:.1}s",
+                    timeout.as_secs_f32()
+                )
+            }
+            QrError::AiApiError { status, body } => {
+                write!(f, "AI API error {}: {}", status, body)
+            }
+            QrError::AiRateLimited {
+                retry_after: Some(delay),
+            } => {
+                write!(
+                    f,
+                    "AI API rate limit reached; retry after {}s",
+                    delay.as_secs()
+                )
+            }
+            QrError::AiRateLimited { retry_after: None } => {
+                write!(f, "AI API rate limit reached")
+            }
+            QrError::InvalidSheet(msg) => {
+                write!(f, "Invalid sheet layout: {}", msg)
+            }
+            QrError::TooWide { width, max_width } => {
+                write!(
+                    f,
+                    "QR code is {} columns wide, more than the {} available",
+                    width, max_width
+                )
+            }
+            QrError::InvalidRange(msg) => {
+                write!(f, "Invalid range: {}", msg)
+            }
+            QrError::OutputExists(path) => {
+                write!(f, "Output file already exists: {}", path.display())
+            }
+            QrError::InvalidEnvReference(msg) => {
+                write!(f, "Invalid environment variable reference: {}", msg)
+            }
+            QrError::NoCodeFound => {
+                write!(f, "No readable QR code found in the image")
+            }
+            QrError::InvalidCompressedContent(msg) => {
+                write!(f, "Invalid compressed content: {}", msg)
+            }
+            QrError::MissingCrc => {
+                write!(f, "Content has no CRC32 checksum")
+            }
+            QrError::CrcMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "CRC32 mismatch: content says {:08X} but hashes to {:08X}; it was corrupted or misread",
+                    expected, actual
+                )
+            }
         }
     }
 }
@@ -145,19 +412,268 @@ impl fmt::Display for QrError {
 impl std::error::Error for QrError {}
 
 pub fn generate_qr(config: &QrConfig) -> Result<QrCode, QrError> {
-    if config.content.is_empty() {
+    generate_qr_bytes(config.content.as_bytes(), config)
+}
+
+/// Encode raw bytes, using `config` for everything except `content`
+pub fn generate_qr_bytes(data: &[u8], config: &QrConfig) -> Result<QrCode, QrError> {
+    if data.is_empty() {
         return Err(QrError::EmptyContent);
     }
 
+    if config.micro && config.version.is_none() {
+        if let Ok(qr) = generate_micro_qr_bytes(data, config) {
+            return Ok(qr);
+        }
+    }
+
     let ec_level = config.error_correction.to_qrcode_level();
 
-    QrCode::with_error_correction_level(&config.content, ec_level).map_err(|e| {
-        if e.to_string().contains("data too long") {
-            QrError::ContentTooLarge
-        } else {
-            QrError::EncodingFailed(e.to_string())
+    let result = match config.version {
+        Some(version) => {
+            QrCode::with_version(data, qrcode::Version::Normal(version as i16), ec_level)
         }
-    })
+        None => QrCode::with_error_correction_level(data, ec_level),
+    };
+
+    result.map_err(encoding_error)
+}
+
+/// Encode `config.content` as the smallest Micro QR code (M1-M4) that holds it
+///
+/// Micro codes support at most quartile error correction (M4) and hold up to
+/// 35 digits or 15 bytes, so larger content fails with `ContentTooLarge`.
+/// `config.version` is ignored.
+pub fn generate_micro_qr(config: &QrConfig) -> Result<QrCode, QrError> {
+    generate_micro_qr_bytes(config.content.as_bytes(), config)
+}
+
+fn generate_micro_qr_bytes(data: &[u8], config: &QrConfig) -> Result<QrCode, QrError> {
+    if data.is_empty() {
+        return Err(QrError::EmptyContent);
+    }
+
+    let ec_level = config.error_correction.to_qrcode_level();
+    let mut last_error = None;
+    for version in 1..=4 {
+        match QrCode::with_version(data, qrcode::Version::Micro(version), ec_level) {
+            Ok(qr) => return Ok(qr),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    // M4 is the most permissive, so its error is the one worth reporting
+    Err(last_error
+        .map(encoding_error)
+        .unwrap_or(QrError::ContentTooLarge))
+}
+
+fn encoding_error(e: qrcode::types::QrError) -> QrError {
+    if e.to_string().contains("data too long") {
+        QrError::ContentTooLarge
+    } else {
+        QrError::EncodingFailed(e.to_string())
+    }
+}
+
+/// Whether `content` fits in a code of `max_version` (1-40) or smaller at
+/// error correction `ec`
+///
+/// Versions outside 1-40 never fit.
+pub fn fits(content: impl AsRef<[u8]>, ec: ErrorCorrectionLevel, max_version: u8) -> bool {
+    let content = content.as_ref();
+    (1..=40).contains(&max_version)
+        && !content.is_empty()
+        && QrCode::with_version(
+            content,
+            qrcode::Version::Normal(max_version as i16),
+            ec.to_qrcode_level(),
+        )
+        .is_ok()
+}
+
+/// The strongest error correction at which `content` still fits in
+/// `max_version` or smaller, or `None` if it doesn't fit even at `Low`
+pub fn best_error_correction(
+    content: impl AsRef<[u8]>,
+    max_version: u8,
+) -> Option<ErrorCorrectionLevel> {
+    [
+        ErrorCorrectionLevel::High,
+        ErrorCorrectionLevel::Quartile,
+        ErrorCorrectionLevel::Medium,
+        ErrorCorrectionLevel::Low,
+    ]
+    .into_iter()
+    .find(|&ec| fits(content.as_ref(), ec, max_version))
+}
+
+/// Maximum number of characters (bytes in `Byte` mode) a single-segment code
+/// of the given version (1-40) and error correction level can hold.
+///
+/// Returns 0 for versions outside 1-40.
+pub fn capacity(version: u8, ec: ErrorCorrectionLevel, mode: Mode) -> usize {
+    if !(1..=40).contains(&version) {
+        return 0;
+    }
+    version_capacity(qrcode::Version::Normal(version as i16), ec, mode)
+}
+
+/// Like [`capacity`], for any standard or Micro version
+fn version_capacity(version: qrcode::Version, ec: ErrorCorrectionLevel, mode: Mode) -> usize {
+    // M1 only holds digits and M2 adds alphanumerics
+    match (version, mode) {
+        (qrcode::Version::Micro(1), Mode::Numeric) => {}
+        (qrcode::Version::Micro(1), _) | (qrcode::Version::Micro(2), Mode::Byte | Mode::Kanji) => {
+            return 0
+        }
+        _ => {}
+    }
+    let data_bits = match qrcode::bits::Bits::new(version).max_len(ec.to_qrcode_level()) {
+        Ok(bits) => bits,
+        Err(_) => return 0,
+    };
+
+    let qr_mode = mode.to_qrcode_mode();
+    let length_bits = qr_mode.length_bits_count(version);
+    let available = data_bits.saturating_sub(version.mode_bits_count() + length_bits);
+
+    let chars = match mode {
+        // 3 digits per 10 bits, with 4/7-bit tails for 1/2 leftover digits
+        Mode::Numeric => available / 10 * 3 + [0, 0, 0, 0, 1, 1, 1, 2, 2, 2][available % 10],
+        // 2 characters per 11 bits, with a 6-bit tail for a leftover one
+        Mode::Alphanumeric => available / 11 * 2 + usize::from(available % 11 >= 6),
+        Mode::Byte => available / 8,
+        Mode::Kanji => available / 13,
+    };
+
+    chars.min((1 << length_bits) - 1)
+}
+
+/// Size and capacity summary of a generated code
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct QrInfo {
+    /// Version number (1-40, or 1-4 for Micro codes)
+    pub version: u8,
+    pub micro: bool,
+    /// Width in modules, without the quiet zone
+    pub modules: usize,
+    /// Error correction level: `L`, `M`, `Q` or `H`
+    pub ec: char,
+    /// Bytes of content encoded
+    pub data_bytes: usize,
+    /// Bytes this version and level hold in byte mode
+    pub capacity_bytes: usize,
+    /// Bytes of byte-mode content that could still be added
+    pub remaining_bytes: usize,
+}
+
+#[cfg(feature = "json")]
+impl QrInfo {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("QrInfo always serializes")
+    }
+}
+
+/// Describe `qr`, which was generated from `data`
+pub fn qr_info(qr: &QrCode, data: &[u8]) -> QrInfo {
+    let version = qr.version();
+    let (ec, letter) = match qr.error_correction_level() {
+        EcLevel::L => (ErrorCorrectionLevel::Low, 'L'),
+        EcLevel::M => (ErrorCorrectionLevel::Medium, 'M'),
+        EcLevel::Q => (ErrorCorrectionLevel::Quartile, 'Q'),
+        EcLevel::H => (ErrorCorrectionLevel::High, 'H'),
+    };
+    let capacity_bytes = version_capacity(version, ec, Mode::Byte);
+    let number = match version {
+        qrcode::Version::Normal(v) | qrcode::Version::Micro(v) => v as u8,
+    };
+    QrInfo {
+        version: number,
+        micro: version.is_micro(),
+        modules: qr.width(),
+        ec: letter,
+        data_bytes: data.len(),
+        capacity_bytes,
+        remaining_bytes: capacity_bytes.saturating_sub(data.len()),
+    }
+}
+
+/// Filesystem-safe slug of QR content, e.g. `https://example.com` becomes
+/// `https-example-com`. Uses [`slugify`](crate::slugify) without
+/// transliteration, capped at 64 characters, falling back to `qrcode`.
+pub fn content_slug(content: &str) -> String {
+    let options = crate::SlugOptions {
+        max_len: Some(64),
+        transliterate: false,
+        ..Default::default()
+    };
+    let slug = crate::slugify(content, &options);
+    if slug.is_empty() {
+        "qrcode".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Pick an unused output path in `dir` named after the content slug,
+/// appending `-2`, `-3`, ... when the name is already taken.
+pub fn auto_output_path(dir: &Path, content: &str, extension: &str) -> std::path::PathBuf {
+    let slug = content_slug(content);
+    let mut candidate = dir.join(format!("{}.{}", slug, extension));
+    let mut n = 2;
+    while candidate.exists() {
+        candidate = dir.join(format!("{}-{}.{}", slug, n, extension));
+        n += 1;
+    }
+    candidate
+}
+
+/// Read QR content from a file as UTF-8, keeping newlines intact
+pub fn read_content_file(path: &Path) -> Result<String, QrError> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        QrError::IoError(format!(
+            "Failed to read content file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    String::from_utf8(bytes).map_err(|_| QrError::InvalidUtf8(path.display().to_string()))
+}
+
+/// The code's module matrix, `true` for dark, indexed as `[row][column]`
+///
+/// With `quiet_zone`, the matrix is padded with the same light border the
+/// built-in renderers draw, for custom renderers that want identical framing.
+pub fn qr_modules(qr: &QrCode, quiet_zone: bool) -> Vec<Vec<bool>> {
+    let width = qr.width();
+    let pad = if quiet_zone {
+        QUIET_ZONE_MODULES as usize
+    } else {
+        0
+    };
+    let size = width + pad * 2;
+
+    (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| {
+                    let (mx, my) = (x.wrapping_sub(pad), y.wrapping_sub(pad));
+                    mx < width && my < width && qr[(mx, my)] == qrcode::Color::Dark
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Pixels per module that brings the rendered width closest to `target_px`
+///
+/// The rendered width is `(qr.width() + 2 * quiet_zone_modules) * scale`, so
+/// only multiples of that module count are reachable. Never returns less
+/// than 1, however small the target.
+pub fn scale_for_target_px(qr: &QrCode, target_px: u32, quiet_zone_modules: u32) -> u32 {
+    let modules = qr.width() as u32 + quiet_zone_modules * 2;
+    (target_px.saturating_add(modules / 2) / modules).max(1)
 }
 
 pub fn render_to_terminal(qr: &QrCode, config: &QrConfig) -> String {
@@ -176,38 +692,226 @@ pub fn render_to_terminal(qr: &QrCode, config: &QrConfig) -> String {
     renderer.build()
 }
 
-pub fn parse_color(color: &str) -> Result<[u8; 3], QrError> {
-    let color = color.trim();
-
-    // Handle named colors
-    match color.to_lowercase().as_str() {
-        "black" => return Ok([0, 0, 0]),
-        "white" => return Ok([255, 255, 255]),
-        "red" => return Ok([255, 0, 0]),
-        "green" => return Ok([0, 255, 0]),
-        "blue" => return Ok([0, 0, 255]),
-        _ => {}
+/// `render_to_terminal`, refusing codes wider than `max_width` columns
+///
+/// Huge content otherwise renders as thousands of wrapped columns that can't
+/// be scanned anyway. `None` never refuses.
+pub fn render_to_terminal_checked(
+    qr: &QrCode,
+    config: &QrConfig,
+    max_width: Option<usize>,
+) -> Result<String, QrError> {
+    let quiet_zone = if config.quiet_zone {
+        QUIET_ZONE_MODULES as usize * 2
+    } else {
+        0
+    };
+    let width = qr.width() + quiet_zone;
+    match max_width {
+        Some(max_width) if width > max_width => Err(QrError::TooWide { width, max_width }),
+        _ => Ok(render_to_terminal(qr, config)),
+    }
+}
+
+/// Resize the quiet zone of a `render_to_image` result to `modules` wide,
+/// cropping it or padding it with `config.quiet_zone_color` or, without one,
+/// `config.light_color`
+#[cfg(feature = "image-output")]
+pub fn with_quiet_zone(image: &DynamicImage, modules: u32, config: &ImageConfig) -> DynamicImage {
+    if modules == QUIET_ZONE_MODULES {
+        return image.clone();
+    }
+    let (width, height) = image.dimensions();
+    let shift = (modules as i64 - QUIET_ZONE_MODULES as i64) * config.scale as i64;
+    let (width, height) = (
+        (width as i64 + shift * 2) as u32,
+        (height as i64 + shift * 2) as u32,
+    );
+    match config.quiet_zone_color {
+        Some(color) => {
+            let mut resized = RgbaImage::from_pixel(width, height, Rgba(color));
+            image::imageops::replace(&mut resized, &image.to_rgba8(), shift, shift);
+            DynamicImage::ImageRgba8(resized)
+        }
+        None => {
+            let mut resized = RgbImage::from_pixel(width, height, Rgb(config.light_color));
+            image::imageops::overlay(&mut resized, &image.to_rgb8(), shift, shift);
+            DynamicImage::ImageRgb8(resized)
+        }
     }
+}
+
+/// Render with 24-bit ANSI colors from `image_config`
+///
+/// Each character is an upper half block whose foreground is the top module
+/// and background the bottom one, so the code keeps its colors whatever the
+/// terminal theme. Only use this when stdout is a terminal that accepts
+/// truecolor escapes; otherwise use `render_to_terminal`.
+pub fn render_to_terminal_colored(
+    qr: &QrCode,
+    config: &QrConfig,
+    image_config: &ImageConfig,
+) -> String {
+    let modules = qr_modules(qr, config.quiet_zone);
+    let color = |dark: bool| {
+        if dark != config.invert {
+            image_config.dark_color
+        } else {
+            image_config.light_color
+        }
+    };
+
+    let lines: Vec<String> = modules
+        .chunks(2)
+        .map(|pair| {
+            let mut line = String::new();
+            let mut current = None;
+            for x in 0..pair[0].len() {
+                let top = color(pair[0][x]);
+                // The bottom row past the end is light
+                let bottom = color(pair.get(1).is_some_and(|row| row[x]));
+                if current != Some((top, bottom)) {
+                    line.push_str(&format!(
+                        "\x1b[38;2;{};{};{};48;2;{};{};{}m",
+                        top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+                    ));
+                    current = Some((top, bottom));
+                }
+                line.push('▀');
+            }
+            line.push_str("\x1b[0m");
+            line
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Render as plain block characters that survive copy-paste into chat apps
+///
+/// Unlike `render_to_terminal`, the output never contains escape codes and
+/// only uses `█`, `▀`, `▄` and spaces, which every common monospace font draws.
+/// The quiet zone is always included so the code scans off any background.
+pub fn render_to_unicode_string(qr: &QrCode, config: &UnicodeConfig) -> String {
+    let modules = qr_modules(qr, true);
+    let filled = |dark: bool| dark != config.invert;
+
+    let mut lines: Vec<String> = if config.double_width {
+        modules
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&dark| if filled(dark) { "██" } else { "  " })
+                    .collect()
+            })
+            .collect()
+    } else {
+        // Two module rows per line; the bottom row past the end is light
+        modules
+            .chunks(2)
+            .map(|pair| {
+                (0..pair[0].len())
+                    .map(|x| {
+                        let top = filled(pair[0][x]);
+                        let bottom = pair.get(1).map_or(config.invert, |row| filled(row[x]));
+                        match (top, bottom) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    };
 
-    // Handle hex colors
-    let hex = color.strip_prefix('#').unwrap_or(color);
+    if config.fence {
+        lines.insert(0, "```".to_string());
+        lines.push("```".to_string());
+    }
+    lines.join("\n")
+}
 
-    if hex.len() != 6 {
-        return Err(QrError::InvalidColor(color.to_string()));
+/// Render as a plain (`P1`) PBM bitmap, `1` for each dark pixel
+///
+/// Pixels are written without separators, each image row on its own lines
+/// wrapped at 70 characters, the format's line length limit.
+pub fn render_to_pbm(qr: &QrCode, config: &PbmConfig) -> String {
+    let width = qr.width();
+    let scale = config.scale.max(1) as usize;
+    let pad = config.quiet_zone as usize;
+    let size = (width + pad * 2) * scale;
+
+    let mut pbm = format!("P1\n{} {}\n", size, size);
+    for py in 0..size {
+        let row: Vec<char> = (0..size)
+            .map(|px| {
+                let (mx, my) = (
+                    (px / scale).wrapping_sub(pad),
+                    (py / scale).wrapping_sub(pad),
+                );
+                let dark = mx < width && my < width && qr[(mx, my)] == qrcode::Color::Dark;
+                if dark {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect();
+        for chunk in row.chunks(70) {
+            pbm.extend(chunk);
+            pbm.push('\n');
+        }
     }
+    pbm
+}
 
-    let r = u8::from_str_radix(&hex[0..2], 16)
-        .map_err(|_| QrError::InvalidColor(color.to_string()))?;
-    let g = u8::from_str_radix(&hex[2..4], 16)
-        .map_err(|_| QrError::InvalidColor(color.to_string()))?;
-    let b = u8::from_str_radix(&hex[4..6], 16)
-        .map_err(|_| QrError::InvalidColor(color.to_string()))?;
+/// Lowest WCAG contrast ratio between dark and light modules that phone
+/// scanners read reliably
+pub const MIN_QR_CONTRAST: f64 = 3.0;
+
+/// A warning when `dark` and `light` are too close for scanners to tell apart
+pub fn contrast_warning(dark: [u8; 3], light: [u8; 3]) -> Option<String> {
+    let ratio = crate::color::contrast_ratio(dark, light);
+    (ratio < MIN_QR_CONTRAST).then(|| {
+        format!(
+            "Dark and light colors have a contrast ratio of {:.1}:1; scanners need at least {:.0}:1",
+            ratio, MIN_QR_CONTRAST
+        )
+    })
+}
 
-    Ok([r, g, b])
+/// Parse a QR color: hex, `rgb(...)` or a CSS color name
+///
+/// `green` stays the pure `#00ff00` it was before CSS names were accepted,
+/// so existing codes keep their colors; CSS's darker green is `#008000`.
+pub fn parse_color(color: &str) -> Result<[u8; 3], QrError> {
+    if color.trim().eq_ignore_ascii_case("green") {
+        return Ok([0, 255, 0]);
+    }
+    crate::color::parse_css_color(color)
+        .map_err(|_| QrError::InvalidColor(color.trim().to_string()))
 }
 
 #[cfg(feature = "image-output")]
 pub fn render_to_image(qr: &QrCode, config: &ImageConfig) -> DynamicImage {
+    if let Some(quiet_zone_color) = config.quiet_zone_color {
+        let opaque = |[r, g, b]: [u8; 3]| Rgba([r, g, b, 255]);
+        let (start, end) = (
+            QUIET_ZONE_MODULES as usize,
+            QUIET_ZONE_MODULES as usize + qr.width(),
+        );
+        return render_to_image_with(qr, config.scale, |x, y, dark| {
+            if !(start..end).contains(&x) || !(start..end).contains(&y) {
+                Rgba(quiet_zone_color)
+            } else if dark {
+                opaque(config.dark_color)
+            } else {
+                opaque(config.light_color)
+            }
+        });
+    }
+
     let dark = Rgb(config.dark_color);
     let light = Rgb(config.light_color);
 
@@ -222,16 +926,126 @@ pub fn render_to_image(qr: &QrCode, config: &ImageConfig) -> DynamicImage {
     DynamicImage::ImageRgb8(image)
 }
 
+/// Render with a per-module color chosen by `color(x, y, dark)`
+///
+/// Coordinates count modules from the top-left of the quiet zone, which is
+/// always included (its modules are light), so the image matches
+/// `render_to_image` in size. Each module is `scale` pixels square.
+#[cfg(feature = "image-output")]
+pub fn render_to_image_with<F>(qr: &QrCode, scale: u32, color: F) -> DynamicImage
+where
+    F: Fn(usize, usize, bool) -> Rgba<u8>,
+{
+    let modules = qr_modules(qr, true);
+    let size = modules.len() as u32 * scale;
+
+    let image = RgbaImage::from_fn(size, size, |px, py| {
+        let (x, y) = ((px / scale) as usize, (py / scale) as usize);
+        color(x, y, modules[y][x])
+    });
+
+    DynamicImage::ImageRgba8(image)
+}
+
+/// Center the logo on the code
+///
+/// Returns warnings about the logo itself, such as a missing transparent
+/// background; see [`logo_warning`].
+#[cfg(feature = "image-output")]
+pub fn overlay_logo(
+    qr_image: &mut DynamicImage,
+    logo_config: &LogoConfig,
+) -> Result<Vec<String>, QrError> {
+    let region = qr_image.dimensions();
+    place_logo(qr_image, logo_config, (0, 0), region)
+}
+
+/// Where [`overlay_on_background_with_logo`] put the code and logo
+#[cfg(feature = "image-output")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogoPlacement {
+    /// The code's top-left corner in the image
+    pub origin: (u32, u32),
+    /// Pixels per module
+    pub scale: u32,
+    /// Warnings about the logo itself, see [`logo_warning`]
+    pub warnings: Vec<String>,
+}
+
+/// Warn about a logo without transparency
+///
+/// An opaque logo hides every module under its bounding box, including the
+/// corners around a round or irregular shape.
+#[cfg(feature = "image-output")]
+pub fn logo_warning(logo: &DynamicImage) -> Option<String> {
+    if has_transparency(logo) {
+        return None;
+    }
+    Some(
+        "The logo has no transparent areas and hides every module behind it; a PNG with a transparent background \
+         covers less of the code"
+            .to_string(),
+    )
+}
+
+#[cfg(feature = "image-output")]
+fn has_transparency(image: &DynamicImage) -> bool {
+    image.color().has_alpha() && image.pixels().any(|(_, _, pixel)| pixel[3] < 255)
+}
+
+/// Fill a rectangle with rounded corners of `radius` pixels
+#[cfg(feature = "image-output")]
+fn fill_rounded_rect(
+    image: &mut DynamicImage,
+    origin: (u32, u32),
+    size: (u32, u32),
+    radius: u32,
+    color: Rgba<u8>,
+) {
+    let radius = radius.min(size.0 / 2).min(size.1 / 2) as i64;
+    let (width, height) = (size.0 as i64, size.1 as i64);
+    for dy in 0..height {
+        for dx in 0..width {
+            // Distance into the nearest corner's square, if in one
+            let cx = if dx < radius {
+                radius - dx
+            } else {
+                (dx - (width - 1 - radius)).max(0)
+            };
+            let cy = if dy < radius {
+                radius - dy
+            } else {
+                (dy - (height - 1 - radius)).max(0)
+            };
+            if cx * cx + cy * cy > radius * radius {
+                continue;
+            }
+            let (x, y) = (origin.0 + dx as u32, origin.1 + dy as u32);
+            if x < image.width() && y < image.height() {
+                image::GenericImage::put_pixel(image, x, y, color);
+            }
+        }
+    }
+}
+
+/// Center `logo_config`'s logo on the `size` region of `image` at `origin`,
+/// sized relative to the region's width
 #[cfg(feature = "image-output")]
-pub fn overlay_logo(qr_image: &mut DynamicImage, logo_config: &LogoConfig) -> Result<(), QrError> {
+fn place_logo(
+    image: &mut DynamicImage,
+    logo_config: &LogoConfig,
+    origin: (u32, u32),
+    size: (u32, u32),
+) -> Result<Vec<String>, QrError> {
     if logo_config.size_percent < 5 || logo_config.size_percent > 30 {
         return Err(QrError::LogoTooLarge);
     }
 
-    let logo = image::open(Path::new(&logo_config.path))
-        .map_err(|e| QrError::InvalidLogoPath(format!("{}: {}", logo_config.path, e)))?;
+    let logo = image::open(&logo_config.path)
+        .map_err(|e| QrError::InvalidLogoPath(format!("{}: {}", logo_config.path.display(), e)))?;
+    let warnings: Vec<String> = logo_warning(&logo).into_iter().collect();
 
-    let (qr_width, qr_height) = qr_image.dimensions();
+    let (qr_width, qr_height) = size;
     let max_logo_size = (qr_width as f32 * logo_config.size_percent as f32 / 100.0) as u32;
 
     let logo_resized = logo.resize(
@@ -241,34 +1055,122 @@ pub fn overlay_logo(qr_image: &mut DynamicImage, logo_config: &LogoConfig) -> Re
     );
 
     let (logo_width, logo_height) = logo_resized.dimensions();
-    let x = (qr_width - logo_width) / 2;
-    let y = (qr_height - logo_height) / 2;
+    let x = origin.0 + (qr_width - logo_width) / 2;
+    let y = origin.1 + (qr_height - logo_height) / 2;
+
+    // An opaque logo already covers its whole box, so only a transparent one
+    // needs the backing
+    if logo_config.backing && warnings.is_empty() {
+        let pad = (logo_width.max(logo_height) / 10).max(2);
+        fill_rounded_rect(
+            image,
+            (x.saturating_sub(pad), y.saturating_sub(pad)),
+            (logo_width + pad * 2, logo_height + pad * 2),
+            pad * 2,
+            Rgba([255, 255, 255, 255]),
+        );
+    }
 
-    image::imageops::overlay(qr_image, &logo_resized, x as i64, y as i64);
+    image::imageops::overlay(image, &logo_resized, x as i64, y as i64);
 
-    Ok(())
+    Ok(warnings)
 }
 
 #[cfg(feature = "image-output")]
 pub fn overlay_on_background(
     qr: &QrCode,
-    background_path: &str,
+    background_config: &BackgroundConfig,
     image_config: &ImageConfig,
 ) -> Result<DynamicImage, QrError> {
-    let mut background = image::open(Path::new(background_path))
-        .map_err(|e| QrError::InvalidLogoPath(format!("{}: {}", background_path, e)))?;
+    let background = image::open(&background_config.path).map_err(|e| {
+        QrError::InvalidLogoPath(format!("{}: {}", background_config.path.display(), e))
+    })?;
 
-    let (bg_width, bg_height) = background.dimensions();
+    compose_on_background(qr, background, background_config, image_config)
+        .map(|(image, _, _)| image)
+}
 
-    // Calculate QR code size to fit in background (with some margin)
-    let qr_modules = qr.width();
-    let margin = 20u32;
-    let available_size = bg_width.min(bg_height).saturating_sub(margin * 2);
-    let scale = available_size / qr_modules as u32;
+/// `overlay_on_background`, then the logo centered on the placed code
+///
+/// Returns the image along with where the code went, for checking the result
+/// with [`is_likely_scannable`].
+#[cfg(feature = "image-output")]
+pub fn overlay_on_background_with_logo(
+    qr: &QrCode,
+    background_config: &BackgroundConfig,
+    logo_config: &LogoConfig,
+    image_config: &ImageConfig,
+) -> Result<(DynamicImage, LogoPlacement), QrError> {
+    let background = image::open(&background_config.path).map_err(|e| {
+        QrError::InvalidLogoPath(format!("{}: {}", background_config.path.display(), e))
+    })?;
+
+    let (mut image, origin, scale) =
+        compose_on_background(qr, background, background_config, image_config)?;
+    let side = (qr.width() as u32 + QUIET_ZONE_MODULES * 2) * scale;
+    let warnings = place_logo(&mut image, logo_config, origin, (side, side))?;
+    Ok((
+        image,
+        LogoPlacement {
+            origin,
+            scale,
+            warnings,
+        },
+    ))
+}
 
-    if scale < 2 {
-        return Err(QrError::BackgroundTooSmall);
-    }
+/// Place the code on `background` per `config.fit`, returning the image, the
+/// code's top-left corner in it and its pixels per module
+#[cfg(feature = "image-output")]
+fn compose_on_background(
+    qr: &QrCode,
+    mut background: DynamicImage,
+    config: &BackgroundConfig,
+    image_config: &ImageConfig,
+) -> Result<(DynamicImage, (u32, u32), u32), QrError> {
+    let qr_modules = qr.width() as u32 + QUIET_ZONE_MODULES * 2;
+    let margin = 20u32;
+    // Canvas side for the modes that size the output to the code
+    let canvas = qr_modules * image_config.scale + margin * 2;
+
+    let (mut background, scale) = match config.fit {
+        BackgroundFit::Center => {
+            // Calculate QR code size to fit in background (with some margin)
+            let (bg_width, bg_height) = background.dimensions();
+            let available_size = bg_width.min(bg_height).saturating_sub(margin * 2);
+            let scale = available_size / qr_modules;
+
+            if scale >= 2 {
+                (background, scale)
+            } else if config.upscale {
+                // Grow the shorter side to fit the code at the requested scale
+                let scale = image_config.scale.max(2);
+                let target = qr_modules * scale + margin * 2;
+                let ratio = target as f32 / bg_width.min(bg_height) as f32;
+                background = background.resize_exact(
+                    (bg_width as f32 * ratio).ceil() as u32,
+                    (bg_height as f32 * ratio).ceil() as u32,
+                    image::imageops::FilterType::Lanczos3,
+                );
+                (background, scale)
+            } else {
+                return Err(QrError::BackgroundTooSmall);
+            }
+        }
+        BackgroundFit::Tile => {
+            let mut tiled = DynamicImage::new_rgba8(canvas, canvas);
+            image::imageops::tile(&mut tiled, &background);
+            (tiled, image_config.scale)
+        }
+        BackgroundFit::Stretch => (
+            background.resize_exact(canvas, canvas, image::imageops::FilterType::Lanczos3),
+            image_config.scale,
+        ),
+        BackgroundFit::Scale => (
+            background.resize_to_fill(canvas, canvas, image::imageops::FilterType::Lanczos3),
+            image_config.scale,
+        ),
+    };
 
     let qr_config = ImageConfig {
         scale,
@@ -276,6 +1178,7 @@ pub fn overlay_on_background(
     };
 
     let qr_image = render_to_image(qr, &qr_config);
+    let (bg_width, bg_height) = background.dimensions();
     let (qr_width, qr_height) = qr_image.dimensions();
 
     // Center QR on background
@@ -284,11 +1187,79 @@ pub fn overlay_on_background(
 
     image::imageops::overlay(&mut background, &qr_image, x as i64, y as i64);
 
-    Ok(background)
+    Ok((background, (x, y), scale))
+}
+
+/// Add a caption band below `image` with `label` centered in it
+///
+/// The band uses the light color and the text the dark color, so the label
+/// matches the code. Labels too wide for the image are shortened with "…".
+#[cfg(feature = "image-output")]
+pub fn add_label(
+    image: &DynamicImage,
+    label: &str,
+    size: f32,
+    config: &ImageConfig,
+) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    let band = text::caption_band_height(size);
+
+    let mut labelled = RgbImage::from_pixel(width, height + band, Rgb(config.light_color));
+    image::imageops::overlay(&mut labelled, &image.to_rgb8(), 0, 0);
+    text::draw_caption(
+        &mut labelled,
+        label,
+        (0, height),
+        width,
+        size,
+        config.dark_color,
+    );
+
+    DynamicImage::ImageRgb8(labelled)
+}
+
+/// Surround `image` with a solid `color` frame `width` pixels wide
+///
+/// Fails when the framed image would be wider or taller than `u32::MAX`.
+#[cfg(feature = "image-output")]
+pub fn add_border(
+    image: &DynamicImage,
+    width: u32,
+    color: [u8; 3],
+) -> Result<DynamicImage, QrError> {
+    let (w, h) = image.dimensions();
+    let grow = |side: u32| width.checked_mul(2).and_then(|both| side.checked_add(both));
+    let (Some(framed_w), Some(framed_h)) = (grow(w), grow(h)) else {
+        return Err(QrError::ImageProcessingFailed(format!(
+            "a {}px border is too wide for a {}x{} image",
+            width, w, h
+        )));
+    };
+    let mut framed = RgbaImage::from_pixel(
+        framed_w,
+        framed_h,
+        Rgba([color[0], color[1], color[2], 255]),
+    );
+    image::imageops::overlay(&mut framed, &image.to_rgba8(), width as i64, width as i64);
+    Ok(DynamicImage::ImageRgba8(framed))
+}
+
+/// Run `config.post_process` on a finished image; without one the image is
+/// returned untouched
+#[cfg(feature = "image-output")]
+pub fn finish_image(image: DynamicImage, config: &ImageConfig) -> Result<DynamicImage, QrError> {
+    match &config.post_process {
+        Some(post_process) => (post_process.0)(image),
+        None => Ok(image),
+    }
 }
 
 #[cfg(feature = "image-output")]
 pub fn render_to_svg(qr: &QrCode, config: &ImageConfig) -> String {
+    if config.svg_use_classes || !config.svg_palette.is_empty() {
+        return render_to_svg_with(qr, config, &SvgOptions::default());
+    }
+
     let dark_hex = format!(
         "#{:02x}{:02x}{:02x}",
         config.dark_color[0], config.dark_color[1], config.dark_color[2]
@@ -305,103 +1276,431 @@ pub fn render_to_svg(qr: &QrCode, config: &ImageConfig) -> String {
         .build()
 }
 
-#[cfg(feature = "image-output")]
-pub fn save_image(image: &DynamicImage, path: &str) -> Result<(), QrError> {
-    image
-        .save(path)
-        .map_err(|e| QrError::IoError(format!("Failed to save image: {}", e)))
+/// How `ImageConfig::svg_palette` colors are spread over a code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaletteMode {
+    /// One color per quadrant, clockwise from the top-left, cycling through
+    /// the palette
+    #[default]
+    Quadrants,
+    /// Concentric bands from the center out, one per palette color
+    Radial,
 }
 
-#[cfg(feature = "ai-generation")]
-pub mod ai {
-    use super::*;
-    use serde::{Deserialize, Serialize};
-
-    #[derive(Debug, Serialize)]
-    struct StabilityRequest {
-        prompt: String,
-        output_format: String,
-        aspect_ratio: String,
+/// Index into a palette of `tones` colors for the module at `(x, y)` of a
+/// code `size` modules wide, quiet zone included
+pub fn palette_tone(mode: PaletteMode, tones: usize, x: usize, y: usize, size: usize) -> usize {
+    if tones == 0 {
+        return 0;
     }
-
-    #[derive(Debug, Deserialize)]
-    struct StabilityResponse {
-        image: String,
+    match mode {
+        PaletteMode::Quadrants => {
+            let (right, bottom) = (x >= size / 2, y >= size / 2);
+            let quadrant = match (right, bottom) {
+                (false, false) => 0,
+                (true, false) => 1,
+                (true, true) => 2,
+                (false, true) => 3,
+            };
+            quadrant % tones
+        }
+        PaletteMode::Radial => {
+            // Distances between module centers, so the bands are symmetric
+            let center = size as f32 / 2.0;
+            let distance = (x as f32 + 0.5 - center).hypot(y as f32 + 0.5 - center);
+            let corner = center * std::f32::consts::SQRT_2;
+            ((distance / corner * tones as f32) as usize).min(tones - 1)
+        }
     }
+}
 
-    pub fn generate_ai_qr(
-        qr: &QrCode,
-        prompt: &str,
-        api_key: &str,
-        image_config: &ImageConfig,
-    ) -> Result<DynamicImage, QrError> {
-        let client = reqwest::blocking::Client::new();
-
-        let full_prompt = format!(
-            "A QR code with artistic styling: {}. The QR code pattern should remain scannable.",
-            prompt
-        );
+/// Options for `render_to_svg_with`
+#[derive(Debug, Clone, Default)]
+pub struct SvgOptions {
+    /// Draw each horizontal run of dark modules as one shape instead of one
+    /// per module, roughly halving the output size
+    pub merge_runs: bool,
+    /// Corner radius as a fraction of the module size (0.0-0.5); rounded
+    /// shapes are emitted as `<rect>` elements with `rx`/`ry`
+    pub corner_radius: f32,
+}
 
-        // Generate AI background using Stability AI
-        let response = client
-            .post("https://api.stability.ai/v2beta/stable-image/generate/core")
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Accept", "application/json")
-            .form(&[
-                ("prompt", full_prompt.as_str()),
-                ("output_format", "png"),
-                ("aspect_ratio", "1:1"),
-            ])
-            .send()
-            .map_err(|e| QrError::ImageProcessingFailed(format!("API request failed: {}", e)))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            return Err(QrError::ImageProcessingFailed(format!(
-                "API error {}: {}",
-                status, body
-            )));
-        }
-
-        let result: StabilityResponse = response
-            .json()
-            .map_err(|e| QrError::ImageProcessingFailed(format!("Failed to parse response: {}", e)))?;
-
-        let image_bytes = base64::Engine::decode(
-            &base64::engine::general_purpose::STANDARD,
-            &result.image,
-        )
-        .map_err(|e| QrError::ImageProcessingFailed(format!("Failed to decode image: {}", e)))?;
+/// Render to SVG with control over how dark modules are drawn
+///
+/// Without rounding, every shape is a subpath of a single `<path>`. Module
+/// size comes from `config.scale` and the quiet zone is always included.
+#[cfg(feature = "image-output")]
+pub fn render_to_svg_with(qr: &QrCode, config: &ImageConfig, options: &SvgOptions) -> String {
+    use std::fmt::Write;
 
-        let mut background = image::load_from_memory(&image_bytes)
-            .map_err(|e| QrError::ImageProcessingFailed(format!("Failed to load image: {}", e)))?;
+    let modules = qr_modules(qr, true);
+    let scale = config.scale;
+    let size = modules.len() as u32 * scale;
 
-        // Overlay QR code on the AI-generated background
-        let (bg_width, bg_height) = background.dimensions();
-        let qr_modules = qr.width();
-        let scale = (bg_width.min(bg_height) - 40) / qr_modules as u32;
+    let palette = &config.svg_palette;
+    let tone = |x: usize, y: usize| {
+        palette_tone(config.svg_palette_mode, palette.len(), x, y, modules.len())
+    };
 
-        let qr_config = ImageConfig {
-            scale,
-            dark_color: [0, 0, 0],
-            light_color: [255, 255, 255],
-        };
+    // (column, row, length, tone) of each dark shape, in modules; merged runs
+    // stop where the tone changes
+    let mut shapes = Vec::new();
+    for (y, row) in modules.iter().enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            if !row[x] {
+                x += 1;
+                continue;
+            }
+            let shape_tone = tone(x, y);
+            let len = if options.merge_runs {
+                (x..row.len())
+                    .take_while(|&i| row[i] && tone(i, y) == shape_tone)
+                    .count()
+            } else {
+                1
+            };
+            shapes.push((x as u32, y as u32, len as u32, shape_tone));
+            x += len;
+        }
+    }
+
+    // Either an inline fill or a class for the page's CSS to style
+    let paint = |class: &str, color: [u8; 3]| {
+        if config.svg_use_classes {
+            format!(r#"class="{}""#, class)
+        } else {
+            format!(r#"fill="{}""#, crate::color::to_hex_color(color))
+        }
+    };
+    // Palette tones also get a numbered class, so CSS can restyle each one
+    let dark = |tone: usize| match palette.get(tone) {
+        Some(_) if config.svg_use_classes => format!(r#"class="qr-dark qr-tone-{}""#, tone),
+        Some(&color) => paint("qr-dark", color),
+        None => paint("qr-dark", config.dark_color),
+    };
+    let light = paint("qr-light", config.light_color);
+    let radius = options.corner_radius.clamp(0.0, 0.5) * scale as f32;
+
+    let mut svg = format!(
+        r#"<?xml version="1.0" standalone="yes"?><svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{size}" height="{size}" viewBox="0 0 {size} {size}""#
+    );
+    if radius == 0.0 {
+        svg.push_str(r#" shape-rendering="crispEdges""#);
+    }
+    let _ = write!(
+        svg,
+        r#"><rect x="0" y="0" width="{size}" height="{size}" {light}/>"#
+    );
+
+    if radius > 0.0 {
+        for (x, y, len, tone) in shapes {
+            let _ = write!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{radius}" ry="{radius}" {}/>"#,
+                x * scale,
+                y * scale,
+                len * scale,
+                scale,
+                dark(tone)
+            );
+        }
+    } else {
+        // One path per tone
+        for tone in 0..palette.len().max(1) {
+            let _ = write!(svg, r#"<path {} d=""#, dark(tone));
+            for &(x, y, len, _) in shapes.iter().filter(|shape| shape.3 == tone) {
+                let _ = write!(
+                    svg,
+                    "M{} {}h{}v{}h-{}z",
+                    x * scale,
+                    y * scale,
+                    len * scale,
+                    scale,
+                    len * scale
+                );
+            }
+            svg.push_str(r#""/>"#);
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Render to a single-page vector PDF, one point per pixel of `render_to_image`
+///
+/// The page is the light color with each horizontal run of dark modules
+/// filled as one rectangle, quiet zone included.
+#[cfg(feature = "image-output")]
+pub fn render_to_pdf(qr: &QrCode, config: &ImageConfig) -> Result<Vec<u8>, QrError> {
+    use printpdf::path::{PaintMode, WindingOrder};
+    use printpdf::{Color, Mm, PdfDocument, Point, Pt, Rect, Rgb};
+
+    let modules = qr_modules(qr, true);
+    let scale = config.scale.max(1) as usize;
+    let size = modules.len() * scale;
+    // PDF's origin is the bottom-left corner
+    let rect = |x: usize, y: usize, width: usize, height: usize| Rect {
+        ll: Point {
+            x: Pt(x as f32),
+            y: Pt(y as f32),
+        },
+        ur: Point {
+            x: Pt((x + width) as f32),
+            y: Pt((y + height) as f32),
+        },
+        mode: PaintMode::Fill,
+        winding: WindingOrder::NonZero,
+    };
+    let rgb = |c: [u8; 3]| {
+        Color::Rgb(Rgb::new(
+            c[0] as f32 / 255.0,
+            c[1] as f32 / 255.0,
+            c[2] as f32 / 255.0,
+            None,
+        ))
+    };
 
-        // Create semi-transparent QR
-        let qr_image = render_to_image(qr, &qr_config);
-        let (qr_width, qr_height) = qr_image.dimensions();
+    let page = Mm::from(Pt(size as f32));
+    let (doc, page_index, layer_index) = PdfDocument::new("QR code", page, page, "Layer 1");
+    let layer = doc.get_page(page_index).get_layer(layer_index);
+    layer.set_fill_color(rgb(config.light_color));
+    layer.add_rect(rect(0, 0, size, size));
+    layer.set_fill_color(rgb(config.dark_color));
+    for (y, row) in modules.iter().enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            if !row[x] {
+                x += 1;
+                continue;
+            }
+            let len = row[x..].iter().take_while(|&&dark| dark).count();
+            layer.add_rect(rect(x * scale, size - (y + 1) * scale, len * scale, scale));
+            x += len;
+        }
+    }
 
-        let x = (bg_width - qr_width) / 2;
-        let y = (bg_height - qr_height) / 2;
+    doc.save_to_bytes()
+        .map_err(|e| QrError::IoError(format!("Failed to write PDF: {}", e)))
+}
 
-        // Blend QR with background
-        image::imageops::overlay(&mut background, &qr_image, x as i64, y as i64);
+/// Share of damaged codewords each error correction level can recover
+#[cfg(all(feature = "image-output", not(feature = "decode")))]
+fn recovery_ratio(level: EcLevel) -> f32 {
+    match level {
+        EcLevel::L => 0.07,
+        EcLevel::M => 0.15,
+        EcLevel::Q => 0.25,
+        EcLevel::H => 0.30,
+    }
+}
 
-        Ok(background)
+/// Fraction of data modules whose sampled color disagrees with the code.
+///
+/// `origin` is the top-left corner of the rendered code (including its quiet
+/// zone) inside `image`, and `scale` is the module size in pixels. Each module
+/// is sampled at its center and classified against the mean luminance.
+#[cfg(feature = "image-output")]
+pub fn module_error_ratio(
+    image: &DynamicImage,
+    qr: &QrCode,
+    origin: (u32, u32),
+    scale: u32,
+) -> f32 {
+    let width = qr.width();
+    let colors = qr.to_colors();
+    let (img_width, img_height) = image.dimensions();
+
+    let samples: Vec<Option<f32>> = (0..width * width)
+        .map(|i| {
+            let px = origin.0 + (QUIET_ZONE_MODULES + (i % width) as u32) * scale + scale / 2;
+            let py = origin.1 + (QUIET_ZONE_MODULES + (i / width) as u32) * scale + scale / 2;
+            (px < img_width && py < img_height).then(|| {
+                let [r, g, b, _] = image.get_pixel(px, py).0;
+                0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+            })
+        })
+        .collect();
+
+    let visible: Vec<f32> = samples.iter().flatten().copied().collect();
+    if visible.is_empty() {
+        return 1.0;
     }
+    let threshold = visible.iter().sum::<f32>() / visible.len() as f32;
+
+    let mismatches = samples
+        .iter()
+        .zip(colors.iter())
+        .filter(|(sample, color)| match sample {
+            Some(luma) => (*luma < threshold) != (**color == qrcode::Color::Dark),
+            None => true,
+        })
+        .count();
+
+    mismatches as f32 / colors.len() as f32
+}
+
+/// Check that a rendered code still reads after compositing.
+///
+/// With the `decode` feature the image is run through [`decode::decode_qr`].
+/// Without it, this falls back to comparing the module error ratio against
+/// what the code's error correction level can recover, which is not a full
+/// decode, so treat `true` as "likely".
+#[cfg(feature = "decode")]
+pub fn is_likely_scannable(
+    image: &DynamicImage,
+    _qr: &QrCode,
+    _origin: (u32, u32),
+    _scale: u32,
+) -> bool {
+    decode::decode_qr(image, &decode::DecodeConfig::default()).is_ok()
 }
 
+/// Check that a rendered code still reads after compositing.
+///
+/// Compares the module error ratio against what the code's error correction
+/// level can recover. This is not a full decode, so treat `true` as "likely".
+#[cfg(all(feature = "image-output", not(feature = "decode")))]
+pub fn is_likely_scannable(
+    image: &DynamicImage,
+    qr: &QrCode,
+    origin: (u32, u32),
+    scale: u32,
+) -> bool {
+    module_error_ratio(image, qr, origin, scale) <= recovery_ratio(qr.error_correction_level())
+}
+
+#[cfg(feature = "image-output")]
+pub fn save_image(image: &DynamicImage, path: &Path) -> Result<(), QrError> {
+    image
+        .save(path)
+        .map_err(|e| QrError::IoError(format!("Failed to save image: {}", e)))
+}
+
+/// Render `qr` in a file format and write it to `path`
+///
+/// PNGs go through `config.post_process` first; vector formats can't.
+#[cfg(feature = "image-output")]
+pub fn save_qr(
+    qr: &QrCode,
+    format: OutputFormat,
+    config: &ImageConfig,
+    path: &Path,
+) -> Result<(), QrError> {
+    let write = |bytes: Vec<u8>| {
+        std::fs::write(path, bytes)
+            .map_err(|e| QrError::IoError(format!("Failed to write {}: {}", path.display(), e)))
+    };
+    match format {
+        OutputFormat::Png => save_image(&finish_image(render_to_image(qr, config), config)?, path),
+        OutputFormat::Svg => write(render_to_svg(qr, config).into_bytes()),
+        OutputFormat::Pdf => write(render_to_pdf(qr, config)?),
+        OutputFormat::Terminal | OutputFormat::Unicode => Err(QrError::IoError(format!(
+            "{:?} output can't be saved to a file",
+            format
+        ))),
+    }
+}
+
+/// Encode `image` as PNG, as [`save_image`] would write it
+#[cfg(feature = "image-output")]
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, QrError> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .map_err(|e| QrError::ImageProcessingFailed(format!("Failed to encode PNG: {}", e)))?;
+    Ok(bytes)
+}
+
+/// Encode `image` as PNG, as a 1-bit palette image when it has only two colors
+///
+/// A plain two-color code is a fraction of the size this way. Images with
+/// more colors (gradients, colored finders, logos) are encoded as usual.
+#[cfg(feature = "image-output")]
+pub fn encode_png_optimized(image: &DynamicImage) -> Result<Vec<u8>, QrError> {
+    let failed = |e: &dyn fmt::Display| {
+        QrError::ImageProcessingFailed(format!("Failed to encode PNG: {}", e))
+    };
+    let rgba = image.to_rgba8();
+
+    let mut palette: Vec<Rgba<u8>> = Vec::with_capacity(2);
+    for pixel in rgba.pixels() {
+        if !palette.contains(pixel) {
+            if palette.len() == 2 {
+                return encode_png(image);
+            }
+            palette.push(*pixel);
+        }
+    }
+
+    // Eight pixels per byte, each row starting on a new byte
+    let (width, height) = rgba.dimensions();
+    let row_bytes = (width as usize).div_ceil(8);
+    let mut data = vec![0u8; row_bytes * height as usize];
+    for (x, y, pixel) in rgba.enumerate_pixels() {
+        if palette.first() != Some(pixel) {
+            data[y as usize * row_bytes + x as usize / 8] |= 0x80 >> (x % 8);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::One);
+    encoder.set_compression(png::Compression::High);
+    encoder.set_palette(
+        palette
+            .iter()
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect::<Vec<u8>>(),
+    );
+    if palette.iter().any(|p| p[3] < 255) {
+        encoder.set_trns(palette.iter().map(|p| p[3]).collect::<Vec<u8>>());
+    }
+    let mut writer = encoder.write_header().map_err(|e| failed(&e))?;
+    writer.write_image_data(&data).map_err(|e| failed(&e))?;
+    writer.finish().map_err(|e| failed(&e))?;
+    Ok(bytes)
+}
+
+/// Save `image` as PNG via [`encode_png_optimized`], whatever `path`'s extension
+#[cfg(feature = "image-output")]
+pub fn save_png_optimized(image: &DynamicImage, path: &Path) -> Result<(), QrError> {
+    let bytes = encode_png_optimized(image)?;
+    std::fs::write(path, bytes)
+        .map_err(|e| QrError::IoError(format!("Failed to save image: {}", e)))
+}
+
+#[cfg(feature = "ai-generation")]
+pub mod ai;
+
+#[cfg(feature = "image-output")]
+pub mod batch;
+
+pub mod content;
+
+#[cfg(feature = "image-output")]
+pub mod damage;
+
+#[cfg(feature = "decode")]
+pub mod decode;
+
+#[cfg(feature = "image-output")]
+pub mod metadata;
+
+#[cfg(feature = "ai-generation")]
+pub mod scratch;
+
+#[cfg(feature = "image-output")]
+pub mod sheet;
+
+#[cfg(feature = "image-output")]
+mod text;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -460,6 +1759,137 @@ mod tests {
         assert_ne!(normal, inverted);
     }
 
+    #[test]
+    fn test_read_content_file_keeps_newlines() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"line one\nline two\n").unwrap();
+
+        let content = read_content_file(file.path()).unwrap();
+        assert_eq!(content, "line one\nline two\n");
+
+        let config = QrConfig {
+            content,
+            ..Default::default()
+        };
+        assert!(generate_qr(&config).is_ok());
+    }
+
+    #[test]
+    fn test_read_content_file_errors() {
+        let missing = read_content_file(Path::new("/nonexistent/content.txt"));
+        assert!(matches!(missing, Err(QrError::IoError(_))));
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, &[0xff, 0xfe, 0x00]).unwrap();
+        let invalid = read_content_file(file.path());
+        assert!(matches!(invalid, Err(QrError::InvalidUtf8(_))));
+    }
+
+    #[test]
+    fn test_generate_qr_bytes() {
+        let qr = generate_qr_bytes(&[0xff, 0xfe, 0x00, 0x01], &QrConfig::default()).unwrap();
+        assert!(qr.width() > 0);
+        assert!(matches!(
+            generate_qr_bytes(&[], &QrConfig::default()),
+            Err(QrError::EmptyContent)
+        ));
+    }
+
+    #[test]
+    fn test_capacity_known_values() {
+        use ErrorCorrectionLevel::*;
+        assert_eq!(capacity(1, Low, Mode::Numeric), 41);
+        assert_eq!(capacity(1, Low, Mode::Alphanumeric), 25);
+        assert_eq!(capacity(1, Low, Mode::Byte), 17);
+        assert_eq!(capacity(1, High, Mode::Numeric), 17);
+        assert_eq!(capacity(10, Medium, Mode::Byte), 213);
+        assert_eq!(capacity(40, Low, Mode::Numeric), 7089);
+        assert_eq!(capacity(40, Low, Mode::Byte), 2953);
+        assert_eq!(capacity(40, High, Mode::Kanji), 784);
+        assert_eq!(capacity(0, Low, Mode::Byte), 0);
+        assert_eq!(capacity(41, Low, Mode::Byte), 0);
+    }
+
+    #[test]
+    fn test_qr_info() {
+        let config = QrConfig {
+            content: "https://example.com/path".to_string(),
+            ..Default::default()
+        };
+        let qr = generate_qr(&config).unwrap();
+        let info = qr_info(&qr, config.content.as_bytes());
+        assert_eq!(info.version, 2);
+        assert!(!info.micro);
+        assert_eq!(info.modules, 25);
+        assert_eq!(info.ec, 'M');
+        assert_eq!(info.data_bytes, 24);
+        assert_eq!(
+            info.capacity_bytes,
+            capacity(2, ErrorCorrectionLevel::Medium, Mode::Byte)
+        );
+        assert_eq!(info.remaining_bytes, info.capacity_bytes - 24);
+
+        let micro = generate_micro_qr(&QrConfig {
+            content: "12345".to_string(),
+            error_correction: ErrorCorrectionLevel::Low,
+            ..Default::default()
+        })
+        .unwrap();
+        let info = qr_info(&micro, b"12345");
+        assert!(info.micro);
+        assert_eq!(info.version, 1);
+        assert_eq!(info.modules, 11);
+        // M1 holds digits only
+        assert_eq!(info.capacity_bytes, 0);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_qr_info_json() {
+        let qr = generate_qr(&QrConfig {
+            content: "hello".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&qr_info(&qr, b"hello").to_json()).unwrap();
+        assert_eq!(json["modules"], 21);
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["ec"], "M");
+        assert_eq!(json["remaining_bytes"], 14 - 5);
+    }
+
+    #[test]
+    fn test_content_slug() {
+        assert_eq!(content_slug("https://example.com"), "https-example-com");
+        assert_eq!(
+            content_slug("https://example.com/a?b=1&c=2#top"),
+            "https-example-com-a-b-1-c-2-top"
+        );
+        assert_eq!(content_slug("  Hello, World!  "), "hello-world");
+        assert_eq!(content_slug("../../etc/passwd"), "etc-passwd");
+        assert_eq!(content_slug("a\\b:c*d?e\"f<g>h|i"), "a-b-c-d-e-f-g-h-i");
+        assert_eq!(content_slug("日本語"), "qrcode");
+        assert_eq!(content_slug(""), "qrcode");
+        assert_eq!(content_slug(&"x".repeat(100)).len(), 64);
+        assert!(!content_slug(&format!("{}/tail", "y".repeat(63))).ends_with('-'));
+    }
+
+    #[test]
+    fn test_auto_output_path_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = auto_output_path(dir.path(), "https://example.com", "png");
+        assert_eq!(first, dir.path().join("https-example-com.png"));
+
+        std::fs::write(&first, b"").unwrap();
+        let second = auto_output_path(dir.path(), "https://example.com", "png");
+        assert_eq!(second, dir.path().join("https-example-com-2.png"));
+
+        std::fs::write(&second, b"").unwrap();
+        let third = auto_output_path(dir.path(), "https://example.com", "png");
+        assert_eq!(third, dir.path().join("https-example-com-3.png"));
+    }
+
     #[test]
     fn test_parse_hex_color() {
         let color = parse_color("#ff5500").unwrap();
@@ -471,6 +1901,10 @@ mod tests {
         assert_eq!(parse_color("black").unwrap(), [0, 0, 0]);
         assert_eq!(parse_color("white").unwrap(), [255, 255, 255]);
         assert_eq!(parse_color("red").unwrap(), [255, 0, 0]);
+        assert_eq!(parse_color("blue").unwrap(), [0, 0, 255]);
+        // Kept from before CSS names, unlike the CSS value [0, 128, 0]
+        assert_eq!(parse_color(" Green ").unwrap(), [0, 255, 0]);
+        assert_eq!(parse_color("navy").unwrap(), [0, 0, 128]);
     }
 
     #[test]
@@ -497,6 +1931,342 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pinned_version() {
+        let config = QrConfig {
+            content: "hi".to_string(),
+            version: Some(5),
+            ..Default::default()
+        };
+        let qr = generate_qr(&config).unwrap();
+        assert_eq!(qr.version(), qrcode::Version::Normal(5));
+        assert_eq!(qr.width(), 37);
+
+        // Without a pin the smallest version is used
+        let config = QrConfig {
+            version: None,
+            ..config
+        };
+        assert_eq!(generate_qr(&config).unwrap().width(), 21);
+    }
+
+    #[test]
+    fn test_fits() {
+        // Version 1 holds 17 bytes at Low but only 7 at High
+        assert!(fits("abcdefg", ErrorCorrectionLevel::High, 1));
+        assert!(!fits("abcdefgh", ErrorCorrectionLevel::High, 1));
+        assert!(fits("abcdefghijklmnopq", ErrorCorrectionLevel::Low, 1));
+        assert!(!fits("abcdefghijklmnopqr", ErrorCorrectionLevel::Low, 1));
+        assert!(fits("abcdefghijklmnopqr", ErrorCorrectionLevel::Low, 2));
+        assert!(!fits("abc", ErrorCorrectionLevel::Low, 0));
+        assert!(!fits("abc", ErrorCorrectionLevel::Low, 41));
+        assert!(!fits("", ErrorCorrectionLevel::Low, 40));
+    }
+
+    #[test]
+    fn test_best_error_correction() {
+        assert_eq!(
+            best_error_correction("abcdefg", 1),
+            Some(ErrorCorrectionLevel::High)
+        );
+        assert_eq!(
+            best_error_correction("abcdefghijk", 1),
+            Some(ErrorCorrectionLevel::Quartile)
+        );
+        assert_eq!(
+            best_error_correction("abcdefghijklmn", 1),
+            Some(ErrorCorrectionLevel::Medium)
+        );
+        assert_eq!(
+            best_error_correction("abcdefghijklmnopq", 1),
+            Some(ErrorCorrectionLevel::Low)
+        );
+        assert_eq!(best_error_correction("abcdefghijklmnopqr", 1), None);
+        // A higher cap lets the same content have stronger correction
+        assert_eq!(
+            best_error_correction("abcdefghijklmnopq", 3),
+            Some(ErrorCorrectionLevel::High)
+        );
+    }
+
+    #[test]
+    fn test_micro_qr() {
+        let config = QrConfig {
+            content: "12345".to_string(),
+            error_correction: ErrorCorrectionLevel::Low,
+            micro: true,
+            ..Default::default()
+        };
+        let qr = generate_micro_qr(&config).unwrap();
+        // M1 is 11 modules wide against 21 for the smallest standard code
+        assert_eq!(qr.version(), qrcode::Version::Micro(1));
+        assert_eq!(qr.width(), 11);
+        assert_eq!(generate_qr(&config).unwrap().width(), 11);
+
+        let config = QrConfig {
+            content: "ID-2024-0042".to_string(),
+            ..config
+        };
+        assert!(generate_qr(&config).unwrap().version().is_micro());
+    }
+
+    #[test]
+    fn test_micro_qr_falls_back_to_standard() {
+        let config = QrConfig {
+            content: "https://example.com/a/long/path".to_string(),
+            micro: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            generate_micro_qr(&config),
+            Err(QrError::ContentTooLarge)
+        ));
+        let qr = generate_qr(&config).unwrap();
+        assert!(!qr.version().is_micro());
+
+        // Micro codes have no high error correction level
+        let config = QrConfig {
+            content: "1".to_string(),
+            error_correction: ErrorCorrectionLevel::High,
+            ..config
+        };
+        assert!(generate_micro_qr(&config).is_err());
+        assert!(!generate_qr(&config).unwrap().version().is_micro());
+    }
+
+    #[test]
+    fn test_pinned_version_too_small() {
+        let config = QrConfig {
+            content: "x".repeat(100),
+            version: Some(1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            generate_qr(&config),
+            Err(QrError::ContentTooLarge)
+        ));
+
+        let config = QrConfig {
+            content: "x".to_string(),
+            version: Some(41),
+            ..Default::default()
+        };
+        assert!(matches!(
+            generate_qr(&config),
+            Err(QrError::EncodingFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_qr_modules() {
+        let qr = generate_qr(&QrConfig {
+            content: "https://example.com".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let width = qr.width();
+
+        let modules = qr_modules(&qr, false);
+        assert_eq!(modules.len(), width);
+        assert!(modules.iter().all(|row| row.len() == width));
+        // Finder pattern corners
+        for (x, y) in [(0, 0), (width - 1, 0), (0, width - 1), (6, 6)] {
+            assert!(modules[y][x], "module ({}, {}) should be dark", x, y);
+        }
+        assert!(!modules[7][7]);
+
+        let padded = qr_modules(&qr, true);
+        assert_eq!(padded.len(), width + 8);
+        assert!(padded[0].iter().all(|dark| !dark));
+        assert!(padded[4][4]);
+        assert_eq!(padded[4 + 10][4..4 + width], modules[10][..]);
+    }
+
+    #[test]
+    fn test_scale_for_target_px() {
+        let v5 = generate_qr(&QrConfig {
+            content: "x".to_string(),
+            version: Some(5),
+            ..Default::default()
+        })
+        .unwrap();
+        // 37 modules plus a 4-module quiet zone on each side is 45
+        assert_eq!(scale_for_target_px(&v5, 512, QUIET_ZONE_MODULES), 11);
+        assert_eq!(scale_for_target_px(&v5, 450, QUIET_ZONE_MODULES), 10);
+        // 517.5 is the midpoint between scales 11 and 12
+        assert_eq!(scale_for_target_px(&v5, 517, QUIET_ZONE_MODULES), 11);
+        assert_eq!(scale_for_target_px(&v5, 518, QUIET_ZONE_MODULES), 12);
+        assert_eq!(scale_for_target_px(&v5, 370, 0), 10);
+        assert_eq!(scale_for_target_px(&v5, 0, QUIET_ZONE_MODULES), 1);
+        assert_eq!(
+            scale_for_target_px(&v5, u32::MAX, QUIET_ZONE_MODULES),
+            u32::MAX / 45
+        );
+    }
+
+    #[test]
+    fn test_render_to_terminal_checked() {
+        let config = QrConfig {
+            content: "x".repeat(1000),
+            ..Default::default()
+        };
+        let qr = generate_qr(&config).unwrap();
+        let width = qr.width() + 8;
+        assert!(width > 80);
+
+        let result = render_to_terminal_checked(&qr, &config, Some(80));
+        assert!(matches!(
+            result,
+            Err(QrError::TooWide { max_width: 80, .. })
+        ));
+
+        let rendered = render_to_terminal_checked(&qr, &config, Some(width)).unwrap();
+        assert_eq!(rendered, render_to_terminal(&qr, &config));
+        assert_eq!(rendered.lines().next().unwrap().chars().count(), width);
+        assert!(render_to_terminal_checked(&qr, &config, None).is_ok());
+    }
+
+    #[test]
+    fn test_ec_for_use_case() {
+        assert_eq!(
+            ec_for_use_case(UseCase::Print),
+            (ErrorCorrectionLevel::Quartile, 4)
+        );
+        assert_eq!(
+            ec_for_use_case(UseCase::Screen),
+            (ErrorCorrectionLevel::Low, 2)
+        );
+        assert_eq!(
+            ec_for_use_case(UseCase::Industrial),
+            (ErrorCorrectionLevel::High, 8)
+        );
+    }
+
+    #[test]
+    fn test_contrast_warning() {
+        assert_eq!(contrast_warning([0, 0, 0], [255, 255, 255]), None);
+        assert_eq!(
+            contrast_warning([0x1a, 0x1a, 0x2e], [0xea, 0xea, 0xea]),
+            None
+        );
+        let warning = contrast_warning([0x77, 0x77, 0x77], [0x88, 0x88, 0x88]).unwrap();
+        assert!(warning.contains("1.3:1"), "{}", warning);
+    }
+
+    #[test]
+    fn test_render_to_terminal_colored() {
+        let config = QrConfig {
+            content: "hello".to_string(),
+            ..Default::default()
+        };
+        let qr = generate_qr(&config).unwrap();
+        let image_config = ImageConfig {
+            dark_color: [0x1a, 0x73, 0xe8],
+            ..Default::default()
+        };
+
+        let colored = render_to_terminal_colored(&qr, &config, &image_config);
+        assert!(colored.contains("\x1b["));
+        assert!(colored.contains("38;2;26;115;232"));
+        assert!(colored.lines().all(|line| line.ends_with("\x1b[0m")));
+        // 29 module rows (with quiet zone) at two per line
+        assert_eq!(colored.lines().count(), 15);
+
+        let plain = render_to_terminal(&qr, &config);
+        assert!(!plain.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_render_to_pbm() {
+        let qr = generate_qr(&QrConfig {
+            content: "test".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(qr.width(), 21);
+
+        let pbm = render_to_pbm(&qr, &PbmConfig::default());
+        let lines: Vec<&str> = pbm.lines().collect();
+        assert_eq!(lines[0], "P1");
+        assert_eq!(lines[1], "29 29");
+        let rows = &lines[2..];
+        assert_eq!(rows.len(), 29);
+        assert!(rows.iter().all(|row| row.len() == 29));
+        // Quiet zone, then the top-left finder corner
+        assert_eq!(&rows[4][..5], "00001");
+        assert!(rows[3].chars().all(|c| c == '0'));
+        let modules = qr_modules(&qr, true);
+        for (row, modules) in rows.iter().zip(&modules) {
+            let expected: String = modules
+                .iter()
+                .map(|&dark| if dark { '1' } else { '0' })
+                .collect();
+            assert_eq!(*row, expected);
+        }
+
+        // 3px per module, 1 module of quiet zone
+        let scaled = render_to_pbm(
+            &qr,
+            &PbmConfig {
+                scale: 3,
+                quiet_zone: 1,
+            },
+        );
+        let lines: Vec<&str> = scaled.lines().collect();
+        assert_eq!(lines[1], "69 69");
+        assert_eq!(&lines[2 + 3][..4], "0001");
+        assert_eq!(&lines[2 + 2][..4], "0000");
+
+        // Rows wider than 70 pixels wrap, but every pixel is still there
+        let wide = render_to_pbm(
+            &qr,
+            &PbmConfig {
+                scale: 4,
+                quiet_zone: 4,
+            },
+        );
+        let body: Vec<&str> = wide.lines().skip(2).collect();
+        assert!(body.iter().all(|line| line.len() <= 70));
+        assert_eq!(body.concat().len(), 116 * 116);
+    }
+
+    #[test]
+    fn test_render_to_unicode_string() {
+        let qr = generate_qr(&QrConfig {
+            content: "https://example.com".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let size = qr.width() + 8;
+
+        let text = render_to_unicode_string(&qr, &UnicodeConfig::default());
+        assert!(text
+            .chars()
+            .all(|c| matches!(c, '█' | '▀' | '▄' | ' ' | '\n')));
+        assert_eq!(text.lines().count(), size.div_ceil(2));
+        assert!(text.lines().all(|line| line.chars().count() == size));
+
+        let wide = render_to_unicode_string(
+            &qr,
+            &UnicodeConfig {
+                fence: true,
+                double_width: true,
+                ..Default::default()
+            },
+        );
+        let lines: Vec<&str> = wide.lines().collect();
+        assert_eq!(lines.first(), Some(&"```"));
+        assert_eq!(lines.last(), Some(&"```"));
+        let body = &lines[1..lines.len() - 1];
+        assert_eq!(body.len(), size);
+        assert!(body.iter().all(|line| line.chars().count() == size * 2));
+        assert!(body
+            .iter()
+            .all(|line| line.chars().all(|c| c == '█' || c == ' ')));
+        // Top-left finder corner
+        assert!(body[4].chars().skip(8).take(2).all(|c| c == '█'));
+    }
+
     #[cfg(feature = "image-output")]
     mod image_tests {
         use super::*;
@@ -514,6 +2284,39 @@ mod tests {
             assert!(width > 0 && height > 0);
         }
 
+        #[test]
+        fn test_render_to_image_with_column_colors() {
+            let qr = generate_qr(&QrConfig {
+                content: "https://example.com".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let columns = qr.width() + 8;
+            // Dark modules fade from red to blue across the columns
+            let shade = |x: usize| {
+                Rgba([
+                    (255 - x * 255 / (columns - 1)) as u8,
+                    0,
+                    (x * 255 / (columns - 1)) as u8,
+                    255,
+                ])
+            };
+            let image = render_to_image_with(&qr, 4, |x, _, dark| {
+                if dark {
+                    shade(x)
+                } else {
+                    Rgba([255, 255, 255, 0])
+                }
+            });
+
+            assert_eq!(image.dimensions(), (columns as u32 * 4, columns as u32 * 4));
+            assert_eq!(image.get_pixel(0, 0), Rgba([255, 255, 255, 0]));
+            // Top-left and top-right finder corners sit just inside the quiet zone
+            let right = columns - 5;
+            assert_eq!(image.get_pixel(4 * 4, 4 * 4), shade(4));
+            assert_eq!(image.get_pixel(right as u32 * 4 + 3, 4 * 4), shade(right));
+        }
+
         #[test]
         fn test_render_to_svg() {
             let config = QrConfig {
@@ -527,11 +2330,686 @@ mod tests {
             assert!(svg.contains("</svg>"));
         }
 
+        #[test]
+        fn test_render_to_svg_with_merged_runs() {
+            let qr = generate_qr(&QrConfig {
+                content: "https://example.com".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let config = ImageConfig::default();
+            let dark_modules = qr_modules(&qr, false)
+                .iter()
+                .flatten()
+                .filter(|&&dark| dark)
+                .count();
+
+            // The stock renderer draws one subpath per dark module
+            let naive = render_to_svg(&qr, &config);
+            assert_eq!(naive.matches('M').count(), dark_modules);
+
+            let per_module = render_to_svg_with(&qr, &config, &SvgOptions::default());
+            assert_eq!(per_module.matches('M').count(), dark_modules);
+
+            let merged = render_to_svg_with(
+                &qr,
+                &config,
+                &SvgOptions {
+                    merge_runs: true,
+                    ..Default::default()
+                },
+            );
+            let runs = merged.matches('M').count();
+            assert!(
+                runs * 3 < dark_modules * 2,
+                "{} runs for {} modules",
+                runs,
+                dark_modules
+            );
+            assert!(merged.len() * 3 < naive.len() * 2);
+            assert_eq!(merged.matches("<path").count(), 1);
+            // The top row of the top-left finder is one 7-module run
+            assert!(merged.contains("M32 32h56v8h-56z"));
+        }
+
+        #[test]
+        fn test_post_process_border() {
+            let qr = generate_qr(&QrConfig {
+                content: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let plain = render_to_image(&qr, &ImageConfig::default());
+            assert_eq!(
+                finish_image(plain.clone(), &ImageConfig::default()).unwrap(),
+                plain
+            );
+
+            let config = ImageConfig {
+                post_process: Some(PostProcess::new(|image| {
+                    add_border(&image, 12, [255, 0, 0])
+                })),
+                ..Default::default()
+            };
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("framed.png");
+            save_qr(&qr, OutputFormat::Png, &config, &path).unwrap();
+
+            let framed = image::open(&path).unwrap();
+            assert_eq!(
+                framed.dimensions(),
+                (plain.width() + 24, plain.height() + 24)
+            );
+            assert_eq!(framed.get_pixel(0, 0).0, [255, 0, 0, 255]);
+            // The code's own quiet zone starts inside the border
+            assert_eq!(framed.get_pixel(12, 12).0, [255, 255, 255, 255]);
+
+            assert!(matches!(
+                add_border(&plain, 4_000_000_000, [255, 0, 0]),
+                Err(QrError::ImageProcessingFailed(msg)) if msg.contains("too wide")
+            ));
+        }
+
+        #[test]
+        fn test_render_to_pdf() {
+            use printpdf::lopdf::{Document, Object};
+
+            let qr = generate_qr(&QrConfig {
+                content: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let pdf = render_to_pdf(&qr, &ImageConfig::default()).unwrap();
+            assert!(pdf.starts_with(b"%PDF-"));
+            let number = |n: &Object| match n {
+                Object::Real(n) => *n,
+                Object::Integer(n) => *n as f32,
+                _ => panic!("expected a number, got {:?}", n),
+            };
+
+            let doc = Document::load_mem(&pdf).unwrap();
+            let pages = doc.get_pages();
+            assert_eq!(pages.len(), 1);
+            let page = pages[&1];
+            let media_box: Vec<f32> = doc
+                .get_dictionary(page)
+                .unwrap()
+                .get(b"MediaBox")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(number)
+                .collect();
+            assert_eq!(media_box.len(), 4);
+            assert!((media_box[2] - 232.0).abs() < 0.01 && (media_box[3] - 232.0).abs() < 0.01);
+
+            let rects: Vec<Vec<f32>> = doc
+                .get_and_decode_page_content(page)
+                .unwrap()
+                .operations
+                .into_iter()
+                .filter(|op| op.operator == "re")
+                .map(|op| op.operands.iter().map(number).collect())
+                .collect();
+            // The light page, then the top row of the top-left finder, measured up from the bottom
+            assert_eq!(rects[0], [0.0, 0.0, 232.0, 232.0]);
+            assert!(rects.contains(&vec![32.0, 192.0, 56.0, 8.0]));
+        }
+
+        #[test]
+        fn test_render_to_svg_with_rounded_corners() {
+            let qr = generate_qr(&QrConfig {
+                content: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let dark_modules = qr_modules(&qr, false)
+                .iter()
+                .flatten()
+                .filter(|&&dark| dark)
+                .count();
+            let svg = render_to_svg_with(
+                &qr,
+                &ImageConfig::default(),
+                &SvgOptions {
+                    corner_radius: 0.5,
+                    ..Default::default()
+                },
+            );
+
+            assert_eq!(svg.matches(r#"rx="4""#).count(), dark_modules);
+            assert!(!svg.contains("<path"));
+            assert!(!svg.contains("crispEdges"));
+        }
+
+        #[test]
+        fn test_render_to_svg_with_classes() {
+            let qr = generate_qr(&QrConfig {
+                content: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let config = ImageConfig {
+                svg_use_classes: true,
+                ..Default::default()
+            };
+
+            let svg = render_to_svg(&qr, &config);
+            assert!(
+                svg.contains(r#"<rect x="0" y="0" width="232" height="232" class="qr-light"/>"#)
+            );
+            assert!(svg.contains(r#"<path class="qr-dark" d=""#));
+            assert!(!svg.contains("fill"));
+
+            let rounded = render_to_svg_with(
+                &qr,
+                &config,
+                &SvgOptions {
+                    corner_radius: 0.5,
+                    ..Default::default()
+                },
+            );
+            assert!(rounded.contains(r#"ry="4" class="qr-dark"/>"#));
+            assert!(!rounded.contains("fill"));
+
+            // Inline colors stay the default
+            assert!(render_to_svg(&qr, &ImageConfig::default()).contains("fill="));
+        }
+
+        #[test]
+        fn test_render_to_svg_with_palette() {
+            let qr = generate_qr(&QrConfig {
+                content: "https://example.com/brand".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let fills = |svg: &str| -> std::collections::BTreeSet<String> {
+                svg.match_indices("fill=\"#")
+                    .map(|(i, _)| svg[i + 6..i + 13].to_string())
+                    .collect()
+            };
+            let dark_modules = qr_modules(&qr, false)
+                .iter()
+                .flatten()
+                .filter(|&&dark| dark)
+                .count();
+
+            // Dark and light only without a palette
+            assert_eq!(fills(&render_to_svg(&qr, &ImageConfig::default())).len(), 2);
+
+            let palette = vec![[29, 78, 216], [124, 58, 237], [219, 39, 119]];
+            for mode in [PaletteMode::Quadrants, PaletteMode::Radial] {
+                let config = ImageConfig {
+                    svg_palette: palette.clone(),
+                    svg_palette_mode: mode,
+                    ..Default::default()
+                };
+                let svg = render_to_svg(&qr, &config);
+                let expected = ["#1d4ed8", "#7c3aed", "#db2777", "#ffffff"].map(String::from);
+                assert_eq!(fills(&svg), expected.into_iter().collect(), "{:?}", mode);
+                assert!(!svg.contains("#000000"));
+                assert_eq!(svg.matches("<path").count(), 3);
+                assert_eq!(svg.matches('M').count(), dark_modules);
+
+                // Merged runs still cover every module, split where the tone changes
+                let merged = render_to_svg_with(
+                    &qr,
+                    &config,
+                    &SvgOptions {
+                        merge_runs: true,
+                        ..Default::default()
+                    },
+                );
+                assert_eq!(fills(&merged), fills(&svg));
+            }
+        }
+
+        #[test]
+        fn test_palette_tone_regions() {
+            // Four quadrants cycle through a two-color palette
+            let size = 29;
+            let quadrants: Vec<usize> = [(0, 0), (28, 0), (28, 28), (0, 28)]
+                .iter()
+                .map(|&(x, y)| palette_tone(PaletteMode::Quadrants, 2, x, y, size))
+                .collect();
+            assert_eq!(quadrants, [0, 1, 0, 1]);
+
+            // Bands grow outward from the center and reach the last color at the corners
+            assert_eq!(palette_tone(PaletteMode::Radial, 3, 14, 14, size), 0);
+            assert_eq!(palette_tone(PaletteMode::Radial, 3, 0, 0, size), 2);
+            let along_diagonal: Vec<usize> = (0..=14)
+                .rev()
+                .map(|i| palette_tone(PaletteMode::Radial, 3, i, i, size))
+                .collect();
+            assert!(along_diagonal.windows(2).all(|pair| pair[0] <= pair[1]));
+
+            assert_eq!(palette_tone(PaletteMode::Radial, 0, 0, 0, size), 0);
+        }
+
+        #[test]
+        fn test_plain_render_has_no_module_errors() {
+            let config = QrConfig {
+                content: "https://example.com".to_string(),
+                ..Default::default()
+            };
+            let qr = generate_qr(&config).unwrap();
+            let image = render_to_image(&qr, &ImageConfig::default());
+            assert_eq!(module_error_ratio(&image, &qr, (0, 0), 8), 0.0);
+            assert!(is_likely_scannable(&image, &qr, (0, 0), 8));
+        }
+
+        #[test]
+        fn test_covered_code_is_not_scannable() {
+            let config = QrConfig {
+                content: "https://example.com".to_string(),
+                ..Default::default()
+            };
+            let qr = generate_qr(&config).unwrap();
+            let mut image = render_to_image(&qr, &ImageConfig::default()).to_rgba8();
+            // Blank out the middle 60% of the code, far past what level M recovers
+            let (width, height) = image.dimensions();
+            for y in height / 5..height * 4 / 5 {
+                for x in width / 5..width * 4 / 5 {
+                    image.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+                }
+            }
+            assert!(!is_likely_scannable(
+                &DynamicImage::ImageRgba8(image),
+                &qr,
+                (0, 0),
+                8
+            ));
+        }
+
+        #[test]
+        fn test_label_adds_band_below_code() {
+            let config = QrConfig {
+                content: "SKU-12345".to_string(),
+                ..Default::default()
+            };
+            let qr = generate_qr(&config).unwrap();
+            let image_config = ImageConfig::default();
+            let plain = render_to_image(&qr, &image_config);
+            let labelled = add_label(&plain, "SKU-12345", 20.0, &image_config);
+
+            assert_eq!(labelled.width(), plain.width());
+            assert_eq!(
+                labelled.height(),
+                plain.height() + text::caption_band_height(20.0)
+            );
+            // The code itself is untouched and some label text was drawn
+            assert_eq!(labelled.get_pixel(0, 0), plain.get_pixel(0, 0));
+            let band_has_text = (plain.height()..labelled.height())
+                .flat_map(|y| (0..labelled.width()).map(move |x| (x, y)))
+                .any(|(x, y)| labelled.get_pixel(x, y).0[0] < 128);
+            assert!(band_has_text);
+        }
+
+        fn background_test_qr() -> QrCode {
+            generate_qr(&QrConfig {
+                content: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+        }
+
+        fn background_config(fit: BackgroundFit) -> BackgroundConfig {
+            BackgroundConfig {
+                fit,
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn test_background_center_keeps_size() {
+            let qr = background_test_qr();
+            let background = DynamicImage::new_rgb8(300, 200);
+            let (image, origin, scale) = compose_on_background(
+                &qr,
+                background,
+                &background_config(BackgroundFit::Center),
+                &ImageConfig::default(),
+            )
+            .unwrap();
+
+            // (200 - 40) / 29 modules = 5px per module, 145px code
+            assert_eq!(image.dimensions(), (300, 200));
+            assert_eq!(origin, ((300 - 145) / 2, (200 - 145) / 2));
+            assert_eq!(scale, 5);
+            assert!(is_likely_scannable(&image, &qr, origin, 5));
+        }
+
+        #[test]
+        fn test_background_center_too_small() {
+            let qr = background_test_qr();
+            let small = || DynamicImage::new_rgb8(60, 90);
+            let config = background_config(BackgroundFit::Center);
+            assert!(matches!(
+                compose_on_background(&qr, small(), &config, &ImageConfig::default()),
+                Err(QrError::BackgroundTooSmall)
+            ));
+
+            let upscale = BackgroundConfig {
+                upscale: true,
+                ..config
+            };
+            let (image, origin, _) =
+                compose_on_background(&qr, small(), &upscale, &ImageConfig::default()).unwrap();
+            // Shorter side grows to 29 * 8 + 40, keeping the 2:3 aspect ratio
+            assert_eq!(image.dimensions(), (272, 408));
+            assert_eq!(origin, (20, (408 - 232) / 2));
+        }
+
+        #[test]
+        fn test_background_fits_sized_to_code() {
+            let qr = background_test_qr();
+            // 29 modules at 8px plus a 20px margin on each side
+            let canvas = 29 * 8 + 40;
+            for fit in [
+                BackgroundFit::Tile,
+                BackgroundFit::Stretch,
+                BackgroundFit::Scale,
+            ] {
+                let background = DynamicImage::new_rgb8(50, 30);
+                let (image, origin, _) = compose_on_background(
+                    &qr,
+                    background,
+                    &background_config(fit),
+                    &ImageConfig::default(),
+                )
+                .unwrap();
+                assert_eq!(image.dimensions(), (canvas, canvas), "{:?}", fit);
+                assert_eq!(origin, (20, 20), "{:?}", fit);
+            }
+        }
+
+        #[test]
+        fn test_background_tile_repeats() {
+            let qr = background_test_qr();
+            let mut background = image::RgbImage::from_pixel(10, 10, Rgb([255, 0, 0]));
+            background.put_pixel(0, 0, Rgb([0, 0, 255]));
+            let (image, _, _) = compose_on_background(
+                &qr,
+                DynamicImage::ImageRgb8(background),
+                &background_config(BackgroundFit::Tile),
+                &ImageConfig::default(),
+            )
+            .unwrap();
+
+            // Every tile's corner pixel shows up along the top margin
+            assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+            assert_eq!(image.get_pixel(10, 10).0, [0, 0, 255, 255]);
+            assert_eq!(image.get_pixel(11, 10).0, [255, 0, 0, 255]);
+        }
+
+        #[test]
+        fn test_background_with_logo_centers_logo_on_code() {
+            let dir = tempfile::tempdir().unwrap();
+            let background_path = dir.path().join("background.png");
+            let logo_path = dir.path().join("logo.png");
+            image::RgbImage::from_pixel(400, 250, Rgb([0, 128, 0]))
+                .save(&background_path)
+                .unwrap();
+            image::RgbImage::from_pixel(10, 10, Rgb([255, 0, 0]))
+                .save(&logo_path)
+                .unwrap();
+
+            let qr = generate_qr(&QrConfig {
+                content: "test".to_string(),
+                error_correction: ErrorCorrectionLevel::High,
+                ..Default::default()
+            })
+            .unwrap();
+            let background_config = BackgroundConfig {
+                path: background_path,
+                ..Default::default()
+            };
+            let logo_config = LogoConfig {
+                path: logo_path,
+                ..Default::default()
+            };
+            let (
+                image,
+                LogoPlacement {
+                    origin,
+                    scale,
+                    warnings,
+                },
+            ) = overlay_on_background_with_logo(
+                &qr,
+                &background_config,
+                &logo_config,
+                &ImageConfig::default(),
+            )
+            .unwrap();
+            // A plain RGB logo has no transparency
+            assert_eq!(warnings.len(), 1);
+
+            // (250 - 40) / 29 modules = 7px per module, 203px code
+            assert_eq!(scale, 7);
+            let side = 29 * scale;
+            assert_eq!(origin, ((400 - side) / 2, (250 - side) / 2));
+
+            // The 40px logo sits in the middle of the code, not elsewhere
+            let red = [255, 0, 0, 255];
+            let (cx, cy) = (origin.0 + side / 2, origin.1 + side / 2);
+            assert_eq!(image.get_pixel(cx, cy).0, red);
+            assert_eq!(image.get_pixel(cx - 19, cy - 19).0, red);
+            assert_eq!(image.get_pixel(cx + 19, cy + 19).0, red);
+            assert_ne!(image.get_pixel(cx - 22, cy).0, red);
+            assert_eq!(image.get_pixel(0, 0).0, [0, 128, 0, 255]);
+            assert!(is_likely_scannable(&image, &qr, origin, scale));
+        }
+
+        #[test]
+        fn test_optimized_png_is_smaller() {
+            let qr = generate_qr(&QrConfig {
+                content: "https://example.com/some/longer/path".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let image = render_to_image(&qr, &ImageConfig::default());
+
+            let mut rgb = Vec::new();
+            image
+                .write_to(&mut std::io::Cursor::new(&mut rgb), image::ImageFormat::Png)
+                .unwrap();
+            let optimized = encode_png_optimized(&image).unwrap();
+            assert!(
+                optimized.len() * 2 < rgb.len(),
+                "{} vs {} bytes",
+                optimized.len(),
+                rgb.len()
+            );
+
+            // Same pixels either way
+            let decoded = image::load_from_memory(&optimized).unwrap();
+            assert_eq!(decoded.to_rgba8(), image.to_rgba8());
+        }
+
+        #[test]
+        fn test_optimized_png_keeps_transparency_and_extra_colors() {
+            let qr = background_test_qr();
+            let transparent = ImageConfig {
+                quiet_zone_color: Some([0, 0, 0, 0]),
+                ..Default::default()
+            };
+            // Three colors: falls back to a regular encode
+            let image = render_to_image(&qr, &transparent);
+            let decoded = image::load_from_memory(&encode_png_optimized(&image).unwrap()).unwrap();
+            assert_eq!(decoded.to_rgba8(), image.to_rgba8());
+
+            // Two colors, one of them transparent, on an odd width
+            let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(13, 5, |x, y| {
+                if (x + y) % 3 == 0 {
+                    Rgba([0, 0, 0, 0])
+                } else {
+                    Rgba([10, 20, 30, 255])
+                }
+            }));
+            let decoded = image::load_from_memory(&encode_png_optimized(&image).unwrap()).unwrap();
+            assert_eq!(decoded.to_rgba8(), image.to_rgba8());
+        }
+
+        #[test]
+        fn test_quiet_zone_color() {
+            let qr = background_test_qr();
+            let config = ImageConfig {
+                scale: 2,
+                quiet_zone_color: Some([0, 0, 0, 0]),
+                ..Default::default()
+            };
+            let image = render_to_image(&qr, &config);
+            assert_eq!(image.width(), (21 + 8) * 2);
+
+            let transparent = [0, 0, 0, 0];
+            // Border pixels, including next to the code
+            assert_eq!(image.get_pixel(0, 0).0, transparent);
+            assert_eq!(image.get_pixel(7, 30).0, transparent);
+            assert_eq!(image.get_pixel(57, 57).0, transparent);
+            // The finder corner is dark and the separator beside it light
+            assert_eq!(image.get_pixel(8, 8).0, [0, 0, 0, 255]);
+            assert_eq!(image.get_pixel((4 + 7) * 2, 8).0, [255, 255, 255, 255]);
+
+            // A wider quiet zone is padded with the same color
+            let wide = with_quiet_zone(&image, 6, &config);
+            assert_eq!(wide.width(), (21 + 12) * 2);
+            assert_eq!(wide.get_pixel(0, 0).0, transparent);
+            assert_eq!(wide.get_pixel(11, 11).0, transparent);
+            assert_eq!(wide.get_pixel(12, 12).0, [0, 0, 0, 255]);
+        }
+
+        #[test]
+        fn test_with_quiet_zone() {
+            let qr = background_test_qr();
+            let config = ImageConfig {
+                scale: 2,
+                ..Default::default()
+            };
+            let image = render_to_image(&qr, &config);
+            assert_eq!(image.width(), (21 + 8) * 2);
+
+            let wide = with_quiet_zone(&image, 8, &config);
+            assert_eq!(wide.dimensions(), ((21 + 16) * 2, (21 + 16) * 2));
+            // The finder pattern's corner moves in by the extra 4 modules
+            assert_eq!(wide.get_pixel(16, 16).0, [0, 0, 0, 255]);
+            assert_eq!(wide.get_pixel(15, 15).0, [255, 255, 255, 255]);
+
+            let tight = with_quiet_zone(&image, 2, &config);
+            assert_eq!(tight.dimensions(), ((21 + 4) * 2, (21 + 4) * 2));
+            assert_eq!(tight.get_pixel(4, 4).0, [0, 0, 0, 255]);
+            assert_eq!(tight.get_pixel(3, 3).0, [255, 255, 255, 255]);
+
+            assert_eq!(with_quiet_zone(&image, 0, &config).width(), 21 * 2);
+        }
+
+        #[test]
+        fn test_opaque_logo_warns() {
+            let dir = tempfile::tempdir().unwrap();
+            let opaque_path = dir.path().join("opaque.png");
+            let round_path = dir.path().join("round.png");
+            image::RgbImage::from_pixel(10, 10, Rgb([255, 0, 0]))
+                .save(&opaque_path)
+                .unwrap();
+            // A red disk on a transparent background
+            image::RgbaImage::from_fn(100, 100, |x, y| {
+                let (dx, dy) = (x as i32 * 2 - 99, y as i32 * 2 - 99);
+                if dx * dx + dy * dy <= 100 * 100 {
+                    Rgba([255, 0, 0, 255])
+                } else {
+                    Rgba([0, 0, 0, 0])
+                }
+            })
+            .save(&round_path)
+            .unwrap();
+
+            // A dark canvas shows whether anything was drawn over a spot
+            let dark = [0, 0, 0, 255];
+            let overlay = |path: &std::path::Path, backing: bool| {
+                let mut image =
+                    DynamicImage::ImageRgba8(RgbaImage::from_pixel(200, 200, Rgba(dark)));
+                let logo_config = LogoConfig {
+                    path: path.to_path_buf(),
+                    backing,
+                    ..Default::default()
+                };
+                let warnings = overlay_logo(&mut image, &logo_config).unwrap();
+                (image, warnings)
+            };
+
+            let (_, warnings) = overlay(&opaque_path, false);
+            assert_eq!(warnings.len(), 1);
+            assert!(warnings[0].contains("transparent"));
+
+            // The disk's corners let the code show through...
+            let (image, warnings) = overlay(&round_path, false);
+            assert!(warnings.is_empty());
+            // The 40px logo's top-left corner
+            let (cx, cy) = (80, 80);
+            assert_eq!(image.get_pixel(cx, cy).0, dark);
+            assert_eq!(image.get_pixel(100, 100).0, [255, 0, 0, 255]);
+
+            // ...unless asked for a backing
+            let (image, warnings) = overlay(&round_path, true);
+            assert!(warnings.is_empty());
+            assert_eq!(image.get_pixel(cx, cy).0, [255, 255, 255, 255]);
+            assert_eq!(image.get_pixel(100, 100).0, [255, 0, 0, 255]);
+            // Rounded: the backing's own corner is left alone
+            assert_eq!(image.get_pixel(cx - 4, cy - 4).0, dark);
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn test_non_utf8_paths() {
+            use std::ffi::{OsStr, OsString};
+            use std::os::unix::ffi::OsStrExt;
+
+            let dir = tempfile::tempdir().unwrap();
+            let logo_path = dir.path().join(OsStr::from_bytes(b"logo-\xff.png"));
+            let background_path = dir.path().join(OsStr::from_bytes(b"background-\xfe.png"));
+            let output_path = dir.path().join(OsStr::from_bytes(b"qr-\xfd.png"));
+            image::RgbImage::from_pixel(10, 10, Rgb([255, 0, 0]))
+                .save(&logo_path)
+                .unwrap();
+            image::RgbImage::from_pixel(400, 400, Rgb([0, 128, 0]))
+                .save(&background_path)
+                .unwrap();
+
+            let qr = background_test_qr();
+            let logo_config = LogoConfig {
+                path: logo_path,
+                ..Default::default()
+            };
+            let background_config = BackgroundConfig {
+                path: background_path,
+                ..Default::default()
+            };
+            let (image, _) = overlay_on_background_with_logo(
+                &qr,
+                &background_config,
+                &logo_config,
+                &ImageConfig::default(),
+            )
+            .unwrap();
+            save_image(&image, &output_path).unwrap();
+
+            // Saved under the exact name, not a lossy replacement
+            assert!(output_path.exists());
+            let names: Vec<OsString> = std::fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect();
+            assert!(names.contains(&OsString::from(OsStr::from_bytes(b"qr-\xfd.png"))));
+            assert_eq!(names.len(), 3);
+        }
+
         #[test]
         fn test_logo_size_validation() {
             let logo_config = LogoConfig {
-                path: "test.png".to_string(),
+                path: PathBuf::from("test.png"),
                 size_percent: 50, // Too large
+                ..Default::default()
             };
             let config = QrConfig {
                 content: "test".to_string(),
@@ -0,0 +1,1054 @@
+use super::*;
+use image::GenericImage;
+use serde::{Deserialize, Serialize};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Base URL of the Stability AI stable-image generation endpoints
+pub const DEFAULT_BASE_URL: &str = "https://api.stability.ai/v2beta/stable-image/generate";
+
+/// Aspect ratios accepted by the Stability AI stable-image endpoints
+pub const ALLOWED_ASPECT_RATIOS: &[&str] = &[
+    "16:9", "1:1", "21:9", "2:3", "3:2", "4:5", "5:4", "9:16", "9:21",
+];
+
+/// Output formats that can be decoded back into an image for the overlay step
+pub const ALLOWED_OUTPUT_FORMATS: &[&str] = &["png", "jpeg"];
+
+/// Configuration for the AI background request
+#[derive(Debug, Clone)]
+pub struct AiConfig {
+    pub base_url: String,
+    pub model: String,
+    pub aspect_ratio: String,
+    pub output_format: String,
+    pub negative_prompt: Option<String>,
+    /// Opacity of the QR modules over the AI art (0.0-1.0); light modules
+    /// are blended more transparently than dark ones
+    pub overlay_opacity: f32,
+    /// Per-attempt request timeout
+    pub timeout: Duration,
+    /// Extra attempts made after a timeout, 429 or 5xx response
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled on each subsequent one. A 429
+    /// with a `Retry-After` header waits as long as that asks instead
+    pub retry_backoff: Duration,
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model: "core".to_string(),
+            aspect_ratio: "1:1".to_string(),
+            output_format: "png".to_string(),
+            negative_prompt: None,
+            overlay_opacity: 0.85,
+            timeout: Duration::from_secs(60),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl AiConfig {
+    /// Full endpoint URL for the configured model
+    pub fn endpoint(&self) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), self.model)
+    }
+
+    pub fn validate(&self) -> Result<(), QrError> {
+        if self.model.is_empty() {
+            return Err(QrError::InvalidAiConfig(
+                "model cannot be empty".to_string(),
+            ));
+        }
+        if !ALLOWED_ASPECT_RATIOS.contains(&self.aspect_ratio.as_str()) {
+            return Err(QrError::InvalidAiConfig(format!(
+                "unsupported aspect ratio '{}' (expected one of: {})",
+                self.aspect_ratio,
+                ALLOWED_ASPECT_RATIOS.join(", ")
+            )));
+        }
+        if !ALLOWED_OUTPUT_FORMATS.contains(&self.output_format.as_str()) {
+            return Err(QrError::InvalidAiConfig(format!(
+                "unsupported output format '{}' (expected one of: {})",
+                self.output_format,
+                ALLOWED_OUTPUT_FORMATS.join(", ")
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.overlay_opacity) {
+            return Err(QrError::InvalidAiConfig(format!(
+                "overlay opacity must be between 0.0 and 1.0, got {}",
+                self.overlay_opacity
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Result of an AI-styled QR generation
+#[derive(Debug)]
+pub struct AiQrResult {
+    pub image: DynamicImage,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StabilityRequest<'a> {
+    prompt: String,
+    output_format: &'a str,
+    aspect_ratio: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    negative_prompt: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StabilityResponse {
+    image: String,
+}
+
+/// A source of artwork that a QR code can be blended onto
+pub trait QrBackgroundProvider {
+    fn generate(&self, prompt: &str, cfg: &AiConfig) -> Result<DynamicImage, QrError>;
+}
+
+/// Background provider backed by the Stability AI stable-image API
+#[derive(Debug, Clone)]
+pub struct StabilityProvider {
+    api_key: String,
+}
+
+impl StabilityProvider {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+        }
+    }
+}
+
+impl StabilityProvider {
+    fn authorization(&self) -> String {
+        format!("Bearer {}", self.api_key)
+    }
+
+    fn request_once(
+        &self,
+        client: &reqwest::blocking::Client,
+        request: &StabilityRequest,
+        cfg: &AiConfig,
+    ) -> Result<DynamicImage, QrError> {
+        let response = client
+            .post(cfg.endpoint())
+            .header("Authorization", self.authorization())
+            .header("Accept", "application/json")
+            .form(request)
+            .send()
+            .map_err(|e| transport_error(e, cfg, "API request failed"))?;
+
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(rate_limited(response.headers()));
+        }
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().unwrap_or_default();
+            return Err(QrError::AiApiError { status, body });
+        }
+
+        let result: StabilityResponse = response
+            .json()
+            .map_err(|e| transport_error(e, cfg, "Failed to parse response"))?;
+        decode_response(result)
+    }
+}
+
+impl QrBackgroundProvider for StabilityProvider {
+    fn generate(&self, prompt: &str, cfg: &AiConfig) -> Result<DynamicImage, QrError> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(cfg.timeout)
+            .build()
+            .map_err(client_error)?;
+
+        let request = StabilityRequest::new(prompt, cfg);
+
+        let mut attempt = 0;
+        loop {
+            match self.request_once(&client, &request, cfg) {
+                Err(e) if attempt < cfg.max_retries && is_transient(&e) => {
+                    std::thread::sleep(retry_delay(cfg, attempt, &e));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<'a> StabilityRequest<'a> {
+    fn new(prompt: &str, cfg: &'a AiConfig) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            output_format: &cfg.output_format,
+            aspect_ratio: &cfg.aspect_ratio,
+            negative_prompt: cfg.negative_prompt.as_deref(),
+        }
+    }
+}
+
+fn client_error(e: reqwest::Error) -> QrError {
+    QrError::ImageProcessingFailed(format!("HTTP client error: {}", e))
+}
+
+/// Map a failed send or read, keeping timeouts distinct so they are retried
+fn transport_error(e: reqwest::Error, cfg: &AiConfig, action: &str) -> QrError {
+    if e.is_timeout() {
+        QrError::AiTimeout(cfg.timeout)
+    } else {
+        QrError::ImageProcessingFailed(format!("{}: {}", action, e))
+    }
+}
+
+/// Decode the base64 image in a successful response
+fn decode_response(result: StabilityResponse) -> Result<DynamicImage, QrError> {
+    let image_bytes =
+        base64::Engine::decode(&base64::engine::general_purpose::STANDARD, &result.image).map_err(
+            |e| QrError::ImageProcessingFailed(format!("Failed to decode image: {}", e)),
+        )?;
+
+    image::load_from_memory(&image_bytes)
+        .map_err(|e| QrError::ImageProcessingFailed(format!("Failed to load image: {}", e)))
+}
+
+/// A 429 response, with its `Retry-After` when given in seconds (the
+/// HTTP-date form falls back to the usual backoff)
+fn rate_limited(headers: &reqwest::header::HeaderMap) -> QrError {
+    let retry_after = headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs);
+    QrError::AiRateLimited { retry_after }
+}
+
+/// Longest backoff between retries, however many attempts came before
+pub const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Delay before retry number `attempt` (from 0) after `err`: what the
+/// server asked for, or else doubling each time up to [`MAX_RETRY_BACKOFF`]
+fn retry_delay(cfg: &AiConfig, attempt: u32, err: &QrError) -> Duration {
+    match err {
+        QrError::AiRateLimited {
+            retry_after: Some(delay),
+        } => *delay,
+        _ => {
+            let factor = 2u32.checked_pow(attempt).unwrap_or(u32::MAX);
+            cfg.retry_backoff
+                .saturating_mul(factor)
+                .min(MAX_RETRY_BACKOFF)
+        }
+    }
+}
+
+/// Whether a failed request is worth retrying
+fn is_transient(err: &QrError) -> bool {
+    match err {
+        QrError::AiTimeout(_) | QrError::AiRateLimited { .. } => true,
+        QrError::AiApiError { status, .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+/// Limits on calls through a [`RateLimitedProvider`]; `None` means unlimited
+#[derive(Debug, Clone, Default)]
+pub struct RateLimit {
+    /// Most calls allowed to run at once
+    pub max_concurrent: Option<usize>,
+    /// Most calls started in any minute; starts are spaced evenly
+    pub requests_per_minute: Option<u32>,
+}
+
+/// Wraps a provider so calls from many threads, e.g. a batch, stay within a
+/// [`RateLimit`]
+///
+/// Each `generate` waits for a free slot and its turn before calling the
+/// inner provider. Retries inside the provider aren't counted again; a 429
+/// from Stability AI is retried after its `Retry-After`.
+pub struct RateLimitedProvider<P> {
+    inner: P,
+    limit: RateLimit,
+    state: Mutex<LimiterState>,
+    freed: Condvar,
+}
+
+struct LimiterState {
+    in_flight: usize,
+    /// Earliest time the next call may start
+    next_start: Instant,
+}
+
+impl<P: QrBackgroundProvider> RateLimitedProvider<P> {
+    pub fn new(inner: P, limit: RateLimit) -> Self {
+        Self {
+            inner,
+            limit,
+            state: Mutex::new(LimiterState {
+                in_flight: 0,
+                next_start: Instant::now(),
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Wait for a slot and reserve the next start time
+    ///
+    /// The slot is given back when the returned guard drops, even if the
+    /// call it covers panics.
+    fn acquire(&self) -> Slot<'_> {
+        let max = self.limit.max_concurrent.unwrap_or(usize::MAX).max(1);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while state.in_flight >= max {
+            state = self.freed.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        state.in_flight += 1;
+        let start = state.next_start.max(Instant::now());
+        if let Some(rpm) = self.limit.requests_per_minute.filter(|&rpm| rpm > 0) {
+            state.next_start = start + Duration::from_secs(60) / rpm;
+        }
+        Slot {
+            state: &self.state,
+            freed: &self.freed,
+            start,
+        }
+    }
+}
+
+/// One reserved call through a [`RateLimitedProvider`]
+struct Slot<'a> {
+    state: &'a Mutex<LimiterState>,
+    freed: &'a Condvar,
+    /// When the call may start
+    start: Instant,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.in_flight -= 1;
+        self.freed.notify_one();
+    }
+}
+
+impl<P: QrBackgroundProvider> QrBackgroundProvider for RateLimitedProvider<P> {
+    fn generate(&self, prompt: &str, cfg: &AiConfig) -> Result<DynamicImage, QrError> {
+        let slot = self.acquire();
+        std::thread::sleep(slot.start.saturating_duration_since(Instant::now()));
+        self.inner.generate(prompt, cfg)
+    }
+}
+
+pub fn generate_ai_qr(
+    qr: &QrCode,
+    prompt: &str,
+    provider: &dyn QrBackgroundProvider,
+    image_config: &ImageConfig,
+    ai_config: &AiConfig,
+) -> Result<AiQrResult, QrError> {
+    ai_config.validate()?;
+    let background = provider.generate(&styled_prompt(prompt), ai_config)?;
+    blend_onto_background(background, qr, image_config, ai_config)
+}
+
+fn styled_prompt(prompt: &str) -> String {
+    format!(
+        "A QR code with artistic styling: {}. The QR code pattern should remain scannable.",
+        prompt
+    )
+}
+
+/// Composite `qr` onto the generated art and check the result still scans
+fn blend_onto_background(
+    mut background: DynamicImage,
+    qr: &QrCode,
+    image_config: &ImageConfig,
+    ai_config: &AiConfig,
+) -> Result<AiQrResult, QrError> {
+    let (origin, scale) =
+        composite_qr(&mut background, qr, image_config, ai_config.overlay_opacity)?;
+
+    let mut warnings = Vec::new();
+    if !is_likely_scannable(&background, qr, origin, scale) {
+        warnings.push(format!(
+            "Blended QR may not scan at opacity {:.2}; try a higher opacity",
+            ai_config.overlay_opacity
+        ));
+    }
+
+    Ok(AiQrResult {
+        image: background,
+        warnings,
+    })
+}
+
+/// Alpha-blend the QR code centered onto `background`.
+///
+/// Dark modules use `opacity` directly while light modules use `opacity²`,
+/// so the art shows through the light areas first. Returns the origin of the
+/// rendered code and the module scale used.
+fn composite_qr(
+    background: &mut DynamicImage,
+    qr: &QrCode,
+    image_config: &ImageConfig,
+    opacity: f32,
+) -> Result<((u32, u32), u32), QrError> {
+    let (bg_width, bg_height) = background.dimensions();
+    let qr_modules = qr.width() as u32 + QUIET_ZONE_MODULES * 2;
+    let scale = bg_width.min(bg_height).saturating_sub(40) / qr_modules;
+
+    if scale == 0 {
+        return Err(QrError::BackgroundTooSmall);
+    }
+
+    let qr_config = ImageConfig {
+        scale,
+        ..image_config.clone()
+    };
+
+    let qr_image = render_to_image(qr, &qr_config).to_rgba8();
+    let (qr_width, qr_height) = qr_image.dimensions();
+
+    let x = (bg_width - qr_width) / 2;
+    let y = (bg_height - qr_height) / 2;
+
+    let width = qr.width();
+    let colors = qr.to_colors();
+    let is_dark = |px: u32, py: u32| {
+        let mx = (px / scale).checked_sub(QUIET_ZONE_MODULES)? as usize;
+        let my = (py / scale).checked_sub(QUIET_ZONE_MODULES)? as usize;
+        (mx < width && my < width).then(|| colors[my * width + mx] == qrcode::Color::Dark)
+    };
+
+    for (px, py, pixel) in qr_image.enumerate_pixels() {
+        let alpha = if is_dark(px, py).unwrap_or(false) {
+            opacity
+        } else {
+            opacity * opacity
+        };
+        let under = background.get_pixel(x + px, y + py);
+        let blend = |top: u8, bottom: u8| {
+            (top as f32 * alpha + bottom as f32 * (1.0 - alpha)).round() as u8
+        };
+        let blended = image::Rgba([
+            blend(pixel[0], under[0]),
+            blend(pixel[1], under[1]),
+            blend(pixel[2], under[2]),
+            blend(255, under[3]),
+        ]);
+        background.put_pixel(x + px, y + py, blended);
+    }
+
+    Ok(((x, y), scale))
+}
+
+#[cfg(feature = "ai-async")]
+mod nonblocking {
+    use super::*;
+    use std::future::Future;
+
+    /// Async counterpart of [`QrBackgroundProvider`], for use inside a
+    /// runtime where blocking requests would panic
+    pub trait AsyncQrBackgroundProvider {
+        fn generate_async(
+            &self,
+            prompt: &str,
+            cfg: &AiConfig,
+        ) -> impl Future<Output = Result<DynamicImage, QrError>> + Send;
+    }
+
+    impl StabilityProvider {
+        async fn request_once_async(
+            &self,
+            client: &reqwest::Client,
+            request: &StabilityRequest<'_>,
+            cfg: &AiConfig,
+        ) -> Result<DynamicImage, QrError> {
+            let response = client
+                .post(cfg.endpoint())
+                .header("Authorization", self.authorization())
+                .header("Accept", "application/json")
+                .form(request)
+                .send()
+                .await
+                .map_err(|e| transport_error(e, cfg, "API request failed"))?;
+
+            if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(rate_limited(response.headers()));
+            }
+            if !response.status().is_success() {
+                let status = response.status().as_u16();
+                let body = response.text().await.unwrap_or_default();
+                return Err(QrError::AiApiError { status, body });
+            }
+
+            let result: StabilityResponse = response
+                .json()
+                .await
+                .map_err(|e| transport_error(e, cfg, "Failed to parse response"))?;
+            decode_response(result)
+        }
+    }
+
+    impl AsyncQrBackgroundProvider for StabilityProvider {
+        async fn generate_async(
+            &self,
+            prompt: &str,
+            cfg: &AiConfig,
+        ) -> Result<DynamicImage, QrError> {
+            let client = reqwest::Client::builder()
+                .timeout(cfg.timeout)
+                .build()
+                .map_err(client_error)?;
+
+            let request = StabilityRequest::new(prompt, cfg);
+
+            let mut attempt = 0;
+            loop {
+                match self.request_once_async(&client, &request, cfg).await {
+                    Err(e) if attempt < cfg.max_retries && is_transient(&e) => {
+                        tokio::time::sleep(retry_delay(cfg, attempt, &e)).await;
+                        attempt += 1;
+                    }
+                    result => return result,
+                }
+            }
+        }
+    }
+
+    /// [`generate_ai_qr`] without blocking the calling thread
+    pub async fn generate_ai_qr_async<P: AsyncQrBackgroundProvider>(
+        qr: &QrCode,
+        prompt: &str,
+        provider: &P,
+        image_config: &ImageConfig,
+        ai_config: &AiConfig,
+    ) -> Result<AiQrResult, QrError> {
+        ai_config.validate()?;
+        let background = provider
+            .generate_async(&styled_prompt(prompt), ai_config)
+            .await?;
+        blend_onto_background(background, qr, image_config, ai_config)
+    }
+}
+
+#[cfg(feature = "ai-async")]
+pub use nonblocking::{generate_ai_qr_async, AsyncQrBackgroundProvider};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Encode a blank PNG the way the Stability API returns it
+    fn fake_image_body(width: u32, height: u32) -> String {
+        let mut png = Vec::new();
+        DynamicImage::new_rgb8(width, height)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png);
+        format!("{{\"image\":\"{}\"}}", encoded)
+    }
+
+    fn read_request(stream: &mut std::net::TcpStream) -> String {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut chunk).unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf).to_string();
+            if let Some(header_end) = text.find("\r\n\r\n") {
+                let content_length = text[..header_end]
+                    .lines()
+                    .find_map(|l| {
+                        let (name, value) = l.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if buf.len() >= header_end + 4 + content_length || n == 0 {
+                    return text;
+                }
+            }
+        }
+    }
+
+    /// Serve canned HTTP responses in order, one per connection, and hand
+    /// back the raw requests
+    fn serve_sequence(responses: Vec<(&str, String)>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let responses: Vec<(String, String)> = responses
+            .into_iter()
+            .map(|(status, body)| (status.to_string(), body))
+            .collect();
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                requests.push(read_request(&mut stream));
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (format!("http://{}", addr), handle)
+    }
+
+    fn serve_once(status: &str, body: String) -> (String, thread::JoinHandle<String>) {
+        let (url, handle) = serve_sequence(vec![(status, body)]);
+        let handle = thread::spawn(move || handle.join().unwrap().remove(0));
+        (url, handle)
+    }
+
+    /// Provider that returns a solid-color canvas without any network access
+    struct SolidProvider([u8; 3]);
+
+    impl QrBackgroundProvider for SolidProvider {
+        fn generate(&self, _prompt: &str, _cfg: &AiConfig) -> Result<DynamicImage, QrError> {
+            Ok(DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+                400,
+                400,
+                Rgb(self.0),
+            )))
+        }
+    }
+
+    fn test_qr() -> QrCode {
+        generate_qr(&QrConfig {
+            content: "https://example.com".to_string(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(AiConfig::default().validate().is_ok());
+        assert!(AiConfig::default().endpoint().ends_with("/generate/core"));
+    }
+
+    #[test]
+    fn test_invalid_aspect_ratio() {
+        let config = AiConfig {
+            aspect_ratio: "7:3".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(QrError::InvalidAiConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_output_format() {
+        let config = AiConfig {
+            output_format: "gif".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(QrError::InvalidAiConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_request_serializes_config_fields() {
+        let (base_url, server) = serve_once("200 OK", fake_image_body(400, 400));
+        let config = AiConfig {
+            base_url,
+            model: "ultra".to_string(),
+            aspect_ratio: "16:9".to_string(),
+            output_format: "jpeg".to_string(),
+            negative_prompt: Some("blurry".to_string()),
+            ..Default::default()
+        };
+
+        let result = generate_ai_qr(
+            &test_qr(),
+            "watercolor",
+            &StabilityProvider::new("key"),
+            &ImageConfig::default(),
+            &config,
+        )
+        .unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.starts_with("POST /ultra "));
+        assert!(request.contains("aspect_ratio=16%3A9"));
+        assert!(request.contains("output_format=jpeg"));
+        assert!(request.contains("negative_prompt=blurry"));
+        assert!(request.contains("watercolor"));
+        assert_eq!(result.image.dimensions(), (400, 400));
+    }
+
+    #[test]
+    fn test_negative_prompt_omitted_when_unset() {
+        let (base_url, server) = serve_once("200 OK", fake_image_body(400, 400));
+        let config = AiConfig {
+            base_url,
+            ..Default::default()
+        };
+
+        generate_ai_qr(
+            &test_qr(),
+            "neon",
+            &StabilityProvider::new("key"),
+            &ImageConfig::default(),
+            &config,
+        )
+        .unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.contains("aspect_ratio=1%3A1"));
+        assert!(!request.contains("negative_prompt"));
+    }
+
+    #[test]
+    fn test_custom_provider_background_is_overlaid() {
+        let config = AiConfig {
+            overlay_opacity: 1.0,
+            ..Default::default()
+        };
+        let result = generate_ai_qr(
+            &test_qr(),
+            "anything",
+            &SolidProvider([10, 200, 30]),
+            &ImageConfig::default(),
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(result.image.dimensions(), (400, 400));
+        // Margin outside the code keeps the provider's color
+        assert_eq!(result.image.get_pixel(0, 0).0, [10, 200, 30, 255]);
+        // Center of the canvas is covered by QR modules
+        let center = result.image.get_pixel(200, 200).0;
+        assert!(center == [0, 0, 0, 255] || center == [255, 255, 255, 255]);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_retries_transient_errors() {
+        let (base_url, server) = serve_sequence(vec![
+            ("503 Service Unavailable", "{}".to_string()),
+            ("503 Service Unavailable", "{}".to_string()),
+            ("200 OK", fake_image_body(400, 400)),
+        ]);
+        let config = AiConfig {
+            base_url,
+            retry_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+
+        let image = StabilityProvider::new("key")
+            .generate("neon", &config)
+            .unwrap();
+        assert_eq!(image.dimensions(), (400, 400));
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_rate_limited_retry_honors_retry_after() {
+        // The extra header rides along on the status line
+        let (base_url, server) = serve_sequence(vec![
+            ("429 Too Many Requests\r\nRetry-After: 1", "{}".to_string()),
+            ("200 OK", fake_image_body(400, 400)),
+        ]);
+        let config = AiConfig {
+            base_url,
+            retry_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+
+        let started = Instant::now();
+        StabilityProvider::new("key")
+            .generate("neon", &config)
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(server.join().unwrap().len(), 2);
+
+        let (base_url, server) = serve_once("429 Too Many Requests", "{}".to_string());
+        let config = AiConfig {
+            base_url,
+            max_retries: 0,
+            ..Default::default()
+        };
+        let result = StabilityProvider::new("key").generate("neon", &config);
+        server.join().unwrap();
+        assert!(matches!(
+            result,
+            Err(QrError::AiRateLimited { retry_after: None })
+        ));
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        let cfg = AiConfig {
+            retry_backoff: Duration::from_millis(500),
+            ..Default::default()
+        };
+        let err = QrError::AiTimeout(Duration::from_secs(1));
+        assert_eq!(retry_delay(&cfg, 0, &err), Duration::from_millis(500));
+        assert_eq!(retry_delay(&cfg, 3, &err), Duration::from_secs(4));
+        for attempt in [7, 31, 32, 64, u32::MAX] {
+            assert_eq!(retry_delay(&cfg, attempt, &err), MAX_RETRY_BACKOFF);
+        }
+    }
+
+    /// Records how many calls overlap
+    #[derive(Default)]
+    struct CountingProvider {
+        in_flight: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    impl QrBackgroundProvider for CountingProvider {
+        fn generate(&self, _prompt: &str, _cfg: &AiConfig) -> Result<DynamicImage, QrError> {
+            use std::sync::atomic::Ordering;
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(DynamicImage::new_rgb8(1, 1))
+        }
+    }
+
+    #[test]
+    fn test_rate_limiter_caps_in_flight_requests() {
+        let limit = RateLimit {
+            max_concurrent: Some(2),
+            ..Default::default()
+        };
+        let provider = RateLimitedProvider::new(CountingProvider::default(), limit);
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| provider.generate("neon", &AiConfig::default()).unwrap());
+            }
+        });
+        assert_eq!(
+            provider
+                .inner
+                .peak
+                .load(std::sync::atomic::Ordering::SeqCst),
+            2
+        );
+        assert_eq!(provider.state.lock().unwrap().in_flight, 0);
+    }
+
+    struct PanickingProvider;
+
+    impl QrBackgroundProvider for PanickingProvider {
+        fn generate(&self, _prompt: &str, _cfg: &AiConfig) -> Result<DynamicImage, QrError> {
+            panic!("provider failed");
+        }
+    }
+
+    #[test]
+    fn test_rate_limiter_frees_slot_when_provider_panics() {
+        let limit = RateLimit {
+            max_concurrent: Some(1),
+            ..Default::default()
+        };
+        let provider = RateLimitedProvider::new(PanickingProvider, limit);
+        for _ in 0..2 {
+            let call = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                provider.generate("neon", &AiConfig::default())
+            }));
+            assert!(call.is_err());
+        }
+        assert_eq!(provider.state.lock().unwrap().in_flight, 0);
+    }
+
+    #[test]
+    fn test_rate_limiter_spaces_requests() {
+        // 1200 a minute is one every 50ms
+        let limit = RateLimit {
+            requests_per_minute: Some(1200),
+            ..Default::default()
+        };
+        let provider = RateLimitedProvider::new(SolidProvider([0, 0, 0]), limit);
+        let started = Instant::now();
+        for _ in 0..4 {
+            provider.generate("neon", &AiConfig::default()).unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[cfg(feature = "ai-async")]
+    #[tokio::test]
+    async fn test_async_generation_returns_overlaid_image() {
+        let (base_url, server) = serve_sequence(vec![
+            ("503 Service Unavailable", "{}".to_string()),
+            ("200 OK", fake_image_body(400, 400)),
+        ]);
+        let config = AiConfig {
+            base_url,
+            overlay_opacity: 1.0,
+            retry_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+
+        let result = generate_ai_qr_async(
+            &test_qr(),
+            "neon",
+            &StabilityProvider::new("key"),
+            &ImageConfig::default(),
+            &config,
+        )
+        .await
+        .unwrap();
+        let requests = server.join().unwrap();
+
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1]
+            .to_lowercase()
+            .contains("authorization: bearer key"));
+        assert_eq!(result.image.dimensions(), (400, 400));
+        // The art was all black; the margin stays so and light modules cover the middle
+        assert_eq!(result.image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        let has_light =
+            (100..300).any(|x| result.image.get_pixel(x, 200).0 == [255, 255, 255, 255]);
+        assert!(has_light);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_client_errors_are_not_retried() {
+        let (base_url, server) =
+            serve_once("400 Bad Request", "{\"name\":\"bad_request\"}".to_string());
+        let config = AiConfig {
+            base_url,
+            retry_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+
+        let result = StabilityProvider::new("key").generate("neon", &config);
+        server.join().unwrap();
+        assert!(matches!(
+            result,
+            Err(QrError::AiApiError { status: 400, .. })
+        ));
+    }
+
+    #[test]
+    fn test_timeout_is_reported_distinctly() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_millis(500));
+            drop(stream);
+        });
+        let config = AiConfig {
+            base_url: format!("http://{}", addr),
+            timeout: Duration::from_millis(100),
+            max_retries: 0,
+            ..Default::default()
+        };
+
+        let result = StabilityProvider::new("key").generate("neon", &config);
+        server.join().unwrap();
+        assert!(matches!(result, Err(QrError::AiTimeout(_))));
+    }
+
+    #[test]
+    fn test_invalid_opacity() {
+        let config = AiConfig {
+            overlay_opacity: 1.5,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(QrError::InvalidAiConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_full_opacity_matches_hard_overlay() {
+        let qr = test_qr();
+        let image_config = ImageConfig::default();
+        let background =
+            DynamicImage::ImageRgb8(image::RgbImage::from_pixel(400, 400, Rgb([200, 40, 90])));
+
+        let mut blended = background.clone();
+        let ((x, y), scale) = composite_qr(&mut blended, &qr, &image_config, 1.0).unwrap();
+
+        let mut expected = background;
+        let qr_image = render_to_image(
+            &qr,
+            &ImageConfig {
+                scale,
+                ..image_config
+            },
+        );
+        image::imageops::overlay(&mut expected, &qr_image, x as i64, y as i64);
+
+        assert_eq!(blended.to_rgba8(), expected.to_rgba8());
+        assert!(is_likely_scannable(&blended, &qr, (x, y), scale));
+    }
+
+    #[test]
+    fn test_partial_opacity_lets_background_through() {
+        let qr = test_qr();
+        let background =
+            DynamicImage::ImageRgb8(image::RgbImage::from_pixel(400, 400, Rgb([200, 40, 90])));
+
+        let mut blended = background.clone();
+        let ((x, y), scale) =
+            composite_qr(&mut blended, &qr, &ImageConfig::default(), 0.8).unwrap();
+
+        // Top-left finder module is dark, the quiet zone corner is light
+        let dark = blended.get_pixel(
+            x + QUIET_ZONE_MODULES * scale,
+            y + QUIET_ZONE_MODULES * scale,
+        );
+        let light = blended.get_pixel(x, y);
+        // Dark at 0.8 alpha, light at 0.64 alpha over red 200
+        assert_eq!(dark[0], 40);
+        assert_eq!(light[0], 235);
+        assert!(is_likely_scannable(&blended, &qr, (x, y), scale));
+    }
+
+    #[test]
+    fn test_low_opacity_warns_unscannable() {
+        let qr = test_qr();
+        let background =
+            DynamicImage::ImageRgb8(image::RgbImage::from_pixel(400, 400, Rgb([128, 128, 128])));
+
+        let mut blended = background.clone();
+        let ((x, y), scale) =
+            composite_qr(&mut blended, &qr, &ImageConfig::default(), 0.0).unwrap();
+        assert!(!is_likely_scannable(&blended, &qr, (x, y), scale));
+    }
+
+    #[test]
+    fn test_invalid_config_fails_before_request() {
+        let config = AiConfig {
+            aspect_ratio: "square".to_string(),
+            ..Default::default()
+        };
+        let result = generate_ai_qr(
+            &test_qr(),
+            "neon",
+            &SolidProvider([0, 0, 0]),
+            &ImageConfig::default(),
+            &config,
+        );
+        assert!(matches!(result, Err(QrError::InvalidAiConfig(_))));
+    }
+}
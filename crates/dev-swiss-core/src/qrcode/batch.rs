@@ -0,0 +1,241 @@
+//! Rendering many codes to individual files, e.g. numbered ticket ranges.
+
+use super::{generate_qr, save_qr, ImageConfig, OutputFormat, QrConfig, QrError};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+/// Placeholder replaced with the sequence number in range templates
+pub const RANGE_PLACEHOLDER: &str = "{n}";
+
+/// One code to render and the file it goes to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchItem {
+    pub content: String,
+    pub path: PathBuf,
+}
+
+/// What to do when an output file is already present
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExistingOutput {
+    /// Fail before writing anything
+    #[default]
+    Error,
+    /// Leave the existing file alone
+    Skip,
+    Overwrite,
+}
+
+/// How a single output path will be handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputAction {
+    Create,
+    Skip,
+    Overwrite,
+}
+
+/// Files written and left alone by `render_batch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatchSummary {
+    pub created: usize,
+    pub skipped: usize,
+    pub overwritten: usize,
+}
+
+/// Decide what happens to `path` under the `existing` policy
+pub fn output_action(path: &Path, existing: ExistingOutput) -> Result<OutputAction, QrError> {
+    if !path.exists() {
+        return Ok(OutputAction::Create);
+    }
+    match existing {
+        ExistingOutput::Error => Err(QrError::OutputExists(path.to_path_buf())),
+        ExistingOutput::Skip => Ok(OutputAction::Skip),
+        ExistingOutput::Overwrite => Ok(OutputAction::Overwrite),
+    }
+}
+
+/// Parse an inclusive `START-END` range such as `1-100`
+pub fn parse_range(spec: &str) -> Result<RangeInclusive<u64>, QrError> {
+    let invalid =
+        || QrError::InvalidRange(format!("{} (expected START-END, e.g. 1-100)", spec.trim()));
+    let (start, end) = spec.trim().split_once('-').ok_or_else(invalid)?;
+    let start: u64 = start.trim().parse().map_err(|_| invalid())?;
+    let end: u64 = end.trim().parse().map_err(|_| invalid())?;
+    if start > end {
+        return Err(invalid());
+    }
+    Ok(start..=end)
+}
+
+/// Replace every `{n}` in `template` with `n`, zero-padded to `pad` digits
+pub fn expand_template(template: &str, n: u64, pad: usize) -> String {
+    template.replace(RANGE_PLACEHOLDER, &format!("{:0pad$}", n, pad = pad))
+}
+
+/// One item per number in `range`, saved in `dir` as `<n>.<extension>`
+///
+/// File names use the same padding as the content so they sort in order.
+pub fn range_items(
+    template: &str,
+    range: RangeInclusive<u64>,
+    pad: usize,
+    dir: &Path,
+    extension: &str,
+) -> Result<Vec<BatchItem>, QrError> {
+    if !template.contains(RANGE_PLACEHOLDER) {
+        return Err(QrError::InvalidRange(format!(
+            "template has no {} placeholder",
+            RANGE_PLACEHOLDER
+        )));
+    }
+    Ok(range
+        .map(|n| BatchItem {
+            content: expand_template(template, n, pad),
+            path: dir.join(format!("{:0pad$}.{}", n, extension, pad = pad)),
+        })
+        .collect())
+}
+
+/// Render each item to its path as PNG or SVG
+///
+/// `qr_config.content` is ignored in favor of each item's content. With
+/// `ExistingOutput::Error`, every path is checked before anything is written,
+/// so a collision leaves the directory untouched.
+pub fn render_batch(
+    items: &[BatchItem],
+    format: OutputFormat,
+    qr_config: &QrConfig,
+    image_config: &ImageConfig,
+    existing: ExistingOutput,
+) -> Result<BatchSummary, QrError> {
+    if matches!(format, OutputFormat::Terminal | OutputFormat::Unicode) {
+        return Err(QrError::IoError(
+            "batch output must be PNG, SVG or PDF".to_string(),
+        ));
+    }
+    let actions = items
+        .iter()
+        .map(|item| output_action(&item.path, existing))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut summary = BatchSummary::default();
+    for (item, action) in items.iter().zip(actions) {
+        match action {
+            OutputAction::Skip => {
+                summary.skipped += 1;
+                continue;
+            }
+            OutputAction::Create => summary.created += 1,
+            OutputAction::Overwrite => summary.overwritten += 1,
+        }
+
+        let config = QrConfig {
+            content: item.content.clone(),
+            ..qr_config.clone()
+        };
+        save_qr(&generate_qr(&config)?, format, image_config, &item.path)?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("1-100").unwrap(), 1..=100);
+        assert_eq!(parse_range(" 7 - 7 ").unwrap(), 7..=7);
+        for bad in ["", "5", "10-1", "a-3", "-1-3", "1-"] {
+            assert!(
+                matches!(parse_range(bad), Err(QrError::InvalidRange(_))),
+                "{:?}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_expand_template() {
+        assert_eq!(
+            expand_template("https://x.com/t/{n}", 7, 0),
+            "https://x.com/t/7"
+        );
+        assert_eq!(
+            expand_template("https://x.com/t/{n}", 7, 4),
+            "https://x.com/t/0007"
+        );
+        assert_eq!(expand_template("{n}-{n}", 12345, 4), "12345-12345");
+    }
+
+    #[test]
+    fn test_range_items() {
+        let items = range_items("T{n}", 9..=11, 3, Path::new("out"), "png").unwrap();
+        let contents: Vec<&str> = items.iter().map(|i| i.content.as_str()).collect();
+        assert_eq!(contents, ["T009", "T010", "T011"]);
+        assert_eq!(items[0].path, Path::new("out").join("009.png"));
+
+        assert!(matches!(
+            range_items("no placeholder", 1..=2, 0, Path::new("out"), "png"),
+            Err(QrError::InvalidRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_render_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let items = range_items("https://x.com/t/{n}", 1..=3, 2, dir.path(), "svg").unwrap();
+        let summary = render(&items, ExistingOutput::Error).unwrap();
+
+        assert_eq!(summary.created, 3);
+        for name in ["01.svg", "02.svg", "03.svg"] {
+            assert!(std::fs::read_to_string(dir.path().join(name))
+                .unwrap()
+                .contains("<svg"));
+        }
+    }
+
+    fn render(items: &[BatchItem], existing: ExistingOutput) -> Result<BatchSummary, QrError> {
+        render_batch(
+            items,
+            OutputFormat::Svg,
+            &QrConfig::default(),
+            &ImageConfig::default(),
+            existing,
+        )
+    }
+
+    #[test]
+    fn test_render_batch_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let items = range_items("https://x.com/t/{n}", 1..=3, 0, dir.path(), "svg").unwrap();
+        let existing = dir.path().join("2.svg");
+        std::fs::write(&existing, "keep me").unwrap();
+
+        // The default refuses before writing anything
+        let refused = render(&items, ExistingOutput::Error);
+        assert!(matches!(refused, Err(QrError::OutputExists(p)) if p == existing));
+        assert!(!dir.path().join("1.svg").exists());
+
+        let summary = render(&items, ExistingOutput::Skip).unwrap();
+        assert_eq!(
+            summary,
+            BatchSummary {
+                created: 2,
+                skipped: 1,
+                overwritten: 0
+            }
+        );
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "keep me");
+
+        let summary = render(&items, ExistingOutput::Overwrite).unwrap();
+        assert_eq!(
+            summary,
+            BatchSummary {
+                created: 0,
+                skipped: 0,
+                overwritten: 3
+            }
+        );
+        assert!(std::fs::read_to_string(&existing).unwrap().contains("<svg"));
+    }
+}
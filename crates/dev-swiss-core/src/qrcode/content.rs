@@ -0,0 +1,448 @@
+//! Recognizing what QR content is, so scanners offer the right action,
+//! filling in templated content, compressing large payloads and checksumming
+//! them.
+
+use super::QrError;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::{Compression, Crc};
+use std::fmt;
+use std::io::{Read, Write};
+
+/// What a piece of QR content looks like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    /// A web address, with or without a scheme
+    Url,
+    Email,
+    Phone,
+    Text,
+}
+
+impl fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentKind::Url => write!(f, "URL"),
+            ContentKind::Email => write!(f, "email address"),
+            ContentKind::Phone => write!(f, "phone number"),
+            ContentKind::Text => write!(f, "text"),
+        }
+    }
+}
+
+/// Digits allowed in a phone number, as in E.164
+const PHONE_DIGITS: (usize, usize) = (7, 15);
+
+fn strip_prefix_ignore_case<'a>(content: &'a str, prefix: &str) -> Option<&'a str> {
+    content
+        .get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| &content[prefix.len()..])
+}
+
+/// `example.com`-style host: dot-separated labels ending in a 2+ letter TLD
+fn is_hostname(host: &str) -> bool {
+    let labels: Vec<&str> = host.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && labels
+            .last()
+            .is_some_and(|tld| tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+fn is_url(content: &str) -> bool {
+    if ["http://", "https://"]
+        .iter()
+        .any(|scheme| strip_prefix_ignore_case(content, scheme).is_some())
+    {
+        return true;
+    }
+    let host = content.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.split_once(':').map_or(host, |(host, _)| host);
+    is_hostname(host)
+}
+
+fn is_email(content: &str) -> bool {
+    let address = strip_prefix_ignore_case(content, "mailto:").unwrap_or(content);
+    match address.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty() && !local.contains(char::is_whitespace) && is_hostname(domain)
+        }
+        None => false,
+    }
+}
+
+fn phone_digits(content: &str) -> Option<String> {
+    let number = strip_prefix_ignore_case(content, "tel:").unwrap_or(content);
+    let (plus, rest) = match number.strip_prefix('+') {
+        Some(rest) => ("+", rest),
+        None => ("", number),
+    };
+    if !rest
+        .chars()
+        .all(|c| c.is_ascii_digit() || " -.()".contains(c))
+    {
+        return None;
+    }
+    let digits: String = rest.chars().filter(char::is_ascii_digit).collect();
+    (PHONE_DIGITS.0..=PHONE_DIGITS.1)
+        .contains(&digits.len())
+        .then(|| format!("{}{}", plus, digits))
+}
+
+/// Classify `content` as a URL, email address, phone number or plain text
+///
+/// Content already carrying a `mailto:` or `tel:` scheme is classified by it.
+/// Anything ambiguous is `Text`.
+pub fn detect_content_type(content: &str) -> ContentKind {
+    let content = content.trim();
+    // Only phone numbers may contain spaces
+    if content.is_empty()
+        || (content.contains(char::is_whitespace) && phone_digits(content).is_none())
+    {
+        return ContentKind::Text;
+    }
+    if is_email(content) {
+        ContentKind::Email
+    } else if phone_digits(content).is_some() {
+        ContentKind::Phone
+    } else if is_url(content) {
+        ContentKind::Url
+    } else {
+        ContentKind::Text
+    }
+}
+
+/// Wrap `content` in the URI scheme for its kind, e.g. `+1 555 123 4567`
+/// becomes `tel:+15551234567` and `example.com` becomes `https://example.com`
+///
+/// Text, and content that already has its scheme, is returned unchanged apart
+/// from phone number normalization.
+pub fn auto_wrap_content(content: &str) -> String {
+    let trimmed = content.trim();
+    match detect_content_type(trimmed) {
+        ContentKind::Url if trimmed.contains("://") => trimmed.to_string(),
+        ContentKind::Url => format!("https://{}", trimmed),
+        ContentKind::Email => match strip_prefix_ignore_case(trimmed, "mailto:") {
+            Some(address) => format!("mailto:{}", address),
+            None => format!("mailto:{}", trimmed),
+        },
+        ContentKind::Phone => format!("tel:{}", phone_digits(trimmed).unwrap_or_default()),
+        ContentKind::Text => content.to_string(),
+    }
+}
+
+/// Replace each `${VAR}` in `content` with the environment variable's value
+/// and each `$$` with a literal `$`
+///
+/// Unset variables expand to nothing; [`unset_env_vars`] lists them for
+/// callers that would rather warn or fail. A `$` followed by anything else is
+/// kept as is. An unterminated `${` or an empty `${}` is an error.
+pub fn expand_env(content: &str) -> Result<String, QrError> {
+    expand_env_with(content, |name| std::env::var(name).ok())
+}
+
+/// Names of the variables `content` refers to that aren't set, in order of
+/// first use
+pub fn unset_env_vars(content: &str) -> Vec<String> {
+    let mut unset = Vec::new();
+    let _ = expand_env_with(content, |name| {
+        let value = std::env::var(name).ok();
+        if value.is_none() && !unset.iter().any(|n| n == name) {
+            unset.push(name.to_string());
+        }
+        value
+    });
+    unset
+}
+
+fn expand_env_with(
+    content: &str,
+    mut lookup: impl FnMut(&str) -> Option<String>,
+) -> Result<String, QrError> {
+    let invalid = |reason: &str| QrError::InvalidEnvReference(reason.to_string());
+    let mut expanded = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(at) = rest.find('$') {
+        expanded.push_str(&rest[..at]);
+        let after = &rest[at + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+        } else if let Some(after) = after.strip_prefix('{') {
+            let end = after.find('}').ok_or_else(|| invalid("unterminated ${"))?;
+            let name = &after[..end];
+            if name.is_empty() {
+                return Err(invalid("empty ${}"));
+            }
+            expanded.push_str(&lookup(name).unwrap_or_default());
+            rest = &after[end + 1..];
+        } else {
+            expanded.push('$');
+            rest = after;
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Prefix marking a payload made by [`compress_content`]
+pub const COMPRESSED_CONTENT_MARKER: &[u8] = b"DSZ1";
+
+/// Gzip `content` and prefix [`COMPRESSED_CONTENT_MARKER`], so large
+/// repetitive payloads fit in a smaller code
+///
+/// Only readers that call [`decompress_content`] understand the result;
+/// ordinary scanners show it as binary noise.
+pub fn compress_content(content: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(COMPRESSED_CONTENT_MARKER.to_vec(), Compression::best());
+    encoder
+        .write_all(content)
+        .expect("writing to a Vec cannot fail");
+    encoder.finish().expect("writing to a Vec cannot fail")
+}
+
+/// Undo [`compress_content`]
+pub fn decompress_content(payload: &[u8]) -> Result<Vec<u8>, QrError> {
+    let compressed = payload
+        .strip_prefix(COMPRESSED_CONTENT_MARKER)
+        .ok_or_else(|| {
+            QrError::InvalidCompressedContent("missing compression marker".to_string())
+        })?;
+    let mut content = Vec::new();
+    GzDecoder::new(compressed)
+        .read_to_end(&mut content)
+        .map_err(|e| QrError::InvalidCompressedContent(e.to_string()))?;
+    Ok(content)
+}
+
+/// Separates content from the checksum added by [`append_crc`]
+pub const CRC_DELIMITER: &[u8] = b"#CRC32:";
+
+fn crc32(content: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(content);
+    crc.sum()
+}
+
+/// Append [`CRC_DELIMITER`] and the CRC32 of `content` as 8 uppercase hex
+/// digits, e.g. `hello#CRC32:3610A686`
+///
+/// This catches corrupted or misread payloads, not deliberate tampering:
+/// anyone can compute a new checksum for changed content.
+pub fn append_crc(content: &[u8]) -> Vec<u8> {
+    let mut payload = content.to_vec();
+    payload.extend_from_slice(CRC_DELIMITER);
+    payload.extend_from_slice(format!("{:08X}", crc32(content)).as_bytes());
+    payload
+}
+
+/// Check the checksum added by [`append_crc`] and return the content
+/// without it
+pub fn verify_crc(payload: &[u8]) -> Result<&[u8], QrError> {
+    let suffix = CRC_DELIMITER.len() + 8;
+    let split = payload
+        .len()
+        .checked_sub(suffix)
+        .ok_or(QrError::MissingCrc)?;
+    let (content, tail) = payload.split_at(split);
+    let expected = tail
+        .strip_prefix(CRC_DELIMITER)
+        .and_then(|hex| std::str::from_utf8(hex).ok())
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or(QrError::MissingCrc)?;
+    let actual = crc32(content);
+    if actual != expected {
+        return Err(QrError::CrcMismatch { expected, actual });
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        (name == "BUILD_ID").then(|| "1234".to_string())
+    }
+
+    #[test]
+    fn test_expand_env_set_variable() {
+        let expanded = expand_env_with("https://ci/${BUILD_ID}/log", lookup).unwrap();
+        assert_eq!(expanded, "https://ci/1234/log");
+
+        std::env::set_var("DEV_SWISS_TEST_EXPAND", "from-env");
+        assert_eq!(
+            expand_env("id=${DEV_SWISS_TEST_EXPAND}").unwrap(),
+            "id=from-env"
+        );
+        assert!(unset_env_vars("${DEV_SWISS_TEST_EXPAND}").is_empty());
+    }
+
+    #[test]
+    fn test_expand_env_unset_variable() {
+        assert_eq!(expand_env_with("a${MISSING}b", lookup).unwrap(), "ab");
+        assert_eq!(
+            unset_env_vars(
+                "${DEV_SWISS_TEST_UNSET}/${DEV_SWISS_TEST_UNSET}/${DEV_SWISS_TEST_UNSET_2}"
+            ),
+            ["DEV_SWISS_TEST_UNSET", "DEV_SWISS_TEST_UNSET_2"]
+        );
+    }
+
+    #[test]
+    fn test_expand_env_escapes_and_errors() {
+        assert_eq!(
+            expand_env_with("cost: $$5 and ${BUILD_ID}$$", lookup).unwrap(),
+            "cost: $5 and 1234$"
+        );
+        // $$ is consumed before a following brace is seen
+        assert_eq!(
+            expand_env_with("$${BUILD_ID}", lookup).unwrap(),
+            "${BUILD_ID}"
+        );
+        // A lone $ is left alone
+        assert_eq!(
+            expand_env_with("$5 or $HOME$", lookup).unwrap(),
+            "$5 or $HOME$"
+        );
+
+        assert!(matches!(
+            expand_env_with("x${BUILD_ID", lookup),
+            Err(QrError::InvalidEnvReference(_))
+        ));
+        assert!(matches!(
+            expand_env_with("x${}", lookup),
+            Err(QrError::InvalidEnvReference(_))
+        ));
+    }
+
+    #[test]
+    fn test_detect_url() {
+        for url in [
+            "https://example.com",
+            "HTTP://example.com/a?b=c",
+            "example.com",
+            "www.example.co.uk/path",
+            "localhost.dev:8080/x",
+        ] {
+            assert_eq!(detect_content_type(url), ContentKind::Url, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_detect_email() {
+        for email in [
+            "someone@example.com",
+            "first.last+tag@mail.example.org",
+            "mailto:a@b.io",
+        ] {
+            assert_eq!(detect_content_type(email), ContentKind::Email, "{}", email);
+        }
+    }
+
+    #[test]
+    fn test_detect_phone() {
+        for phone in [
+            "+15551234",
+            "+1 (555) 123-4567",
+            "555.123.4567",
+            "tel:+4930123456",
+        ] {
+            assert_eq!(detect_content_type(phone), ContentKind::Phone, "{}", phone);
+        }
+    }
+
+    #[test]
+    fn test_detect_text() {
+        for text in [
+            "hello world",
+            "12345",
+            "v1.2",
+            "a@b",
+            "Meet at 5pm",
+            "",
+            "1234567890123456789",
+        ] {
+            assert_eq!(detect_content_type(text), ContentKind::Text, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_auto_wrap_content() {
+        assert_eq!(auto_wrap_content("+15551234"), "tel:+15551234");
+        assert_eq!(auto_wrap_content("+1 (555) 123-4567"), "tel:+15551234567");
+        assert_eq!(
+            auto_wrap_content("someone@example.com"),
+            "mailto:someone@example.com"
+        );
+        assert_eq!(
+            auto_wrap_content("example.com/docs"),
+            "https://example.com/docs"
+        );
+        assert_eq!(
+            auto_wrap_content("https://example.com"),
+            "https://example.com"
+        );
+        assert_eq!(auto_wrap_content("mailto:a@b.io"), "mailto:a@b.io");
+        assert_eq!(auto_wrap_content("hello world"), "hello world");
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let json =
+            r#"{"items":[{"id":1,"tag":"alpha"},{"id":2,"tag":"alpha"},{"id":3,"tag":"alpha"}]}"#
+                .repeat(8);
+        let compressed = compress_content(json.as_bytes());
+        assert!(compressed.starts_with(COMPRESSED_CONTENT_MARKER));
+        assert!(compressed.len() < json.len() / 4);
+        assert_eq!(decompress_content(&compressed).unwrap(), json.as_bytes());
+
+        assert!(matches!(
+            decompress_content(b"plain text"),
+            Err(QrError::InvalidCompressedContent(_))
+        ));
+        assert!(matches!(
+            decompress_content(b"DSZ1 not gzip"),
+            Err(QrError::InvalidCompressedContent(_))
+        ));
+    }
+
+    #[test]
+    fn test_crc_round_trip() {
+        let payload = append_crc(b"hello");
+        assert_eq!(payload, b"hello#CRC32:3610A686");
+        assert_eq!(verify_crc(&payload).unwrap(), b"hello");
+
+        // Content that itself contains the delimiter is fine
+        let tricky = append_crc(b"a#CRC32:00000000");
+        assert_eq!(verify_crc(&tricky).unwrap(), b"a#CRC32:00000000");
+        assert_eq!(verify_crc(&append_crc(b"")).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_corrupted_payload_fails_crc() {
+        let mut payload = append_crc(b"order=1234;qty=5");
+        payload[6] = b'9';
+        assert!(matches!(
+            verify_crc(&payload),
+            Err(QrError::CrcMismatch { .. })
+        ));
+
+        assert!(matches!(
+            verify_crc(b"no checksum here"),
+            Err(QrError::MissingCrc)
+        ));
+        assert!(matches!(verify_crc(b"short"), Err(QrError::MissingCrc)));
+        assert!(matches!(
+            verify_crc(b"x#CRC32:NOTHEX!!"),
+            Err(QrError::MissingCrc)
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+//! Simulated wear, for checking that an error correction level survives
+//! printing on rough surfaces
+//!
+//! Only data modules are damaged. Finder, timing and alignment patterns
+//! aren't covered by error correction, so a code with a scuffed finder can't
+//! be rescued by any level and wouldn't say anything about the choice.
+
+use super::{QrCode, QUIET_ZONE_MODULES};
+use image::{DynamicImage, GenericImage, GenericImageView, Rgba};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Invert `percent` percent of the data modules of `qr` in `image`, chosen
+/// at random, and return how many were flipped
+///
+/// `image` must be `qr` as drawn by [`render_to_image`](super::render_to_image),
+/// with its quiet zone; the module size is taken from the image width. Each
+/// flipped module is painted in the color of the opposite module type found
+/// in the image, so custom colors stay two-tone.
+pub fn simulate_damage<R: Rng + ?Sized>(
+    image: &mut DynamicImage,
+    qr: &QrCode,
+    percent: f32,
+    rng: &mut R,
+) -> usize {
+    let width = qr.width();
+    let scale = image.width() / (width as u32 + QUIET_ZONE_MODULES * 2);
+    if scale == 0 {
+        return 0;
+    }
+    let origin = |i: usize| (QUIET_ZONE_MODULES + i as u32) * scale;
+
+    let colors = qr.to_colors();
+    let mut data: Vec<(usize, usize)> = (0..width * width)
+        .map(|i| (i % width, i / width))
+        .filter(|&(x, y)| !qr.is_functional(x, y))
+        .collect();
+    let count =
+        ((data.len() as f32 * percent.clamp(0.0, 100.0) / 100.0).round() as usize).min(data.len());
+    let (chosen, _) = data.partial_shuffle(rng, count);
+
+    // Sample the colors before painting, so flipped modules aren't mistaken for originals
+    let sample = |dark: bool| -> Option<Rgba<u8>> {
+        colors
+            .iter()
+            .position(|&color| (color == qrcode::Color::Dark) == dark)
+            .map(|i| image.get_pixel(origin(i % width), origin(i / width)))
+    };
+    let (dark, light) = (sample(true), sample(false));
+
+    for &(x, y) in chosen.iter() {
+        let was_dark = colors[y * width + x] == qrcode::Color::Dark;
+        let Some(paint) = (if was_dark { light } else { dark }) else {
+            continue;
+        };
+        for py in origin(y)..origin(y) + scale {
+            for px in origin(x)..origin(x) + scale {
+                image.put_pixel(px, py, paint);
+            }
+        }
+    }
+    count
+}
+
+/// Render `qr`, damage `percent` percent of its data modules and report
+/// whether it still decodes
+#[cfg(feature = "decode")]
+pub fn reads_after_damage<R: Rng + ?Sized>(qr: &QrCode, percent: f32, rng: &mut R) -> bool {
+    let mut image = super::render_to_image(qr, &super::ImageConfig::default());
+    simulate_damage(&mut image, qr, percent, rng);
+    super::decode::decode_qr(&image, &super::decode::DecodeConfig::default()).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qrcode::{
+        generate_qr, module_error_ratio, render_to_image, ErrorCorrectionLevel, ImageConfig,
+        QrConfig,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn high_ec_code() -> QrCode {
+        generate_qr(&QrConfig {
+            content: "https://example.com/asset/4711".to_string(),
+            error_correction: ErrorCorrectionLevel::High,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_flips_requested_share_of_data_modules() {
+        let qr = high_ec_code();
+        let config = ImageConfig::default();
+        let mut image = render_to_image(&qr, &config);
+        let flipped = simulate_damage(&mut image, &qr, 10.0, &mut StdRng::seed_from_u64(1));
+
+        let width = qr.width();
+        let data_modules = (0..width * width)
+            .filter(|i| !qr.is_functional(i % width, i / width))
+            .count();
+        assert_eq!(flipped, (data_modules as f32 * 0.1).round() as usize);
+        let errors = module_error_ratio(&image, &qr, (0, 0), config.scale) * (width * width) as f32;
+        assert_eq!(errors.round() as usize, flipped);
+    }
+
+    #[cfg(feature = "decode")]
+    #[test]
+    fn test_high_ec_survives_light_damage_only() {
+        let qr = high_ec_code();
+        for seed in 0..5 {
+            assert!(
+                reads_after_damage(&qr, 2.0, &mut StdRng::seed_from_u64(seed)),
+                "seed {}",
+                seed
+            );
+            // Past what error correction can recover
+            assert!(
+                !reads_after_damage(&qr, 30.0, &mut StdRng::seed_from_u64(seed)),
+                "seed {}",
+                seed
+            );
+        }
+    }
+}
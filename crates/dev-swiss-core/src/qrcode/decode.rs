@@ -0,0 +1,281 @@
+//! Reading QR codes back out of images
+//!
+//! Photographed codes are often skewed, unevenly lit or low in contrast. With
+//! `DecodeConfig::preprocess` the image is binarized with a local threshold
+//! first, and any code whose finder patterns are found but which doesn't read
+//! is warped back to an upright square before trying again. If that finds
+//! nothing, the untouched image is decoded as a fallback.
+
+use super::content::{decompress_content, verify_crc, COMPRESSED_CONTENT_MARKER};
+use super::QrError;
+use image::{DynamicImage, GrayImage, Luma};
+use imageproc::contrast::{adaptive_threshold, stretch_contrast_mut};
+use imageproc::geometric_transformations::{warp_into, Interpolation, Projection};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default)]
+pub struct DecodeConfig {
+    /// Clean up and straighten the image before decoding
+    pub preprocess: bool,
+    /// Expand payloads made by [`compress_content`](super::content::compress_content);
+    /// other codes are returned as they are
+    pub decompress: bool,
+    /// Check and strip the checksum added by
+    /// [`append_crc`](super::content::append_crc), failing when a code has
+    /// none or it doesn't match; applied after decompressing
+    pub verify_crc: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeResult {
+    /// Text of each code found, in detection order
+    pub contents: Vec<String>,
+    /// Whether the codes were read from the preprocessed image rather than
+    /// the raw fallback
+    pub preprocessed: bool,
+}
+
+/// Decode every QR code in `image`
+pub fn decode_qr(image: &DynamicImage, config: &DecodeConfig) -> Result<DecodeResult, QrError> {
+    let mut payloads = Vec::new();
+    let mut preprocessed = false;
+    if config.preprocess {
+        payloads = decode_straightened(&preprocess_for_decode(image));
+        preprocessed = !payloads.is_empty();
+    }
+    if payloads.is_empty() {
+        payloads = decode_gray(&image.to_luma8());
+    }
+    if payloads.is_empty() {
+        return Err(QrError::NoCodeFound);
+    }
+
+    let contents = payloads
+        .into_iter()
+        .map(|payload| {
+            let payload = if config.decompress && payload.starts_with(COMPRESSED_CONTENT_MARKER) {
+                decompress_content(&payload)?
+            } else {
+                payload
+            };
+            let content = if config.verify_crc {
+                verify_crc(&payload)?
+            } else {
+                &payload
+            };
+            Ok(String::from_utf8_lossy(content).into_owned())
+        })
+        .collect::<Result<_, QrError>>()?;
+    Ok(DecodeResult {
+        contents,
+        preprocessed,
+    })
+}
+
+/// Open the image at `path` and decode every QR code in it
+pub fn decode_qr_file(path: &Path, config: &DecodeConfig) -> Result<DecodeResult, QrError> {
+    let image = image::open(path).map_err(|e| {
+        QrError::ImageProcessingFailed(format!("Failed to open {}: {}", path.display(), e))
+    })?;
+    decode_qr(&image, config)
+}
+
+/// Grayscale, stretch the contrast to the full range and binarize against
+/// the local mean brightness, so uneven lighting doesn't wash out modules
+pub fn preprocess_for_decode(image: &DynamicImage) -> GrayImage {
+    let mut gray = image.to_luma8();
+    let (low, high) = gray
+        .pixels()
+        .fold((u8::MAX, u8::MIN), |(low, high), Luma([v])| {
+            (low.min(*v), high.max(*v))
+        });
+    if low < high {
+        stretch_contrast_mut(&mut gray, low, high, 0, 255);
+    }
+
+    // Wide enough to span a few modules of a code that fills much of the frame
+    let radius = (gray.width().min(gray.height()) / 16).max(1);
+    adaptive_threshold(&gray, radius)
+}
+
+/// Every code rqrr locates in `gray` by its finder patterns, with its
+/// payload when it reads and its corners in the image
+fn detect(gray: &GrayImage) -> Vec<(Option<Vec<u8>>, [rqrr::Point; 4])> {
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        gray.width() as usize,
+        gray.height() as usize,
+        |x, y| gray.get_pixel(x as u32, y as u32).0[0],
+    );
+    prepared
+        .detect_grids()
+        .iter()
+        .map(|grid| {
+            let mut payload = Vec::new();
+            (
+                grid.decode_to(&mut payload).ok().map(|_| payload),
+                grid.bounds,
+            )
+        })
+        .collect()
+}
+
+/// Payloads of every code that reads directly from `gray`
+fn decode_gray(gray: &GrayImage) -> Vec<Vec<u8>> {
+    detect(gray)
+        .into_iter()
+        .filter_map(|(content, _)| content)
+        .collect()
+}
+
+/// Like [`decode_gray`], but a located code that fails to read is warped to
+/// an upright square and read again
+fn decode_straightened(gray: &GrayImage) -> Vec<Vec<u8>> {
+    detect(gray)
+        .into_iter()
+        .filter_map(|(content, bounds)| {
+            content.or_else(|| {
+                straighten(gray, &bounds)
+                    .and_then(|upright| decode_gray(&upright).into_iter().next())
+            })
+        })
+        .collect()
+}
+
+/// Map the quadrilateral `bounds` (top-left, top-right, bottom-right,
+/// bottom-left) onto a square with a white margin around it
+fn straighten(gray: &GrayImage, bounds: &[rqrr::Point; 4]) -> Option<GrayImage> {
+    let from = bounds.map(|p| (p.x as f32, p.y as f32));
+    let edge = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).hypot(a.1 - b.1);
+    let side = (0..4)
+        .map(|i| edge(from[i], from[(i + 1) % 4]))
+        .fold(0.0, f32::max)
+        .ceil();
+    if side < 1.0 {
+        return None;
+    }
+
+    // About a quiet zone's worth of margin for the detector
+    let margin = (side / 8.0).ceil();
+    let far = margin + side;
+    let to = [(margin, margin), (far, margin), (far, far), (margin, far)];
+    let projection = Projection::from_control_points(from, to)?;
+
+    let size = (far + margin) as u32;
+    let mut out = GrayImage::new(size, size);
+    warp_into(
+        gray,
+        &projection,
+        Interpolation::Bilinear,
+        Luma([255]),
+        &mut out,
+    );
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qrcode::content::{append_crc, compress_content};
+    use crate::qrcode::{generate_qr, generate_qr_bytes, render_to_image, ImageConfig, QrConfig};
+    use imageproc::geometric_transformations::rotate_about_center;
+
+    fn code_image(content: &str) -> DynamicImage {
+        let code = generate_qr(&QrConfig {
+            content: content.to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        render_to_image(&code, &ImageConfig::default())
+    }
+
+    #[test]
+    fn test_decode_rendered_code() {
+        let result =
+            decode_qr(&code_image("https://example.com"), &DecodeConfig::default()).unwrap();
+        assert_eq!(result.contents, ["https://example.com"]);
+        assert!(!result.preprocessed);
+    }
+
+    #[test]
+    fn test_preprocess_reads_rotated_low_contrast_code() {
+        // A slightly rotated photo of a code printed in gray on gray
+        let gray = code_image("rotated").to_luma8();
+        let faded = GrayImage::from_fn(gray.width(), gray.height(), |x, y| {
+            Luma([if gray.get_pixel(x, y).0[0] < 128 {
+                110
+            } else {
+                150
+            }])
+        });
+        let rotated = rotate_about_center(
+            &faded,
+            7f32.to_radians(),
+            Interpolation::Bilinear,
+            Luma([150]),
+        );
+
+        let config = DecodeConfig {
+            preprocess: true,
+            ..Default::default()
+        };
+        let result = decode_qr(&DynamicImage::ImageLuma8(rotated), &config).unwrap();
+        assert_eq!(result.contents, ["rotated"]);
+        assert!(result.preprocessed);
+    }
+
+    #[test]
+    fn test_no_code_found() {
+        let blank = DynamicImage::ImageLuma8(GrayImage::from_pixel(64, 64, Luma([255])));
+        let config = DecodeConfig {
+            preprocess: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            decode_qr(&blank, &config),
+            Err(QrError::NoCodeFound)
+        ));
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let json = r#"{"user":"alice","role":"admin","scopes":["read","write"]}"#.repeat(20);
+        let payload = compress_content(json.as_bytes());
+        let code = generate_qr_bytes(&payload, &QrConfig::default()).unwrap();
+        let image = render_to_image(&code, &ImageConfig::default());
+
+        let config = DecodeConfig {
+            decompress: true,
+            ..Default::default()
+        };
+        assert_eq!(decode_qr(&image, &config).unwrap().contents, [json]);
+
+        // Without decompressing, the payload is the raw compressed bytes
+        let raw = decode_qr(&image, &DecodeConfig::default()).unwrap();
+        assert!(raw.contents[0].starts_with("DSZ1"));
+    }
+
+    #[test]
+    fn test_verify_crc() {
+        let config = DecodeConfig {
+            verify_crc: true,
+            ..Default::default()
+        };
+        let payload = append_crc(b"batch 42");
+        let code = generate_qr_bytes(&payload, &QrConfig::default()).unwrap();
+        let image = render_to_image(&code, &ImageConfig::default());
+        assert_eq!(decode_qr(&image, &config).unwrap().contents, ["batch 42"]);
+
+        let mut corrupted = payload.clone();
+        corrupted[0] = b'c';
+        let code = generate_qr_bytes(&corrupted, &QrConfig::default()).unwrap();
+        let image = render_to_image(&code, &ImageConfig::default());
+        assert!(matches!(
+            decode_qr(&image, &config),
+            Err(QrError::CrcMismatch { .. })
+        ));
+        assert!(matches!(
+            decode_qr(&code_image("plain"), &config),
+            Err(QrError::MissingCrc)
+        ));
+    }
+}
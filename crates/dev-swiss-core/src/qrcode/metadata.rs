@@ -0,0 +1,224 @@
+//! Text metadata in PNG files: what a code holds and when it was made
+//!
+//! Entries are written as uncompressed iTXt chunks right after the header,
+//! so they survive any encoder (including the 1-bit palette path) and hold
+//! UTF-8. Embedding is opt-in: a PNG shared on from a private code would
+//! otherwise carry the content in plain text next to the pixels.
+
+use super::QrError;
+use sha2::{Digest, Sha256};
+use std::io::BufReader;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Keyword holding the encoded content
+pub const METADATA_CONTENT_KEY: &str = "QR-Content";
+
+/// Keyword holding the hex SHA-256 of the encoded content, in place of
+/// [`METADATA_CONTENT_KEY`]
+pub const METADATA_CONTENT_HASH_KEY: &str = "QR-Content-SHA256";
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Signature plus the IHDR chunk, which is always 13 bytes of data
+const HEADER_LEN: usize = 8 + 4 + 4 + 13 + 4;
+
+/// The entries [`embed_png_metadata`] is usually given for a code: the
+/// software, the creation time and either the content or its hash
+///
+/// Content that isn't UTF-8 (raw bytes, compressed payloads) is always
+/// stored as a hash, since PNG text can't hold it.
+pub fn qr_metadata(content: &[u8], hash_only: bool, created: SystemTime) -> Vec<(String, String)> {
+    let secs = created
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut entries = vec![
+        ("Software".to_string(), "dev-swiss".to_string()),
+        ("Creation Time".to_string(), crate::utc::utc_timestamp(secs)),
+    ];
+    match std::str::from_utf8(content) {
+        Ok(text) if !hash_only => {
+            entries.push((METADATA_CONTENT_KEY.to_string(), text.to_string()))
+        }
+        _ => entries.push((
+            METADATA_CONTENT_HASH_KEY.to_string(),
+            crate::hex::to_hex(&Sha256::digest(content)),
+        )),
+    }
+    entries
+}
+
+/// Insert `metadata` into the encoded PNG `png` as iTXt chunks
+///
+/// Keywords must be 1-79 printable ASCII characters without leading,
+/// trailing or doubled spaces, as the PNG specification requires.
+pub fn embed_png_metadata(png: &[u8], metadata: &[(String, String)]) -> Result<Vec<u8>, QrError> {
+    if png.len() < HEADER_LEN || png[..8] != PNG_SIGNATURE || &png[12..16] != b"IHDR" {
+        return Err(QrError::ImageProcessingFailed("Not a PNG file".to_string()));
+    }
+
+    let mut out = Vec::with_capacity(
+        png.len()
+            + metadata
+                .iter()
+                .map(|(k, v)| k.len() + v.len() + 17)
+                .sum::<usize>(),
+    );
+    out.extend_from_slice(&png[..HEADER_LEN]);
+    for (keyword, text) in metadata {
+        check_keyword(keyword)?;
+        // Keyword, then no compression, no language tag and no translated keyword
+        let mut data = Vec::with_capacity(keyword.len() + text.len() + 5);
+        data.extend_from_slice(keyword.as_bytes());
+        data.extend_from_slice(&[0, 0, 0, 0, 0]);
+        data.extend_from_slice(text.as_bytes());
+        write_chunk(&mut out, b"iTXt", &data);
+    }
+    out.extend_from_slice(&png[HEADER_LEN..]);
+    Ok(out)
+}
+
+/// Every text entry in the PNG at `path`, from tEXt, zTXt and iTXt chunks,
+/// as (keyword, text) pairs
+pub fn read_png_metadata(path: &Path) -> Result<Vec<(String, String)>, QrError> {
+    let failed = |e: &dyn std::fmt::Display| {
+        QrError::ImageProcessingFailed(format!(
+            "Failed to read PNG metadata from {}: {}",
+            path.display(),
+            e
+        ))
+    };
+    let file = std::fs::File::open(path)
+        .map_err(|e| QrError::IoError(format!("Failed to open {}: {}", path.display(), e)))?;
+    let mut reader = png::Decoder::new(BufReader::new(file))
+        .read_info()
+        .map_err(|e| failed(&e))?;
+    // Text chunks may also follow the image data
+    reader.finish().map_err(|e| failed(&e))?;
+
+    let info = reader.info();
+    let mut entries: Vec<(String, String)> = info
+        .uncompressed_latin1_text
+        .iter()
+        .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+        .collect();
+    for chunk in &info.compressed_latin1_text {
+        entries.push((
+            chunk.keyword.clone(),
+            chunk.get_text().map_err(|e| failed(&e))?,
+        ));
+    }
+    for chunk in &info.utf8_text {
+        entries.push((
+            chunk.keyword.clone(),
+            chunk.get_text().map_err(|e| failed(&e))?,
+        ));
+    }
+    Ok(entries)
+}
+
+fn check_keyword(keyword: &str) -> Result<(), QrError> {
+    let valid = (1..=79).contains(&keyword.len())
+        && keyword.bytes().all(|b| b == b' ' || b.is_ascii_graphic())
+        && !keyword.starts_with(' ')
+        && !keyword.ends_with(' ')
+        && !keyword.contains("  ");
+    if valid {
+        Ok(())
+    } else {
+        Err(QrError::ImageProcessingFailed(format!(
+            "Invalid PNG text keyword {:?}",
+            keyword
+        )))
+    }
+}
+
+/// Append a chunk: big-endian data length, type, data and the CRC of type
+/// and data
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let mut crc = flate2::Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qrcode::{
+        encode_png, encode_png_optimized, generate_qr, render_to_image, ImageConfig, QrConfig,
+    };
+    use std::time::Duration;
+
+    fn code_png(optimized: bool) -> Vec<u8> {
+        let qr = generate_qr(&QrConfig {
+            content: "https://example.com".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let image = render_to_image(&qr, &ImageConfig::default());
+        if optimized {
+            encode_png_optimized(&image).unwrap()
+        } else {
+            encode_png(&image).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let created = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let metadata = qr_metadata("Grüße, world".as_bytes(), false, created);
+
+        for optimized in [false, true] {
+            let path = dir.path().join(format!("qr-{}.png", optimized));
+            std::fs::write(
+                &path,
+                embed_png_metadata(&code_png(optimized), &metadata).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(read_png_metadata(&path).unwrap(), metadata);
+            // Still a readable image
+            assert!(image::open(&path).is_ok());
+        }
+        assert_eq!(
+            metadata[1],
+            (
+                "Creation Time".to_string(),
+                "2023-11-14T22:13:20Z".to_string()
+            )
+        );
+        assert_eq!(
+            metadata[2],
+            (METADATA_CONTENT_KEY.to_string(), "Grüße, world".to_string())
+        );
+    }
+
+    #[test]
+    fn test_hash_only_and_binary_content() {
+        let hashed = qr_metadata(b"abc", true, UNIX_EPOCH);
+        assert_eq!(
+            hashed[2],
+            (
+                METADATA_CONTENT_HASH_KEY.to_string(),
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string()
+            )
+        );
+        let binary = qr_metadata(&[0xff, 0xfe], false, UNIX_EPOCH);
+        assert_eq!(binary[2].0, METADATA_CONTENT_HASH_KEY);
+    }
+
+    #[test]
+    fn test_rejects_bad_keywords_and_non_png() {
+        let png = code_png(false);
+        for keyword in ["", " lead", "two  spaces", "tab\there", &"k".repeat(80)] {
+            let entry = [(keyword.to_string(), "x".to_string())];
+            assert!(embed_png_metadata(&png, &entry).is_err(), "{:?}", keyword);
+        }
+        assert!(embed_png_metadata(b"GIF89a", &[]).is_err());
+    }
+}
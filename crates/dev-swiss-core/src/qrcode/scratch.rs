@@ -0,0 +1,308 @@
+//! Temporary storage for images fetched from URLs.
+//!
+//! Downloads are written to a private temporary directory rather than the
+//! working directory, so an interrupted or failed fetch leaves nothing behind.
+//! The directory and everything in it is removed when the `ScratchDir` drops.
+
+use super::QrError;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Largest image body `ScratchDir::download` accepts (20 MiB)
+pub const MAX_DOWNLOAD_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Whether `source` names a remote image rather than a local path
+pub fn is_remote_source(source: &str) -> bool {
+    ["http://", "https://"].iter().any(|scheme| {
+        source
+            .get(..scheme.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// A temporary directory that lives as long as the value does
+#[derive(Debug)]
+pub struct ScratchDir {
+    dir: TempDir,
+    downloads: AtomicUsize,
+}
+
+impl ScratchDir {
+    pub fn new() -> Result<Self, QrError> {
+        let dir = tempfile::Builder::new()
+            .prefix("dev-swiss-")
+            .tempdir()
+            .map_err(|e| {
+                QrError::IoError(format!("Failed to create temporary directory: {}", e))
+            })?;
+        Ok(Self {
+            dir,
+            downloads: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Write `bytes` to `name` inside the scratch directory
+    ///
+    /// The data goes to a `.part` file first and is renamed once complete, so
+    /// a reader never sees a half-written file.
+    pub fn store(&self, name: &str, bytes: &[u8]) -> Result<PathBuf, QrError> {
+        let path = self.dir.path().join(name);
+        let partial = self.dir.path().join(format!("{}.part", name));
+        let write = || -> std::io::Result<()> {
+            let mut file = std::fs::File::create(&partial)?;
+            file.write_all(bytes)?;
+            file.sync_all()?;
+            std::fs::rename(&partial, &path)
+        };
+        write().map_err(|e| {
+            let _ = std::fs::remove_file(&partial);
+            QrError::IoError(format!("Failed to write {}: {}", path.display(), e))
+        })?;
+        Ok(path)
+    }
+
+    /// Fetch `url` into the scratch directory and return the local path
+    ///
+    /// Each download gets its own numbered file, so two URLs ending in the
+    /// same name never overwrite each other. The extension follows the
+    /// response's `Content-Type` when it names a known image format, since
+    /// images are opened by extension. Bodies over `MAX_DOWNLOAD_BYTES` are
+    /// refused.
+    pub fn download(&self, url: &str, timeout: Duration) -> Result<PathBuf, QrError> {
+        self.download_limited(url, timeout, MAX_DOWNLOAD_BYTES)
+    }
+
+    fn download_limited(
+        &self,
+        url: &str,
+        timeout: Duration,
+        max_bytes: u64,
+    ) -> Result<PathBuf, QrError> {
+        let failed =
+            |reason: String| QrError::IoError(format!("Failed to download {}: {}", url, reason));
+        let too_large = || failed(format!("larger than {} bytes", max_bytes));
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| failed(e.to_string()))?;
+        let response = client.get(url).send().map_err(|e| failed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(failed(format!("HTTP {}", response.status().as_u16())));
+        }
+        if response.content_length().is_some_and(|len| len > max_bytes) {
+            return Err(too_large());
+        }
+        let extension = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(extension_for_content_type);
+
+        // Read one byte past the limit so a body without Content-Length is caught too
+        let mut bytes = Vec::new();
+        response
+            .take(max_bytes + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|e| failed(e.to_string()))?;
+        if bytes.len() as u64 > max_bytes {
+            return Err(too_large());
+        }
+
+        let index = self.downloads.fetch_add(1, Ordering::Relaxed) + 1;
+        let name = file_name_for(url, extension);
+        self.store(&format!("{}-{}", index, name), &bytes)
+    }
+}
+
+/// Image file extension for a `Content-Type` header value, if it is one `image` can open
+fn extension_for_content_type(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next()?.trim();
+    match mime.to_ascii_lowercase().as_str() {
+        "image/png" => Some("png"),
+        "image/jpeg" | "image/jpg" | "image/pjpeg" => Some("jpg"),
+        _ => None,
+    }
+}
+
+/// Last path segment of `url`, or `download` when it has none
+///
+/// With `extension` set, it replaces any extension the segment already has.
+fn file_name_for(url: &str, extension: Option<&str>) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let name = path
+        .split_once("://")
+        .and_then(|(_, rest)| rest.split_once('/'))
+        .and_then(|(_, path)| path.rsplit('/').next())
+        .unwrap_or_default();
+    let name = if name.is_empty() || name == "." || name == ".." {
+        "download"
+    } else {
+        name
+    };
+    match extension {
+        Some(extension) => Path::new(name)
+            .with_extension(extension)
+            .to_string_lossy()
+            .to_string(),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Answer a single request with `status`, `content_type` and `body`
+    fn serve_once(
+        status: &'static str,
+        content_type: &'static str,
+        body: Vec<u8>,
+    ) -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            let header = format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                content_type,
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).unwrap();
+            // The client may hang up early on an oversized body
+            let _ = stream.write_all(&body);
+        });
+        (format!("http://{}", addr), handle)
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_is_remote_source() {
+        assert!(is_remote_source("https://example.com/logo.png"));
+        assert!(is_remote_source("HTTP://example.com/logo.png"));
+        assert!(!is_remote_source("logo.png"));
+        assert!(!is_remote_source("ftp://example.com/logo.png"));
+        assert!(!is_remote_source("http"));
+    }
+
+    #[test]
+    fn test_file_name_for() {
+        assert_eq!(
+            file_name_for("https://x.com/img/logo.png?v=2", None),
+            "logo.png"
+        );
+        assert_eq!(file_name_for("https://x.com/", None), "download");
+        assert_eq!(file_name_for("https://x.com", None), "download");
+        assert_eq!(file_name_for("https://x.com/a/..", None), "download");
+        assert_eq!(file_name_for("https://x.com/", Some("png")), "download.png");
+        assert_eq!(
+            file_name_for("https://x.com/logo.png", Some("jpg")),
+            "logo.jpg"
+        );
+    }
+
+    #[test]
+    fn test_extension_for_content_type() {
+        assert_eq!(extension_for_content_type("image/png"), Some("png"));
+        assert_eq!(extension_for_content_type("IMAGE/JPEG; q=1"), Some("jpg"));
+        assert_eq!(extension_for_content_type("text/html"), None);
+    }
+
+    #[test]
+    fn test_download_leaves_no_stray_files() {
+        let (url, server) = serve_once("200 OK", "image/png", b"not really a png".to_vec());
+        let scratch = ScratchDir::new().unwrap();
+        let root = scratch.path().to_path_buf();
+
+        let path = scratch
+            .download(&format!("{}/logo.png", url), Duration::from_secs(5))
+            .unwrap();
+        server.join().unwrap();
+
+        assert_eq!(path, root.join("1-logo.png"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"not really a png");
+        // Only the finished file, no partial download
+        assert_eq!(entries(&root), ["1-logo.png"]);
+
+        drop(scratch);
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn test_failed_download_leaves_no_files() {
+        let (url, server) = serve_once("404 Not Found", "text/plain", Vec::new());
+        let scratch = ScratchDir::new().unwrap();
+
+        let result = scratch.download(&format!("{}/missing.png", url), Duration::from_secs(5));
+        server.join().unwrap();
+
+        assert!(matches!(result, Err(QrError::IoError(msg)) if msg.contains("HTTP 404")));
+        assert!(entries(scratch.path()).is_empty());
+    }
+
+    #[test]
+    fn test_same_named_urls_get_separate_files() {
+        let (logo_url, logo_server) = serve_once("200 OK", "image/png", b"logo".to_vec());
+        let (bg_url, bg_server) = serve_once("200 OK", "image/png", b"background".to_vec());
+        let scratch = ScratchDir::new().unwrap();
+        let timeout = Duration::from_secs(5);
+
+        let logo = scratch
+            .download(&format!("{}/x/image.png", logo_url), timeout)
+            .unwrap();
+        let background = scratch
+            .download(&format!("{}/y/image.png", bg_url), timeout)
+            .unwrap();
+        logo_server.join().unwrap();
+        bg_server.join().unwrap();
+
+        assert_ne!(logo, background);
+        assert_eq!(std::fs::read(&logo).unwrap(), b"logo");
+        assert_eq!(std::fs::read(&background).unwrap(), b"background");
+    }
+
+    #[test]
+    fn test_extensionless_url_takes_extension_from_content_type() {
+        let (url, server) = serve_once("200 OK", "image/jpeg", b"jpeg bytes".to_vec());
+        let scratch = ScratchDir::new().unwrap();
+
+        let path = scratch.download(&url, Duration::from_secs(5)).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(path.file_name().unwrap(), "1-download.jpg");
+    }
+
+    #[test]
+    fn test_oversized_download_is_refused() {
+        let (url, server) = serve_once("200 OK", "image/png", vec![0; 64]);
+        let scratch = ScratchDir::new().unwrap();
+
+        let result = scratch.download_limited(&url, Duration::from_secs(5), 16);
+        server.join().unwrap();
+
+        assert!(
+            matches!(result, Err(QrError::IoError(msg)) if msg.contains("larger than 16 bytes"))
+        );
+        assert!(entries(scratch.path()).is_empty());
+    }
+}
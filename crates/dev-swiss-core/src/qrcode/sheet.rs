@@ -0,0 +1,207 @@
+//! Grids of QR codes on a single printable image, e.g. for label sheets.
+
+use super::text::{caption_band_height, draw_caption};
+use super::{generate_qr, render_to_image, ErrorCorrectionLevel, ImageConfig, QrConfig, QrError};
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+
+/// One cell of a sheet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetEntry {
+    pub content: String,
+    pub caption: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SheetConfig {
+    pub columns: u32,
+    /// Outer border and gap between cells, in pixels
+    pub margin: u32,
+    /// Caption font size in pixels
+    pub caption_size: f32,
+    pub error_correction: ErrorCorrectionLevel,
+    pub image: ImageConfig,
+}
+
+impl Default for SheetConfig {
+    fn default() -> Self {
+        Self {
+            columns: 4,
+            margin: 16,
+            caption_size: 16.0,
+            error_correction: ErrorCorrectionLevel::Medium,
+            image: ImageConfig::default(),
+        }
+    }
+}
+
+/// Parse one entry per non-empty line
+///
+/// A tab separates the content from an explicit caption
+/// (`https://x.test/1<TAB>Table 1`). With `caption_content`, lines without one
+/// are captioned with their content.
+pub fn parse_sheet_entries(text: &str, caption_content: bool) -> Vec<SheetEntry> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_once('\t') {
+            Some((content, caption)) => SheetEntry {
+                content: content.to_string(),
+                caption: Some(caption.trim().to_string()).filter(|c| !c.is_empty()),
+            },
+            None => SheetEntry {
+                content: line.to_string(),
+                caption: caption_content.then(|| line.trim().to_string()),
+            },
+        })
+        .collect()
+}
+
+/// Render `entries` into a grid `config.columns` wide
+///
+/// Every cell is as large as the biggest code, so codes of different versions
+/// line up; smaller codes are centered in their cell. A caption band is added
+/// under every row when any entry has a caption.
+pub fn render_sheet(entries: &[SheetEntry], config: &SheetConfig) -> Result<DynamicImage, QrError> {
+    if config.columns == 0 {
+        return Err(QrError::InvalidSheet(
+            "at least one column is required".to_string(),
+        ));
+    }
+    if entries.is_empty() {
+        return Err(QrError::EmptyContent);
+    }
+
+    let codes = entries
+        .iter()
+        .map(|entry| {
+            let qr_config = QrConfig {
+                content: entry.content.clone(),
+                error_correction: config.error_correction,
+                ..Default::default()
+            };
+            generate_qr(&qr_config).map(|qr| render_to_image(&qr, &config.image))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let cell = codes.iter().map(|code| code.width()).max().unwrap_or(0);
+    let band = if entries.iter().any(|e| e.caption.is_some()) {
+        caption_band_height(config.caption_size)
+    } else {
+        0
+    };
+    let columns = config.columns.min(entries.len() as u32);
+    let rows = (entries.len() as u32).div_ceil(columns);
+    let margin = config.margin;
+
+    let width = margin * 2 + columns * cell + (columns - 1) * margin;
+    let height = margin * 2 + rows * (cell + band) + (rows - 1) * margin;
+    let mut sheet = RgbImage::from_pixel(width, height, Rgb(config.image.light_color));
+
+    for (i, (entry, code)) in entries.iter().zip(&codes).enumerate() {
+        let (col, row) = (i as u32 % columns, i as u32 / columns);
+        let cell_x = margin + col * (cell + margin);
+        let cell_y = margin + row * (cell + band + margin);
+        let (code_w, code_h) = code.dimensions();
+        let code_y = cell_y + (cell - code_h) / 2;
+        image::imageops::overlay(
+            &mut sheet,
+            &code.to_rgb8(),
+            (cell_x + (cell - code_w) / 2) as i64,
+            code_y as i64,
+        );
+        if let Some(caption) = &entry.caption {
+            // Directly under the code, so small codes don't float away from their caption
+            draw_caption(
+                &mut sheet,
+                caption,
+                (cell_x, code_y + code_h),
+                cell,
+                config.caption_size,
+                config.image.dark_color,
+            );
+        }
+    }
+
+    Ok(DynamicImage::ImageRgb8(sheet))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(n: usize) -> Vec<SheetEntry> {
+        (1..=n)
+            .map(|i| SheetEntry {
+                content: format!("item-{}", i),
+                caption: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_sheet_entries() {
+        let parsed = parse_sheet_entries("a\n\nb\tLabel B\nc\t\n", true);
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].caption.as_deref(), Some("a"));
+        assert_eq!(parsed[1].content, "b");
+        assert_eq!(parsed[1].caption.as_deref(), Some("Label B"));
+        assert_eq!(parsed[2].caption, None);
+
+        let plain = parse_sheet_entries("a\nb", false);
+        assert!(plain.iter().all(|e| e.caption.is_none()));
+    }
+
+    #[test]
+    fn test_five_entries_two_columns_is_three_rows() {
+        let config = SheetConfig {
+            columns: 2,
+            margin: 10,
+            image: ImageConfig {
+                scale: 4,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let sheet = render_sheet(&entries(5), &config).unwrap();
+
+        // Version 1 codes: (21 + 2 * 4 quiet zone) modules at 4px
+        let cell = 29 * 4;
+        assert_eq!(sheet.width(), 10 * 2 + 2 * cell + 10);
+        assert_eq!(sheet.height(), 10 * 2 + 3 * cell + 2 * 10);
+    }
+
+    #[test]
+    fn test_captions_add_a_band_per_row() {
+        let config = SheetConfig {
+            columns: 2,
+            ..Default::default()
+        };
+        let plain = render_sheet(&entries(5), &config).unwrap();
+
+        let mut captioned = entries(5);
+        captioned[0].caption =
+            Some("A very long caption that will not fit in one cell".to_string());
+        let sheet = render_sheet(&captioned, &config).unwrap();
+
+        assert_eq!(sheet.width(), plain.width());
+        assert_eq!(
+            sheet.height(),
+            plain.height() + 3 * caption_band_height(config.caption_size)
+        );
+    }
+
+    #[test]
+    fn test_render_sheet_rejects_bad_input() {
+        let config = SheetConfig {
+            columns: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            render_sheet(&entries(2), &config),
+            Err(QrError::InvalidSheet(_))
+        ));
+        assert!(matches!(
+            render_sheet(&[], &SheetConfig::default()),
+            Err(QrError::EmptyContent)
+        ));
+    }
+}
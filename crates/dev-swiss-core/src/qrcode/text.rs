@@ -0,0 +1,58 @@
+//! Caption rendering with the bundled DejaVu Sans font
+
+use ab_glyph::{FontRef, PxScale};
+use image::{Rgb, RgbImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+
+const FONT_DATA: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+
+/// Horizontal space kept clear on each side of a caption, in pixels
+const CAPTION_PADDING: u32 = 4;
+
+fn font() -> FontRef<'static> {
+    FontRef::try_from_slice(FONT_DATA).expect("bundled font is a valid TrueType file")
+}
+
+/// Height of the band reserved below a code for a caption of `size` pixels
+pub(crate) fn caption_band_height(size: f32) -> u32 {
+    (size * 1.6).ceil() as u32
+}
+
+/// Draw `text` centered in the band `width` pixels wide whose top-left corner
+/// is at (`x`, `y`), shortening it with an ellipsis if it doesn't fit
+pub(crate) fn draw_caption(
+    image: &mut RgbImage,
+    text: &str,
+    (x, y): (u32, u32),
+    width: u32,
+    size: f32,
+    color: [u8; 3],
+) {
+    let font = font();
+    let scale = PxScale::from(size);
+    let available = width.saturating_sub(CAPTION_PADDING * 2);
+
+    let mut caption = text.to_string();
+    let mut chars: Vec<char> = text.chars().collect();
+    while !chars.is_empty() && text_size(scale, &font, &caption).0 > available {
+        chars.pop();
+        caption = chars.iter().collect::<String>().trim_end().to_string() + "…";
+    }
+    if chars.is_empty() {
+        return;
+    }
+
+    let text_width = text_size(scale, &font, &caption).0;
+    let band = caption_band_height(size);
+    let left = x + (width.saturating_sub(text_width)) / 2;
+    let top = y + (band.saturating_sub(size.ceil() as u32)) / 2;
+    draw_text_mut(
+        image,
+        Rgb(color),
+        left as i32,
+        top as i32,
+        scale,
+        &font,
+        &caption,
+    );
+}
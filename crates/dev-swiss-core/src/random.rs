@@ -0,0 +1,59 @@
+use crate::{encode_base64, to_hex, Base64Config};
+use rand::{CryptoRng, RngCore};
+
+/// Text encodings for random bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RandomEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// `len` bytes from a caller-supplied cryptographically secure RNG
+pub fn random_bytes_with_rng<R: RngCore + CryptoRng>(len: usize, rng: &mut R) -> Vec<u8> {
+    let mut buf = vec![0u8; len];
+    rng.fill_bytes(&mut buf);
+    buf
+}
+
+/// `len` bytes from the thread-local CSPRNG (the same source used for passwords)
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    random_bytes_with_rng(len, &mut rand::thread_rng())
+}
+
+/// `len` random bytes encoded as text
+pub fn random_string(len: usize, encoding: RandomEncoding) -> String {
+    let bytes = random_bytes(len);
+    match encoding {
+        RandomEncoding::Hex => to_hex(&bytes),
+        RandomEncoding::Base64 => encode_base64(&bytes, &Base64Config::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_base64, from_hex};
+
+    #[test]
+    fn test_random_bytes_length() {
+        for len in [0, 1, 32, 1000] {
+            assert_eq!(random_bytes(len).len(), len);
+        }
+        assert_ne!(random_bytes(32), random_bytes(32));
+    }
+
+    #[test]
+    fn test_hex_output_decodes_to_requested_length() {
+        let hex = random_string(32, RandomEncoding::Hex);
+        assert_eq!(hex.len(), 64);
+        assert_eq!(from_hex(&hex).unwrap().len(), 32);
+    }
+
+    #[test]
+    fn test_base64_output_decodes_to_requested_length() {
+        let b64 = random_string(17, RandomEncoding::Base64);
+        let decoded = decode_base64(&b64, &Base64Config::default()).unwrap();
+        assert_eq!(decoded.len(), 17);
+    }
+}
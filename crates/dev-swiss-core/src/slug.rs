@@ -0,0 +1,112 @@
+use deunicode::deunicode;
+
+#[derive(Debug, Clone)]
+pub struct SlugOptions {
+    /// Maximum length in characters; `None` for no limit
+    pub max_len: Option<usize>,
+    /// Inserted between words
+    pub separator: String,
+    /// Transliterate non-ASCII text (`café` becomes `cafe`, `日本` becomes
+    /// `ri-ben`) instead of dropping it
+    pub transliterate: bool,
+}
+
+impl Default for SlugOptions {
+    fn default() -> Self {
+        Self {
+            max_len: None,
+            separator: "-".to_string(),
+            transliterate: true,
+        }
+    }
+}
+
+/// Lowercase ASCII slug of `input`, e.g. `Hello, World!` becomes `hello-world`
+///
+/// Runs of anything other than ASCII letters and digits collapse to a single
+/// separator, with none at either end. The result may be empty.
+pub fn slugify(input: &str, options: &SlugOptions) -> String {
+    let text = if options.transliterate {
+        deunicode(input)
+    } else {
+        input.to_string()
+    };
+
+    let mut slug = String::new();
+    let mut pending_separator = false;
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            if pending_separator && !slug.is_empty() {
+                slug.push_str(&options.separator);
+            }
+            pending_separator = false;
+            slug.push(c.to_ascii_lowercase());
+        } else {
+            pending_separator = true;
+        }
+    }
+
+    if let Some(max_len) = options.max_len {
+        // The slug is pure ASCII, so byte and character lengths agree
+        slug.truncate(max_len);
+        let kept = slug
+            .trim_end_matches(|c: char| !c.is_ascii_alphanumeric())
+            .len();
+        slug.truncate(kept);
+    }
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_punctuation_stripping() {
+        let options = SlugOptions::default();
+        assert_eq!(slugify("Hello, World!", &options), "hello-world");
+        assert_eq!(
+            slugify("  --Rust & Cargo: 2024 edition--  ", &options),
+            "rust-cargo-2024-edition"
+        );
+        assert_eq!(slugify("!!!", &options), "");
+
+        let underscored = SlugOptions {
+            separator: "_".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(slugify("Hello, World!", &underscored), "hello_world");
+    }
+
+    #[test]
+    fn test_accents() {
+        let options = SlugOptions::default();
+        assert_eq!(
+            slugify("Crème Brûlée à Paris", &options),
+            "creme-brulee-a-paris"
+        );
+        assert_eq!(slugify("Straße", &options), "strasse");
+
+        let plain = SlugOptions {
+            transliterate: false,
+            ..Default::default()
+        };
+        assert_eq!(slugify("Crème Brûlée", &plain), "cr-me-br-l-e");
+    }
+
+    #[test]
+    fn test_length_capping() {
+        let options = SlugOptions {
+            max_len: Some(11),
+            ..Default::default()
+        };
+        assert_eq!(slugify("Hello World Again", &options), "hello-world");
+        // A cut landing inside a separator doesn't leave part of it dangling
+        let options = SlugOptions {
+            max_len: Some(6),
+            separator: "--".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(slugify("Hello World", &options), "hello");
+    }
+}
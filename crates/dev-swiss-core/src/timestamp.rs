@@ -0,0 +1,310 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+
+/// Epoch values at or above this magnitude are treated as milliseconds.
+/// 1e11 seconds is the year 5138, while 1e11 milliseconds is March 1973, so
+/// real-world timestamps on either side are unambiguous.
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Unit of a Unix epoch value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EpochUnit {
+    #[default]
+    Seconds,
+    Milliseconds,
+}
+
+/// Time zone used to display (or interpret zone-less) date-times
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum TimeZoneSpec {
+    #[default]
+    Utc,
+    Local,
+    Offset(FixedOffset),
+    Named(Tz),
+}
+
+/// Which way to convert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeDirection {
+    /// Integers are epochs, everything else is a date-time
+    #[default]
+    Auto,
+    FromEpoch,
+    ToEpoch,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TimeConfig {
+    pub direction: TimeDirection,
+    /// Input unit for epoch values (auto-detected by magnitude when `None`),
+    /// or the output unit when converting to an epoch (seconds when `None`)
+    pub unit: Option<EpochUnit>,
+    pub zone: TimeZoneSpec,
+}
+
+#[derive(Debug)]
+pub enum TimeError {
+    InvalidEpoch(String),
+    OutOfRange(i64),
+    InvalidDateTime(String),
+    UnknownTimeZone(String),
+    InvalidLocalTime(String),
+}
+
+impl fmt::Display for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeError::InvalidEpoch(s) => write!(f, "Invalid epoch value: {}", s),
+            TimeError::OutOfRange(v) => write!(f, "Epoch value out of range: {}", v),
+            TimeError::InvalidDateTime(s) => write!(
+                f,
+                "Invalid date-time '{}' (expected RFC 3339, e.g. 2024-01-31T12:00:00Z)",
+                s
+            ),
+            TimeError::UnknownTimeZone(s) => write!(
+                f,
+                "Unknown time zone '{}' (use UTC, local, an offset like +09:00, or an IANA name)",
+                s
+            ),
+            TimeError::InvalidLocalTime(s) => {
+                write!(
+                    f,
+                    "'{}' does not exist or is ambiguous in the selected time zone",
+                    s
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TimeError {}
+
+/// Guess whether an epoch value is in seconds or milliseconds
+pub fn detect_epoch_unit(value: i64) -> EpochUnit {
+    if value.unsigned_abs() >= MILLIS_THRESHOLD as u64 {
+        EpochUnit::Milliseconds
+    } else {
+        EpochUnit::Seconds
+    }
+}
+
+/// Parse `UTC`, `local`, a fixed offset (`+09:00`, `-0500`) or an IANA zone name
+pub fn parse_time_zone(s: &str) -> Result<TimeZoneSpec, TimeError> {
+    let trimmed = s.trim();
+    if trimmed.eq_ignore_ascii_case("utc") || trimmed.eq_ignore_ascii_case("z") {
+        return Ok(TimeZoneSpec::Utc);
+    }
+    if trimmed.eq_ignore_ascii_case("local") {
+        return Ok(TimeZoneSpec::Local);
+    }
+    if trimmed.starts_with(['+', '-']) {
+        let probe = format!("2000-01-01T00:00:00{}", trimmed);
+        return DateTime::parse_from_str(&probe, "%Y-%m-%dT%H:%M:%S%:z")
+            .or_else(|_| DateTime::parse_from_str(&probe, "%Y-%m-%dT%H:%M:%S%z"))
+            .map(|dt| TimeZoneSpec::Offset(*dt.offset()))
+            .map_err(|_| TimeError::UnknownTimeZone(s.to_string()));
+    }
+    trimmed
+        .parse::<Tz>()
+        .map(TimeZoneSpec::Named)
+        .map_err(|_| TimeError::UnknownTimeZone(s.to_string()))
+}
+
+fn format_in<Z: TimeZone>(utc: DateTime<Utc>, zone: &Z, unit: EpochUnit) -> String
+where
+    Z::Offset: fmt::Display,
+{
+    let precision = match unit {
+        EpochUnit::Seconds => SecondsFormat::Secs,
+        EpochUnit::Milliseconds => SecondsFormat::Millis,
+    };
+    utc.with_timezone(zone).to_rfc3339_opts(precision, true)
+}
+
+/// Render a Unix epoch as RFC 3339 in the given zone
+///
+/// Millisecond inputs keep millisecond precision in the output.
+pub fn epoch_to_rfc3339(
+    value: i64,
+    unit: Option<EpochUnit>,
+    zone: &TimeZoneSpec,
+) -> Result<String, TimeError> {
+    let unit = unit.unwrap_or_else(|| detect_epoch_unit(value));
+    let utc = match unit {
+        EpochUnit::Seconds => DateTime::from_timestamp(value, 0),
+        EpochUnit::Milliseconds => DateTime::from_timestamp_millis(value),
+    }
+    .ok_or(TimeError::OutOfRange(value))?;
+
+    Ok(match zone {
+        TimeZoneSpec::Utc => format_in(utc, &Utc, unit),
+        TimeZoneSpec::Local => format_in(utc, &Local, unit),
+        TimeZoneSpec::Offset(offset) => format_in(utc, offset, unit),
+        TimeZoneSpec::Named(tz) => format_in(utc, tz, unit),
+    })
+}
+
+fn localize<Z: TimeZone>(
+    naive: &NaiveDateTime,
+    zone: &Z,
+    input: &str,
+) -> Result<DateTime<Utc>, TimeError> {
+    zone.from_local_datetime(naive)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok_or_else(|| TimeError::InvalidLocalTime(input.to_string()))
+}
+
+/// Convert an RFC 3339 date-time to a Unix epoch
+///
+/// Date-times without an offset (`2024-01-31 12:00:00`) are interpreted in
+/// `zone`.
+pub fn rfc3339_to_epoch(
+    input: &str,
+    unit: EpochUnit,
+    zone: &TimeZoneSpec,
+) -> Result<i64, TimeError> {
+    let trimmed = input.trim();
+    let utc = match DateTime::parse_from_rfc3339(trimmed) {
+        Ok(dt) => dt.with_timezone(&Utc),
+        Err(_) => {
+            let naive = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+                .iter()
+                .find_map(|fmt| NaiveDateTime::parse_from_str(trimmed, fmt).ok())
+                .ok_or_else(|| TimeError::InvalidDateTime(input.to_string()))?;
+            match zone {
+                TimeZoneSpec::Utc => localize(&naive, &Utc, input)?,
+                TimeZoneSpec::Local => localize(&naive, &Local, input)?,
+                TimeZoneSpec::Offset(offset) => localize(&naive, offset, input)?,
+                TimeZoneSpec::Named(tz) => localize(&naive, tz, input)?,
+            }
+        }
+    };
+
+    Ok(match unit {
+        EpochUnit::Seconds => utc.timestamp(),
+        EpochUnit::Milliseconds => utc.timestamp_millis(),
+    })
+}
+
+/// Convert between epoch values and RFC 3339 according to `config`
+pub fn convert_time(input: &str, config: &TimeConfig) -> Result<String, TimeError> {
+    let trimmed = input.trim();
+    let from_epoch = match config.direction {
+        TimeDirection::FromEpoch => true,
+        TimeDirection::ToEpoch => false,
+        TimeDirection::Auto => trimmed.parse::<i64>().is_ok(),
+    };
+
+    if from_epoch {
+        let value = trimmed
+            .parse::<i64>()
+            .map_err(|_| TimeError::InvalidEpoch(input.to_string()))?;
+        epoch_to_rfc3339(value, config.unit, &config.zone)
+    } else {
+        rfc3339_to_epoch(trimmed, config.unit.unwrap_or_default(), &config.zone)
+            .map(|v| v.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_epoch_unit() {
+        assert_eq!(detect_epoch_unit(1_700_000_000), EpochUnit::Seconds);
+        assert_eq!(
+            detect_epoch_unit(1_700_000_000_123),
+            EpochUnit::Milliseconds
+        );
+        assert_eq!(detect_epoch_unit(0), EpochUnit::Seconds);
+    }
+
+    #[test]
+    fn test_epoch_to_rfc3339() {
+        let utc = TimeZoneSpec::Utc;
+        assert_eq!(
+            epoch_to_rfc3339(1_700_000_000, None, &utc).unwrap(),
+            "2023-11-14T22:13:20Z"
+        );
+        assert_eq!(
+            epoch_to_rfc3339(1_700_000_000_123, None, &utc).unwrap(),
+            "2023-11-14T22:13:20.123Z"
+        );
+        // A forced unit overrides magnitude detection
+        assert_eq!(
+            epoch_to_rfc3339(1_700_000_000, Some(EpochUnit::Milliseconds), &utc).unwrap(),
+            "1970-01-20T16:13:20.000Z"
+        );
+        assert!(matches!(
+            epoch_to_rfc3339(i64::MAX, Some(EpochUnit::Seconds), &utc),
+            Err(TimeError::OutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_epoch_to_rfc3339_in_zone() {
+        let tokyo = parse_time_zone("Asia/Tokyo").unwrap();
+        assert_eq!(
+            epoch_to_rfc3339(1_700_000_000, None, &tokyo).unwrap(),
+            "2023-11-15T07:13:20+09:00"
+        );
+        let offset = parse_time_zone("-05:30").unwrap();
+        assert_eq!(
+            epoch_to_rfc3339(1_700_000_000, None, &offset).unwrap(),
+            "2023-11-14T16:43:20-05:30"
+        );
+        assert!(matches!(
+            parse_time_zone("Mars/Olympus"),
+            Err(TimeError::UnknownTimeZone(_))
+        ));
+    }
+
+    #[test]
+    fn test_rfc3339_to_epoch() {
+        let utc = TimeZoneSpec::Utc;
+        assert_eq!(
+            rfc3339_to_epoch("2023-11-15T07:13:20+09:00", EpochUnit::Seconds, &utc).unwrap(),
+            1_700_000_000
+        );
+        assert_eq!(
+            rfc3339_to_epoch("2023-11-14T22:13:20.123Z", EpochUnit::Milliseconds, &utc).unwrap(),
+            1_700_000_000_123
+        );
+        // Zone-less input is read in the selected zone
+        let tokyo = parse_time_zone("Asia/Tokyo").unwrap();
+        assert_eq!(
+            rfc3339_to_epoch("2023-11-15 07:13:20", EpochUnit::Seconds, &tokyo).unwrap(),
+            1_700_000_000
+        );
+        assert!(matches!(
+            rfc3339_to_epoch("yesterday", EpochUnit::Seconds, &utc),
+            Err(TimeError::InvalidDateTime(_))
+        ));
+    }
+
+    #[test]
+    fn test_convert_time_auto_direction() {
+        let config = TimeConfig::default();
+        assert_eq!(
+            convert_time("1700000000123", &config).unwrap(),
+            "2023-11-14T22:13:20.123Z"
+        );
+        assert_eq!(
+            convert_time("2023-11-14T22:13:20Z", &config).unwrap(),
+            "1700000000"
+        );
+
+        let forced = TimeConfig {
+            direction: TimeDirection::FromEpoch,
+            ..Default::default()
+        };
+        assert!(matches!(
+            convert_time("2023-11-14", &forced),
+            Err(TimeError::InvalidEpoch(_))
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+use percent_encoding::{
+    percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS, NON_ALPHANUMERIC,
+};
+use std::fmt;
+
+/// Characters that can never appear literally in a URL
+const URL: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'\\')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// As [`URL`], plus `#` which would end the query early
+const QUERY: &AsciiSet = &URL.add(b'#');
+
+/// Everything except RFC 3986 unreserved characters
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// What part of the input to encode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UrlEncodeMode {
+    /// Encode a whole URL, keeping its structure (`:/?#&=` etc.) intact
+    #[default]
+    Whole,
+    /// Encode only the query string (after `?`, before `#`); `&` and `=`
+    /// separators are kept. Decoding also turns `+` into a space.
+    Query,
+    /// Encode a single component: every reserved character, including `&`,
+    /// `=`, `/` and `%`, is escaped
+    Component,
+}
+
+#[derive(Debug)]
+pub enum UrlEncodeError {
+    InvalidUtf8,
+}
+
+impl fmt::Display for UrlEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UrlEncodeError::InvalidUtf8 => write!(f, "Decoded bytes are not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for UrlEncodeError {}
+
+fn is_escape(bytes: &[u8]) -> bool {
+    bytes.len() >= 3
+        && bytes[0] == b'%'
+        && bytes[1].is_ascii_hexdigit()
+        && bytes[2].is_ascii_hexdigit()
+}
+
+/// Encode with `set`, copying existing `%XX` escapes through unchanged so
+/// already-encoded input is not double-encoded
+fn encode_preserving_escapes(input: &str, set: &'static AsciiSet) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(pos) = rest.find('%') {
+        out.extend(utf8_percent_encode(&rest[..pos], set));
+        if is_escape(&rest.as_bytes()[pos..]) {
+            out.push_str(&rest[pos..pos + 3]);
+            rest = &rest[pos + 3..];
+        } else {
+            out.push_str("%25");
+            rest = &rest[pos + 1..];
+        }
+    }
+    out.extend(utf8_percent_encode(rest, set));
+    out
+}
+
+/// Percent-encode `input` according to `mode`
+///
+/// `Whole` and `Query` leave valid `%XX` escapes alone, so encoding is
+/// idempotent; `Component` escapes every `%`.
+pub fn url_encode(input: &str, mode: UrlEncodeMode) -> String {
+    match mode {
+        UrlEncodeMode::Whole => encode_preserving_escapes(input, URL),
+        UrlEncodeMode::Component => utf8_percent_encode(input, COMPONENT).to_string(),
+        UrlEncodeMode::Query => {
+            let (prefix, rest) = match input.find('?') {
+                Some(pos) => input.split_at(pos + 1),
+                None => ("", input),
+            };
+            let (query, fragment) = match rest.find('#') {
+                Some(pos) => rest.split_at(pos),
+                None => (rest, ""),
+            };
+            format!(
+                "{}{}{}",
+                prefix,
+                encode_preserving_escapes(query, QUERY),
+                fragment
+            )
+        }
+    }
+}
+
+/// Decode percent-escapes in `input`; in `Query` mode `+` also decodes to a space
+pub fn url_decode(input: &str, mode: UrlEncodeMode) -> Result<String, UrlEncodeError> {
+    let decoded = if mode == UrlEncodeMode::Query {
+        let spaced = input.replace('+', " ");
+        percent_decode_str(&spaced)
+            .decode_utf8()
+            .map(|s| s.into_owned())
+    } else {
+        percent_decode_str(input)
+            .decode_utf8()
+            .map(|s| s.into_owned())
+    };
+    decoded.map_err(|_| UrlEncodeError::InvalidUtf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_spaces() {
+        assert_eq!(url_encode("a b", UrlEncodeMode::Whole), "a%20b");
+        assert_eq!(
+            url_encode("https://example.com/a b?q=x y&z=1", UrlEncodeMode::Whole),
+            "https://example.com/a%20b?q=x%20y&z=1"
+        );
+        assert_eq!(
+            url_encode("a b&c=d", UrlEncodeMode::Component),
+            "a%20b%26c%3Dd"
+        );
+    }
+
+    #[test]
+    fn test_encode_unicode() {
+        assert_eq!(url_encode("café", UrlEncodeMode::Component), "caf%C3%A9");
+        assert_eq!(
+            url_decode("caf%C3%A9", UrlEncodeMode::Whole).unwrap(),
+            "café"
+        );
+        assert!(matches!(
+            url_decode("%FF", UrlEncodeMode::Whole),
+            Err(UrlEncodeError::InvalidUtf8)
+        ));
+    }
+
+    #[test]
+    fn test_already_encoded_input() {
+        let encoded = "https://example.com/a%20b?q=caf%C3%A9";
+        assert_eq!(url_encode(encoded, UrlEncodeMode::Whole), encoded);
+        // A stray % that isn't an escape is still encoded
+        assert_eq!(
+            url_encode("100% sure", UrlEncodeMode::Whole),
+            "100%25%20sure"
+        );
+        // Component mode encodes exactly what it is given
+        assert_eq!(url_encode("a%20b", UrlEncodeMode::Component), "a%2520b");
+    }
+
+    #[test]
+    fn test_query_mode() {
+        assert_eq!(
+            url_encode(
+                "https://x.test/a b?q=a b&t=#frag ment",
+                UrlEncodeMode::Query
+            ),
+            "https://x.test/a b?q=a%20b&t=#frag ment"
+        );
+        assert_eq!(
+            url_encode("name=Jo Doe&x=1", UrlEncodeMode::Query),
+            "name=Jo%20Doe&x=1"
+        );
+        assert_eq!(
+            url_decode("q=a+b%2Bc", UrlEncodeMode::Query).unwrap(),
+            "q=a b+c"
+        );
+        assert_eq!(url_decode("a+b", UrlEncodeMode::Whole).unwrap(), "a+b");
+    }
+}
@@ -0,0 +1,136 @@
+use ::uuid::Uuid;
+use rand::Rng;
+
+/// UUID versions that can be generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UuidVersion {
+    /// Gregorian timestamp + node ID (a random node ID is used)
+    V1,
+    /// Random
+    #[default]
+    V4,
+    /// Unix timestamp + random, sortable by creation time
+    V7,
+}
+
+#[derive(Debug, Clone)]
+pub struct UuidConfig {
+    pub version: UuidVersion,
+    pub uppercase: bool,
+    pub hyphens: bool,
+}
+
+impl Default for UuidConfig {
+    fn default() -> Self {
+        Self {
+            version: UuidVersion::V4,
+            uppercase: false,
+            hyphens: true,
+        }
+    }
+}
+
+/// Generate a single UUID formatted according to `config`
+pub fn generate_uuid(config: &UuidConfig) -> String {
+    let uuid = match config.version {
+        UuidVersion::V1 => {
+            // RFC 9562: random node IDs must set the multicast bit
+            let mut node_id: [u8; 6] = rand::thread_rng().gen();
+            node_id[0] |= 0x01;
+            Uuid::now_v1(&node_id)
+        }
+        UuidVersion::V4 => Uuid::new_v4(),
+        UuidVersion::V7 => Uuid::now_v7(),
+    };
+
+    let formatted = if config.hyphens {
+        uuid.hyphenated().to_string()
+    } else {
+        uuid.simple().to_string()
+    };
+
+    if config.uppercase {
+        formatted.to_ascii_uppercase()
+    } else {
+        formatted
+    }
+}
+
+/// Generate `count` UUIDs
+pub fn generate_uuids(config: &UuidConfig, count: usize) -> Vec<String> {
+    (0..count).map(|_| generate_uuid(config)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn version_of(uuid: &str) -> char {
+        uuid.chars().filter(|c| *c != '-').nth(12).unwrap()
+    }
+
+    #[test]
+    fn test_default_format() {
+        let uuid = generate_uuid(&UuidConfig::default());
+        assert_eq!(uuid.len(), 36);
+        for (i, c) in uuid.chars().enumerate() {
+            if [8, 13, 18, 23].contains(&i) {
+                assert_eq!(c, '-');
+            } else {
+                assert!(c.is_ascii_hexdigit() && !c.is_ascii_uppercase());
+            }
+        }
+        assert_eq!(version_of(&uuid), '4');
+        // RFC 4122 variant: 10xx
+        assert!("89ab".contains(uuid.chars().nth(19).unwrap()));
+    }
+
+    #[test]
+    fn test_formatting_options() {
+        let config = UuidConfig {
+            uppercase: true,
+            hyphens: false,
+            ..Default::default()
+        };
+        let uuid = generate_uuid(&config);
+        assert_eq!(uuid.len(), 32);
+        assert!(uuid
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_ascii_uppercase()));
+    }
+
+    #[test]
+    fn test_versions() {
+        for (version, expected) in [
+            (UuidVersion::V1, '1'),
+            (UuidVersion::V4, '4'),
+            (UuidVersion::V7, '7'),
+        ] {
+            let config = UuidConfig {
+                version,
+                ..Default::default()
+            };
+            assert_eq!(version_of(&generate_uuid(&config)), expected);
+        }
+    }
+
+    #[test]
+    fn test_v4_unique_in_batch() {
+        let uuids = generate_uuids(&UuidConfig::default(), 1000);
+        let unique: HashSet<_> = uuids.iter().collect();
+        assert_eq!(unique.len(), 1000);
+    }
+
+    #[test]
+    fn test_v7_time_ordered() {
+        let config = UuidConfig {
+            version: UuidVersion::V7,
+            ..Default::default()
+        };
+        let uuids = generate_uuids(&config, 100);
+        let mut sorted = uuids.clone();
+        sorted.sort();
+        assert_eq!(uuids, sorted);
+    }
+}
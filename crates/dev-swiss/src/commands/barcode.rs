@@ -0,0 +1,79 @@
+use super::RunOutput;
+use crate::logging;
+use clap::{Args, ValueEnum};
+use dev_swiss_core::{
+    generate_barcode, parse_color, render_barcode_to_image, render_barcode_to_svg, save_image,
+    BarcodeImageConfig, BarcodeKind,
+};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct BarcodeArgs {
+    /// Data to encode
+    data: String,
+
+    /// Barcode symbology
+    #[arg(short = 't', long = "type", value_enum, default_value = "code128")]
+    kind: Kind,
+
+    /// Output file; `.svg` writes SVG, anything else an image in that format
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Pixels per module (1-100)
+    #[arg(short, long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..=100))]
+    scale: u32,
+
+    /// Bar height in pixels (1-10000)
+    #[arg(long, default_value_t = 80, value_parser = clap::value_parser!(u32).range(1..=10000))]
+    height: u32,
+
+    /// Bar color (hex: #000000 or name: black)
+    #[arg(long, default_value = "black")]
+    dark_color: String,
+
+    /// Background color (hex: #FFFFFF or name: white)
+    #[arg(long, default_value = "white")]
+    light_color: String,
+}
+
+#[derive(Clone, ValueEnum)]
+enum Kind {
+    Code128,
+}
+
+impl From<Kind> for BarcodeKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Code128 => BarcodeKind::Code128,
+        }
+    }
+}
+
+pub fn run(args: BarcodeArgs) -> Result<RunOutput, String> {
+    let barcode = generate_barcode(args.kind.into(), &args.data).map_err(|e| e.to_string())?;
+    let dark_color = parse_color(&args.dark_color).map_err(|e| e.to_string())?;
+    let light_color = parse_color(&args.light_color).map_err(|e| e.to_string())?;
+
+    let config = BarcodeImageConfig {
+        scale: args.scale,
+        height: args.height,
+        dark_color,
+        light_color,
+    };
+
+    let is_svg = args
+        .output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    if is_svg {
+        std::fs::write(&args.output, render_barcode_to_svg(&barcode, &config))
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+    } else {
+        let image = render_barcode_to_image(&barcode, &config).map_err(|e| e.to_string())?;
+        save_image(&image, &args.output).map_err(|e| e.to_string())?;
+    }
+
+    logging::status(format!("Saved barcode to {}", args.output.display()));
+    RunOutput::file(&args.output)
+}
@@ -0,0 +1,70 @@
+use super::RunOutput;
+use clap::Args;
+use dev_swiss_core::{decode_base64, encode_base64, Base64Alphabet, Base64Config};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct Base64Args {
+    /// Input string (reads stdin when omitted or "-")
+    #[arg(conflicts_with = "file")]
+    input: Option<String>,
+
+    /// Read input from a file
+    #[arg(short, long)]
+    file: Option<PathBuf>,
+
+    /// Decode base64 input
+    #[arg(short, long, conflicts_with = "encode")]
+    decode: bool,
+
+    /// Encode input to base64 (default)
+    #[arg(short, long)]
+    encode: bool,
+
+    /// Use the URL-safe alphabet (- and _ instead of + and /)
+    #[arg(long)]
+    url_safe: bool,
+
+    /// Omit = padding when encoding
+    #[arg(long)]
+    no_padding: bool,
+}
+
+fn read_input(args: &Base64Args) -> std::io::Result<Vec<u8>> {
+    match (&args.input, &args.file) {
+        (Some(input), _) if input != "-" => Ok(input.as_bytes().to_vec()),
+        (_, Some(path)) => std::fs::read(path),
+        _ => {
+            let mut buf = Vec::new();
+            std::io::stdin().read_to_end(&mut buf)?;
+            Ok(buf)
+        }
+    }
+}
+
+pub fn run(args: Base64Args) -> Result<RunOutput, String> {
+    let config = Base64Config {
+        alphabet: if args.url_safe {
+            Base64Alphabet::UrlSafe
+        } else {
+            Base64Alphabet::Standard
+        },
+        padding: !args.no_padding,
+    };
+
+    let input = read_input(&args).map_err(|e| e.to_string())?;
+
+    if args.decode {
+        let text = String::from_utf8_lossy(&input);
+        let bytes = decode_base64(&text, &config).map_err(|e| e.to_string())?;
+        let mut stdout = std::io::stdout();
+        stdout
+            .write_all(&bytes)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| e.to_string())?;
+    } else {
+        println!("{}", encode_base64(&input, &config));
+    }
+    Ok(RunOutput::default())
+}
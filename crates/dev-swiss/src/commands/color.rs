@@ -0,0 +1,62 @@
+use super::RunOutput;
+use clap::Args;
+use dev_swiss_core::{
+    color_name, contrast_ratio, parse_css_color, relative_luminance, rgb_to_hsl, to_hex_color,
+};
+
+#[derive(Args)]
+pub struct ColorArgs {
+    /// Color as #rrggbb, #rgb, rgb(r, g, b) or a CSS name
+    color: String,
+
+    /// Print the WCAG contrast ratio against another color
+    #[arg(long, value_name = "OTHER")]
+    contrast: Option<String>,
+}
+
+fn parse(input: &str) -> Result<[u8; 3], String> {
+    parse_css_color(input).map_err(|e| e.to_string())
+}
+
+/// WCAG 2 conformance for normal and large text
+fn wcag_rating(ratio: f64) -> &'static str {
+    if ratio >= 7.0 {
+        "AAA"
+    } else if ratio >= 4.5 {
+        "AA (AAA for large text)"
+    } else if ratio >= 3.0 {
+        "AA for large text only"
+    } else {
+        "fails WCAG AA"
+    }
+}
+
+pub fn run(args: ColorArgs) -> Result<RunOutput, String> {
+    let rgb = parse(&args.color)?;
+    let (h, s, l) = rgb_to_hsl(rgb);
+
+    println!("Hex:       {}", to_hex_color(rgb));
+    println!("RGB:       rgb({}, {}, {})", rgb[0], rgb[1], rgb[2]);
+    println!(
+        "HSL:       hsl({}, {}%, {}%)",
+        h.round(),
+        (s * 100.0).round(),
+        (l * 100.0).round()
+    );
+    if let Some(name) = color_name(rgb) {
+        println!("Name:      {}", name);
+    }
+    println!("Luminance: {:.4}", relative_luminance(rgb));
+
+    if let Some(other) = args.contrast {
+        let other_rgb = parse(&other)?;
+        let ratio = contrast_ratio(rgb, other_rgb);
+        println!(
+            "Contrast:  {:.2}:1 against {} ({})",
+            ratio,
+            to_hex_color(other_rgb),
+            wcag_rating(ratio)
+        );
+    }
+    Ok(RunOutput::default())
+}
@@ -1,8 +1,16 @@
+use super::RunOutput;
+use crate::logging::{self, Level};
 use clap::{Args, ValueEnum};
-use dev_swiss_core::{convert, ConvertConfig, Format as CoreFormat};
+use dev_swiss_core::{
+    convert, convert_stream, supported_conversions, ConvertConfig, ConvertError, ConvertResult,
+    DocxStyle, Format as CoreFormat,
+};
+use std::fs::File;
+use std::io::Write;
 use std::path::PathBuf;
 
 #[derive(Args)]
+#[command(after_help = supported_help())]
 pub struct ConvertArgs {
     /// Source format
     #[arg(short, long, value_enum)]
@@ -15,22 +23,84 @@ pub struct ConvertArgs {
     /// Input file path
     pub input: PathBuf,
 
-    /// Output file path
+    /// Output file path, or - for stdout
     pub output: PathBuf,
 
     /// Overwrite output file if it exists
     #[arg(long, default_value = "false")]
     pub force: bool,
 
-    /// Show detailed conversion info and warnings
-    #[arg(short, long, default_value = "false")]
-    pub verbose: bool,
+    /// Fail immediately if the PDF has no extractable text (e.g. scanned documents)
+    #[arg(long, default_value = "false")]
+    pub require_text: bool,
+
+    /// Validate and extract without writing the output file
+    #[arg(long, default_value = "false")]
+    pub dry_run: bool,
+
+    /// Encoding of a TXT input (e.g. windows-1252, shift_jis); detected automatically if omitted
+    #[arg(long)]
+    pub encoding: Option<String>,
+
+    /// Keep PDF hyperlinks as clickable links in the DOCX output
+    #[arg(long, default_value = "false")]
+    pub preserve_formatting: bool,
+
+    /// Convert column-aligned PDF text into DOCX tables
+    #[arg(long, default_value = "false")]
+    pub detect_tables: bool,
+
+    /// Base URL for resolving relative links in HTML input
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Produce byte-identical DOCX output for identical input (fixed IDs and timestamps)
+    #[arg(long, default_value = "false")]
+    pub deterministic: bool,
+
+    /// Print how long extraction and building the output took
+    #[arg(long, default_value = "false")]
+    pub timing: bool,
+
+    /// Font family for DOCX output (e.g. "Arial")
+    #[arg(long)]
+    pub font: Option<String>,
+
+    /// Font size in points for DOCX output (4-144)
+    #[arg(long)]
+    pub font_size: Option<f32>,
+
+    /// Page margin in points on every side of DOCX output (0-288; 72 is one inch)
+    #[arg(long)]
+    pub margin: Option<f32>,
+
+    /// Line spacing of DOCX output as a multiple of single spacing (e.g. 1.5; at most 10)
+    #[arg(long, value_name = "LINES")]
+    pub line_spacing: Option<f32>,
+
+    /// Space after each DOCX paragraph, in points (0-1584)
+    #[arg(long, value_name = "POINTS")]
+    pub paragraph_spacing: Option<f32>,
+
+    /// Existing DOCX to append the converted content to, after a page break
+    #[arg(long, value_name = "EXISTING.docx")]
+    pub append: Option<PathBuf>,
+
+    /// Add the values of filled PDF form fields after the last page
+    #[arg(long, default_value = "false")]
+    pub include_form_fields: bool,
+
+    /// Refuse input files larger than this many bytes
+    #[arg(long, value_name = "BYTES")]
+    pub max_size: Option<u64>,
 }
 
 #[derive(Clone, ValueEnum)]
 pub enum Format {
     Pdf,
     Docx,
+    Txt,
+    Html,
 }
 
 impl From<Format> for CoreFormat {
@@ -38,33 +108,214 @@ impl From<Format> for CoreFormat {
         match f {
             Format::Pdf => CoreFormat::Pdf,
             Format::Docx => CoreFormat::Docx,
+            Format::Txt => CoreFormat::Txt,
+            Format::Html => CoreFormat::Html,
         }
     }
 }
 
-pub fn run(args: ConvertArgs) {
+/// List of supported conversions for --help, e.g. "pdf -> docx"
+fn supported_help() -> String {
+    let pairs: Vec<String> = supported_conversions()
+        .iter()
+        .map(|(from, to)| {
+            format!(
+                "  {} -> {}",
+                from.to_string().to_lowercase(),
+                to.to_string().to_lowercase()
+            )
+        })
+        .collect();
+    format!("Supported conversions:\n{}", pairs.join("\n"))
+}
+
+pub fn run(args: ConvertArgs, strict: bool) -> Result<RunOutput, String> {
+    run_with_stdout(args, strict, std::io::stdout().lock())
+}
+
+/// Like [`run`], with `stdout` receiving the converted bytes when the output
+/// path is `-`
+pub fn run_with_stdout<W: Write>(
+    args: ConvertArgs,
+    strict: bool,
+    mut stdout: W,
+) -> Result<RunOutput, String> {
+    let to_stdout = args.output.as_os_str() == "-";
     let config = ConvertConfig {
         input_path: args.input,
         output_path: args.output.clone(),
         from_format: args.from.into(),
         to_format: args.to.into(),
         force: args.force,
-        verbose: args.verbose,
+        verbose: logging::enabled(Level::Info),
+        require_text: args.require_text,
+        dry_run: args.dry_run,
+        encoding: args.encoding,
+        preserve_formatting: args.preserve_formatting,
+        detect_tables: args.detect_tables,
+        base_url: args.base_url,
+        deterministic: args.deterministic,
+        timing: args.timing || logging::enabled(Level::Debug),
+        docx_style: DocxStyle {
+            font_name: args.font,
+            font_size_pt: args.font_size,
+            margin: args.margin,
+            line_spacing: args.line_spacing,
+            paragraph_spacing: args.paragraph_spacing,
+        },
+        strict,
+        append: args.append,
+        include_form_fields: args.include_form_fields,
+        max_input_bytes: args.max_size,
     };
 
-    match convert(&config) {
-        Ok(result) => {
-            if args.verbose {
-                println!("Converted {} page(s)", result.pages_processed);
-                for warning in &result.warnings {
-                    eprintln!("Warning: {}", warning);
-                }
-            }
-            println!("Successfully converted to {}", args.output.display());
+    let result = if to_stdout {
+        convert_to(&config, &mut stdout)
+    } else {
+        convert(&config)
+    };
+    if let Ok(ConvertResult {
+        extract_ms: Some(extract_ms),
+        build_ms: Some(build_ms),
+        ..
+    }) = &result
+    {
+        let timing = format!(
+            "Took {} ms to extract and {} ms to build",
+            extract_ms, build_ms
+        );
+        if args.timing {
+            logging::note(timing);
+        } else {
+            logging::debug(timing);
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+    }
+
+    let result = result.map_err(|e| e.to_string())?;
+    if args.dry_run {
+        println!("Dry run: no file written");
+        println!("Output:   {}", args.output.display());
+        println!("Pages:    {}", result.pages_processed);
+        println!("Warnings: {}", result.warnings.len());
+        for warning in &result.warnings {
+            logging::warning(warning);
         }
+        return Ok(RunOutput::default());
+    }
+
+    logging::info(format!("Converted {} page(s)", result.pages_processed));
+    if logging::enabled(Level::Info) {
+        for warning in &result.warnings {
+            logging::warning(warning);
+        }
+    } else if !result.warnings.is_empty() {
+        logging::note(format!(
+            "{} warning(s); rerun with --verbose to see them",
+            result.warnings.len()
+        ));
+    }
+    if to_stdout {
+        stdout
+            .flush()
+            .map_err(|e| format!("Failed to write output: {}", e))?;
+        return Ok(RunOutput::default());
+    }
+    logging::status(format!(
+        "Successfully converted to {}",
+        args.output.display()
+    ));
+    RunOutput::file(&args.output)
+}
+
+/// Convert `config.input_path` into `writer`, with the same input checks as
+/// [`convert`]
+fn convert_to<W: Write>(config: &ConvertConfig, writer: W) -> Result<ConvertResult, ConvertError> {
+    if !config.input_path.exists() {
+        return Err(ConvertError::InputNotFound(config.input_path.clone()));
+    }
+    let input = File::open(&config.input_path)?;
+    if let Some(limit) = config.max_input_bytes {
+        let size = input.metadata()?.len();
+        if size > limit {
+            return Err(ConvertError::InputTooLarge { size, limit });
+        }
+    }
+    convert_stream(input, writer, config.from_format, config.to_format, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: ConvertArgs,
+    }
+
+    fn parse(argv: &[&str]) -> ConvertArgs {
+        Cli::try_parse_from([&["convert"], argv].concat())
+            .unwrap()
+            .args
+    }
+
+    #[test]
+    fn test_output_to_stdout() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("notes.txt");
+        std::fs::write(&input, "First paragraph\n\nSecond paragraph\n").unwrap();
+
+        let mut stdout = Vec::new();
+        let args = parse(&["-f", "txt", "-t", "docx", input.to_str().unwrap(), "-"]);
+        let output = run_with_stdout(args, false, &mut stdout).unwrap();
+
+        // Nothing but the DOCX (a zip archive) reaches stdout, and no file named "-" is made
+        assert!(
+            stdout.starts_with(b"PK\x03\x04"),
+            "{:?}",
+            &stdout[..stdout.len().min(16)]
+        );
+        assert!(stdout
+            .windows(b"word/document.xml".len())
+            .any(|w| w == b"word/document.xml"));
+        assert_eq!(output, RunOutput::default());
+        assert!(!std::path::Path::new("-").exists());
+
+        let mut stdout = Vec::new();
+        let args = parse(&["-f", "docx", "-t", "txt", "missing.docx", "-"]);
+        let err = run_with_stdout(args, false, &mut stdout).unwrap_err();
+        assert!(err.starts_with("Input file not found"), "{}", err);
+        assert!(stdout.is_empty());
+    }
+
+    #[test]
+    fn test_timing_goes_through_logging() {
+        use crate::logging::{capture, Verbosity};
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("notes.txt");
+        std::fs::write(&input, "Hello\n").unwrap();
+        let lines_at = |verbosity| {
+            let argv = [
+                "-f",
+                "txt",
+                "-t",
+                "docx",
+                "--timing",
+                input.to_str().unwrap(),
+                "-",
+            ];
+            let (result, lines) = capture(verbosity, || {
+                run_with_stdout(parse(&argv), false, &mut Vec::new())
+            });
+            result.unwrap();
+            lines
+        };
+
+        let normal = lines_at(Verbosity::Normal);
+        assert_eq!(normal.len(), 1, "{:?}", normal);
+        assert!(normal[0].starts_with("Note: Took "), "{}", normal[0]);
+        assert!(lines_at(Verbosity::Quiet).is_empty());
     }
 }
@@ -0,0 +1,41 @@
+use super::RunOutput;
+use crate::logging;
+use clap::Args;
+use dev_swiss_core::{decode_qr_file, DecodeConfig};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct DecodeArgs {
+    /// Image containing one or more QR codes (PNG or JPEG)
+    image: PathBuf,
+
+    /// Clean up skewed or low-contrast photos before decoding (falls back to the raw image)
+    #[arg(long, default_value = "false")]
+    preprocess: bool,
+
+    /// Expand codes made with `dev-swiss qrcode --compress`; other codes print as they are
+    #[arg(long, default_value = "false")]
+    decompress: bool,
+
+    /// Check and strip the checksum added by `dev-swiss qrcode --append-crc`; fails if it is
+    /// missing or wrong
+    #[arg(long, default_value = "false")]
+    verify_crc: bool,
+}
+
+pub fn run(args: DecodeArgs) -> Result<RunOutput, String> {
+    let config = DecodeConfig {
+        preprocess: args.preprocess,
+        decompress: args.decompress,
+        verify_crc: args.verify_crc,
+    };
+
+    let result = decode_qr_file(&args.image, &config).map_err(|e| e.to_string())?;
+    if args.preprocess && !result.preprocessed {
+        logging::note("preprocessing found no code; read from the original image");
+    }
+    for content in &result.contents {
+        println!("{}", content);
+    }
+    Ok(RunOutput::default())
+}
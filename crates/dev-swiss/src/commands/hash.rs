@@ -0,0 +1,85 @@
+use super::RunOutput;
+use crate::logging;
+use clap::{Args, ValueEnum};
+use dev_swiss_core::{
+    digest_matches, encode_base64, hash_reader, to_hex, Base64Config, HashAlgorithm,
+};
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct HashArgs {
+    /// Input string (reads stdin when omitted or "-")
+    #[arg(conflicts_with = "file")]
+    input: Option<String>,
+
+    /// Hash a file
+    #[arg(short, long)]
+    file: Option<PathBuf>,
+
+    /// Digest algorithm
+    #[arg(short, long, value_enum, default_value = "sha256")]
+    algo: Algorithm,
+
+    /// Print the digest as base64 instead of hex
+    #[arg(long)]
+    base64: bool,
+
+    /// Expected digest (hex or base64); exit with status 1 on mismatch
+    #[arg(long)]
+    verify: Option<String>,
+}
+
+#[derive(Clone, ValueEnum)]
+enum Algorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl From<Algorithm> for HashAlgorithm {
+    fn from(a: Algorithm) -> Self {
+        match a {
+            Algorithm::Md5 => HashAlgorithm::Md5,
+            Algorithm::Sha1 => HashAlgorithm::Sha1,
+            Algorithm::Sha256 => HashAlgorithm::Sha256,
+            Algorithm::Sha512 => HashAlgorithm::Sha512,
+        }
+    }
+}
+
+pub fn run(args: HashArgs) -> Result<RunOutput, String> {
+    let algorithm: HashAlgorithm = args.algo.into();
+
+    let reader: Box<dyn Read> = match (&args.input, &args.file) {
+        (Some(input), _) if input != "-" => {
+            Box::new(std::io::Cursor::new(input.clone().into_bytes()))
+        }
+        (_, Some(path)) => {
+            Box::new(File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?)
+        }
+        _ => Box::new(std::io::stdin()),
+    };
+
+    let digest = hash_reader(reader, algorithm).map_err(|e| e.to_string())?;
+
+    if args.base64 {
+        println!("{}", encode_base64(&digest, &Base64Config::default()));
+    } else {
+        println!("{}", to_hex(&digest));
+    }
+
+    if let Some(expected) = args.verify {
+        if !digest_matches(&digest, &expected) {
+            return Err(format!(
+                "{} digest does not match {}",
+                algorithm,
+                expected.trim()
+            ));
+        }
+        logging::note(format!("{} digest matches", algorithm));
+    }
+    Ok(RunOutput::default())
+}
@@ -0,0 +1,119 @@
+use super::RunOutput;
+use clap::{Args, ValueEnum};
+use dev_swiss_core::{decode_jwt, time_claims, verify_jwt, JwtAlgorithm};
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Args)]
+pub struct JwtArgs {
+    /// Token to decode (reads stdin when omitted or "-")
+    token: Option<String>,
+
+    /// Verify the signature (requires --secret)
+    #[arg(long, requires = "secret")]
+    verify: bool,
+
+    /// HMAC secret for --verify
+    #[arg(long, env = "JWT_SECRET", hide_env_values = true)]
+    secret: Option<String>,
+
+    /// Expected signing algorithm
+    #[arg(long, value_enum, default_value = "hs256")]
+    alg: Algorithm,
+}
+
+#[derive(Clone, ValueEnum)]
+enum Algorithm {
+    Hs256,
+    Hs384,
+    Hs512,
+}
+
+impl From<Algorithm> for JwtAlgorithm {
+    fn from(a: Algorithm) -> Self {
+        match a {
+            Algorithm::Hs256 => JwtAlgorithm::Hs256,
+            Algorithm::Hs384 => JwtAlgorithm::Hs384,
+            Algorithm::Hs512 => JwtAlgorithm::Hs512,
+        }
+    }
+}
+
+pub fn run(args: JwtArgs) -> Result<RunOutput, String> {
+    let token = match args.token {
+        Some(token) if token != "-" => token,
+        _ => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .map_err(|e| e.to_string())?;
+            buf
+        }
+    };
+
+    let jwt = decode_jwt(&token).map_err(|e| e.to_string())?;
+
+    println!("Header:\n{:#}\n", jwt.header);
+    println!("Payload:\n{:#}", jwt.payload);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let claims = time_claims(&jwt.payload, now);
+    if !claims.is_empty() {
+        println!("\nTimes:");
+        for claim in claims {
+            println!("  {:<4} {}", claim.name, claim.description);
+        }
+    }
+
+    // An exported JWT_SECRET alone doesn't verify, so tokens signed with
+    // other keys still decode
+    match args.secret.filter(|_| args.verify) {
+        Some(secret) => {
+            let algorithm: JwtAlgorithm = args.alg.into();
+            verify_jwt(&jwt, secret.as_bytes(), algorithm).map_err(|e| e.to_string())?;
+            println!("\nSignature: verified ({})", algorithm.name());
+        }
+        None => println!("\nSignature: not verified"),
+    }
+    Ok(RunOutput::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: JwtArgs,
+    }
+
+    fn parse(argv: &[&str]) -> JwtArgs {
+        Cli::try_parse_from([&["jwt"], argv].concat()).unwrap().args
+    }
+
+    /// Signed with HS256 and the secret `your-256-bit-secret`
+    const TOKEN: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+        eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ.\
+        SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c";
+
+    #[test]
+    fn test_secret_verifies_only_with_verify() {
+        // A secret for some other key, as an exported JWT_SECRET would give
+        assert!(run(parse(&["--secret", "other-key", TOKEN])).is_ok());
+
+        let err = run(parse(&["--verify", "--secret", "other-key", TOKEN])).unwrap_err();
+        assert_eq!(err, "Signature verification failed");
+        assert!(run(parse(&[
+            "--verify",
+            "--secret",
+            "your-256-bit-secret",
+            TOKEN
+        ]))
+        .is_ok());
+    }
+}
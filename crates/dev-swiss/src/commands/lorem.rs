@@ -0,0 +1,60 @@
+use super::RunOutput;
+use clap::Args;
+use dev_swiss_core::{generate_lorem, generate_lorem_with_rng, LoremConfig, LoremLength};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[derive(Args)]
+pub struct LoremArgs {
+    /// Number of words
+    #[arg(short, long, conflicts_with_all = ["sentences", "paragraphs"])]
+    words: Option<usize>,
+
+    /// Number of sentences
+    #[arg(short, long, conflicts_with = "paragraphs")]
+    sentences: Option<usize>,
+
+    /// Number of paragraphs (default: 1)
+    #[arg(short, long)]
+    paragraphs: Option<usize>,
+
+    /// Don't start with "Lorem ipsum dolor sit amet"
+    #[arg(long)]
+    no_classic: bool,
+
+    /// Seed for reproducible output
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Print a JSON array of paragraphs
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: LoremArgs) -> Result<RunOutput, String> {
+    let length = if let Some(n) = args.words {
+        LoremLength::Words(n)
+    } else if let Some(n) = args.sentences {
+        LoremLength::Sentences(n)
+    } else {
+        LoremLength::Paragraphs(args.paragraphs.unwrap_or(1))
+    };
+
+    let config = LoremConfig {
+        length,
+        classic_start: !args.no_classic,
+    };
+
+    let paragraphs = match args.seed {
+        Some(seed) => generate_lorem_with_rng(&config, &mut StdRng::seed_from_u64(seed)),
+        None => generate_lorem(&config),
+    };
+
+    if args.json {
+        let quoted: Vec<String> = paragraphs.iter().map(|p| format!("\"{}\"", p)).collect();
+        println!("[{}]", quoted.join(", "));
+    } else {
+        println!("{}", paragraphs.join("\n\n"));
+    }
+    Ok(RunOutput::default())
+}
@@ -1,5 +1,84 @@
+pub mod base64;
+pub mod color;
+pub mod lorem;
 pub mod password;
 pub mod qrcode;
+pub mod random;
+pub mod slug;
+pub mod url;
+pub mod uuid;
+
+#[cfg(feature = "barcode")]
+pub mod barcode;
 
 #[cfg(feature = "convert")]
 pub mod convert;
+
+#[cfg(feature = "decode")]
+pub mod decode;
+
+#[cfg(feature = "hash")]
+pub mod hash;
+
+#[cfg(feature = "jwt")]
+pub mod jwt;
+
+#[cfg(feature = "time")]
+pub mod time;
+
+use crate::logging;
+use std::path::{Path, PathBuf};
+
+/// What a command wrote, so scripts, tests and embedders can check it
+/// without parsing stdout
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunOutput {
+    /// Every file written, in order; empty for commands that only print
+    pub files: Vec<WrittenFile>,
+}
+
+/// A file a command wrote and its size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenFile {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+impl RunOutput {
+    /// Output for a command that wrote the one file at `path`
+    pub fn file(path: &Path) -> Result<Self, String> {
+        let mut output = Self::default();
+        output.add_file(path)?;
+        Ok(output)
+    }
+
+    /// Record `path`, which has just been written, with its size on disk
+    pub fn add_file(&mut self, path: &Path) -> Result<(), String> {
+        let bytes = std::fs::metadata(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+            .len();
+        self.files.push(WrittenFile {
+            path: path.to_path_buf(),
+            bytes,
+        });
+        Ok(())
+    }
+}
+
+/// Print `warnings`, or with --strict fail with the first one instead
+pub fn report_warnings(warnings: &[String], strict: bool) -> Result<(), String> {
+    match warnings.first() {
+        Some(warning) if strict => Err(format!("{} (--strict)", warning)),
+        _ => {
+            for warning in warnings {
+                logging::warning(warning);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Report a single warning, failing when --strict makes it an error
+pub fn warn(warning: impl Into<String>, strict: bool) -> Result<(), String> {
+    report_warnings(&[warning.into()], strict)
+}
@@ -1,11 +1,29 @@
+use super::{warn, RunOutput};
+use crate::logging::{self, Level};
 use clap::Args;
-use dev_swiss_core::{generate_password, PasswordConfig};
+use dev_swiss_core::{
+    available_wordlists, char_class_histogram, class_probabilities, fully_excluded_classes,
+    generate_leet_password, generate_passphrase, generate_password_audited, mask_secret,
+    parse_class_weights, CharClass, ClassWeights, LeetConfig, PassphraseConfig, PasswordConfig,
+    PasswordError, MAX_PASSWORD_LENGTH, PASSWORD_POLICIES,
+};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 #[derive(Args)]
 pub struct PasswordArgs {
-    /// Password length
-    #[arg(short, long, default_value_t = 16)]
-    length: usize,
+    /// Start from a preset (pin, nist, strong, pci); other options override its fields
+    #[arg(
+        long,
+        value_parser = clap::builder::PossibleValuesParser::new(PASSWORD_POLICIES),
+        conflicts_with_all = ["leet", "passphrase"]
+    )]
+    policy: Option<String>,
+
+    /// Password length [default: 16, or the policy's]
+    #[arg(short, long)]
+    length: Option<usize>,
 
     /// Number of passwords to generate
     #[arg(short = 'n', long, default_value_t = 1)]
@@ -27,33 +45,508 @@ pub struct PasswordArgs {
     #[arg(long)]
     no_symbols: bool,
 
-    /// Exclude ambiguous characters (0O1lI)
+    /// Exclude ambiguous characters (0O1lI, or the --ambiguous-chars set)
     #[arg(long)]
     no_ambiguous: bool,
 
-    /// Custom characters to exclude
-    #[arg(long, default_value = "")]
-    exclude: String,
+    /// Characters --no-ambiguous removes instead of 0O1lI (e.g. "0O1lI5S2Z8B")
+    #[arg(long, value_name = "CHARS", requires = "no_ambiguous")]
+    ambiguous_chars: Option<String>,
+
+    /// Custom characters to exclude (whitespace ignored; use \s to exclude whitespace)
+    #[arg(long)]
+    exclude: Option<String>,
+
+    /// Allow lengths above the 4096-character safety limit
+    #[arg(long)]
+    allow_huge: bool,
+
+    /// Allow --length 0, printing empty passwords
+    #[arg(long)]
+    allow_empty: bool,
+
+    /// Regenerate passwords containing keyboard runs like "asdf" of at least N keys (default 4)
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "4")]
+    reject_keyboard_runs: Option<usize>,
+
+    /// Never put two characters of the same class (uppercase, lowercase, number, symbol) next to
+    /// each other
+    #[arg(long)]
+    no_adjacent_same_class: bool,
+
+    /// Favor some classes, e.g. "lowercase=3,numbers=2" (per character; unlisted classes weigh 1)
+    #[arg(
+        long,
+        value_name = "CLASS=N,...",
+        value_parser = weights_arg,
+        conflicts_with_all = ["leet", "passphrase"]
+    )]
+    weights: Option<ClassWeights>,
+
+    /// Generate a memorable dictionary word with leetspeak substitutions (e.g. Dr@g0n!7)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "passphrase", "length", "no_uppercase", "no_lowercase", "no_ambiguous", "exclude",
+            "allow_huge", "allow_empty", "reject_keyboard_runs", "no_adjacent_same_class",
+        ]
+    )]
+    leet: bool,
+
+    /// Generate a passphrase of random dictionary words (e.g. forest-anchor-pebble-lantern-orbit)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "length", "no_uppercase", "no_lowercase", "no_numbers", "no_symbols", "no_ambiguous",
+            "exclude", "allow_huge", "allow_empty", "reject_keyboard_runs",
+            "no_adjacent_same_class",
+        ]
+    )]
+    passphrase: bool,
+
+    /// Number of words in each passphrase [default: 5]
+    #[arg(long, value_name = "N", requires = "passphrase")]
+    words: Option<usize>,
+
+    /// Text between passphrase words [default: -]
+    #[arg(long, value_name = "SEP", requires = "passphrase")]
+    separator: Option<String>,
+
+    /// Wordlist language for passphrases
+    #[arg(
+        long,
+        default_value = "en",
+        value_parser = clap::builder::PossibleValuesParser::new(available_wordlists()),
+        requires = "passphrase"
+    )]
+    lang: String,
+
+    /// Capitalize each passphrase word
+    #[arg(long, requires = "passphrase")]
+    capitalize: bool,
+
+    /// Print how often each character class appeared instead of the passwords, to check for bias
+    #[arg(long, conflicts_with_all = ["leet", "passphrase", "output"])]
+    stats: bool,
+
+    /// Write passwords to this file (owner-only permissions) instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Overwrite the --output file if it already exists
+    #[arg(long, requires = "output")]
+    force: bool,
+
+    /// Also print each saved password masked except its first and last character
+    #[arg(long, requires = "output")]
+    mask: bool,
+}
+
+/// Create `path` readable and writable only by its owner
+///
+/// Fails if the file exists unless `force` is set. On Unix the mode is applied
+/// when the file is created, so the secret is never briefly world-readable.
+fn create_private_file(path: &Path, force: bool) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let file = options.open(path)?;
+        // An overwritten file keeps its old mode unless reset
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    options.open(path)
+}
+
+/// Print the passwords, or save them to --output, optionally with a masked
+/// preview on stdout
+fn emit(
+    passwords: &[String],
+    output: Option<&Path>,
+    force: bool,
+    mask: bool,
+) -> Result<RunOutput, String> {
+    let Some(path) = output else {
+        for password in passwords {
+            println!("{}", password);
+        }
+        return Ok(RunOutput::default());
+    };
+
+    let mut file = create_private_file(path, force).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => format!(
+            "{} already exists; pass --force to overwrite",
+            path.display()
+        ),
+        _ => format!("Failed to create {}: {}", path.display(), e),
+    })?;
+    for password in passwords {
+        writeln!(file, "{}", password)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    logging::status(format!(
+        "Saved {} password(s) to {}",
+        passwords.len(),
+        path.display()
+    ));
+    if mask {
+        for password in passwords {
+            println!("  {}", mask_secret(password, 1));
+        }
+    }
+    RunOutput::file(path)
+}
+
+fn weights_arg(spec: &str) -> Result<ClassWeights, String> {
+    parse_class_weights(spec).map_err(|e| e.to_string())
 }
 
-pub fn run(args: PasswordArgs) {
-    let config = PasswordConfig {
-        length: args.length,
-        uppercase: !args.no_uppercase,
-        lowercase: !args.no_lowercase,
-        numbers: !args.no_numbers,
-        symbols: !args.no_symbols,
-        exclude_ambiguous: args.no_ambiguous,
-        exclude_chars: args.exclude,
+/// Rows of (class, count, share, expected share) for --stats, in class order
+///
+/// The expected share is the chance of drawing the class, from its part of
+/// the character pool and --weights, which an unbiased generator matches
+/// given enough characters.
+fn class_stats(passwords: &[String], config: &PasswordConfig) -> Vec<(CharClass, usize, f64, f64)> {
+    let histogram = char_class_histogram(passwords);
+    let probabilities = class_probabilities(config);
+    let total: usize = histogram.values().sum();
+    let share = |count: usize| {
+        if total == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / total as f64
+        }
     };
 
+    CharClass::ALL
+        .into_iter()
+        .filter(|class| histogram.contains_key(class) || probabilities.contains_key(class))
+        .map(|class| {
+            let count = histogram.get(&class).copied().unwrap_or(0);
+            let expected = probabilities.get(&class).copied().unwrap_or(0.0) * 100.0;
+            (class, count, share(count), expected)
+        })
+        .collect()
+}
+
+fn print_stats(passwords: &[String], config: &PasswordConfig) {
+    let total: usize = passwords
+        .iter()
+        .map(|password| password.chars().count())
+        .sum();
+    println!("Passwords:  {}", passwords.len());
+    println!("Characters: {}", total);
+    println!();
+    println!(
+        "{:<10}  {:>10}  {:>6}  {:>8}",
+        "Class", "Count", "Share", "Expected"
+    );
+    for (class, count, share, expected) in class_stats(passwords, config) {
+        println!(
+            "{:<10}  {:>10}  {:>5.1}%  {:>7.1}%",
+            class.name(),
+            count,
+            share,
+            expected
+        );
+    }
+}
+
+/// The --policy preset (or the defaults) with the explicitly given options applied
+fn password_config(args: &PasswordArgs) -> Result<PasswordConfig, String> {
+    let mut config = match &args.policy {
+        Some(name) => PasswordConfig::from_policy(name).map_err(|e| e.to_string())?,
+        None => PasswordConfig::default(),
+    };
+
+    if let Some(length) = args.length {
+        config.length = length;
+    }
+    config.uppercase &= !args.no_uppercase;
+    config.lowercase &= !args.no_lowercase;
+    config.numbers &= !args.no_numbers;
+    config.symbols &= !args.no_symbols;
+    config.exclude_ambiguous |= args.no_ambiguous;
+    if let Some(chars) = &args.ambiguous_chars {
+        config.ambiguous_chars = Some(chars.clone());
+    }
+    if let Some(exclude) = &args.exclude {
+        config.exclude_chars = exclude.clone();
+    }
+    if args.allow_huge {
+        config.max_length = None;
+    }
+    config.allow_empty |= args.allow_empty;
+    if args.reject_keyboard_runs.is_some() {
+        config.reject_keyboard_runs = args.reject_keyboard_runs;
+    }
+    config.no_adjacent_same_class |= args.no_adjacent_same_class;
+    if let Some(weights) = args.weights {
+        config.class_weights = weights;
+    }
+    Ok(config)
+}
+
+fn passphrase_config(args: &PasswordArgs) -> PassphraseConfig {
+    let defaults = PassphraseConfig::default();
+    PassphraseConfig {
+        words: args.words.unwrap_or(defaults.words),
+        separator: args.separator.clone().unwrap_or(defaults.separator),
+        capitalize: args.capitalize,
+        lang: args.lang.clone(),
+    }
+}
+
+pub fn run(args: PasswordArgs, strict: bool) -> Result<RunOutput, String> {
+    if args.passphrase {
+        let config = passphrase_config(&args);
+        let mut passwords = Vec::with_capacity(args.count);
+        for _ in 0..args.count {
+            passwords.push(generate_passphrase(&config).map_err(|e| e.to_string())?);
+        }
+        let output = emit(&passwords, args.output.as_deref(), args.force, args.mask)?;
+        let bits = config.entropy_bits().map_err(|e| e.to_string())?;
+        logging::note(format!(
+            "~{:.0} bits of entropy ({} words from the '{}' list)",
+            bits, config.words, config.lang
+        ));
+        return Ok(output);
+    }
+
+    if args.leet {
+        let config = LeetConfig {
+            numbers: !args.no_numbers,
+            symbols: !args.no_symbols,
+            ..Default::default()
+        };
+
+        let mut passwords = Vec::with_capacity(args.count);
+        for _ in 0..args.count {
+            passwords.push(generate_leet_password(&config).map_err(|e| e.to_string())?);
+        }
+        let output = emit(&passwords, args.output.as_deref(), args.force, args.mask)?;
+        logging::note(format!(
+            "~{:.0} bits of entropy; fine for low-value accounts only",
+            config.entropy_bits()
+        ));
+        return Ok(output);
+    }
+
+    let config = password_config(&args)?;
+
+    if logging::enabled(Level::Info) || strict {
+        for class in fully_excluded_classes(&config) {
+            warn(
+                format!("exclusions remove every {} character", class),
+                strict,
+            )?;
+        }
+    }
+
+    let mut passwords = Vec::with_capacity(args.count);
     for _ in 0..args.count {
-        match generate_password(&config) {
-            Ok(password) => println!("{}", password),
-            Err(e) => {
-                eprintln!("Error: {}", e);
-                std::process::exit(1);
+        match generate_password_audited(&config) {
+            Ok((password, audit)) => {
+                passwords.push(password);
+                logging::info(format!(
+                    "  pool: {} chars, uppercase: {}, lowercase: {}, numbers: {}, symbols: {}",
+                    audit.pool_size,
+                    audit.has_uppercase,
+                    audit.has_lowercase,
+                    audit.has_numbers,
+                    audit.has_symbols
+                ));
+            }
+            Err(e @ PasswordError::LengthTooLarge(_)) => {
+                return Err(format!(
+                    "{} ({}); pass --allow-huge to override",
+                    e, MAX_PASSWORD_LENGTH
+                ));
             }
+            Err(e @ PasswordError::LengthTooShort) => {
+                return Err(format!(
+                    "{}; pass --allow-empty to print empty passwords",
+                    e
+                ));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    if args.stats {
+        print_stats(&passwords, &config);
+        if config.no_adjacent_same_class {
+            logging::note(
+                "--no-adjacent-same-class skews the shares away from the pool's composition",
+            );
+        }
+        return Ok(RunOutput::default());
+    }
+    emit(&passwords, args.output.as_deref(), args.force, args.mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: PasswordArgs,
+    }
+
+    fn config_for(argv: &[&str]) -> PasswordConfig {
+        let argv = std::iter::once("password").chain(argv.iter().copied());
+        password_config(&Cli::parse_from(argv).args).unwrap()
+    }
+
+    #[test]
+    fn test_policy_fields_can_be_overridden() {
+        assert_eq!(config_for(&[]).length, 16);
+
+        let pin = config_for(&["--policy", "pin"]);
+        assert_eq!(pin.length, 6);
+        assert!(!pin.lowercase);
+
+        let longer_pin = config_for(&["--policy", "pin", "-l", "8"]);
+        assert_eq!(longer_pin.length, 8);
+        assert!(longer_pin.numbers && !longer_pin.lowercase);
+
+        let strong = config_for(&[
+            "--policy",
+            "strong",
+            "--no-symbols",
+            "--reject-keyboard-runs",
+            "3",
+        ]);
+        assert_eq!(strong.length, 32);
+        assert!(!strong.symbols);
+        assert_eq!(strong.reject_keyboard_runs, Some(3));
+    }
+
+    #[test]
+    fn test_class_stats_cover_enabled_classes() {
+        let config = config_for(&["--no-symbols"]);
+        let passwords = vec!["Ab12".to_string(), "cd34".to_string()];
+        let stats = class_stats(&passwords, &config);
+
+        let classes: Vec<CharClass> = stats.iter().map(|row| row.0).collect();
+        assert_eq!(
+            classes,
+            [
+                CharClass::Uppercase,
+                CharClass::Lowercase,
+                CharClass::Number
+            ]
+        );
+        let (_, count, share, expected) = stats[2];
+        assert_eq!(count, 4);
+        assert_eq!(share, 50.0);
+        // 10 digits in a pool of 62
+        assert!((expected - 1000.0 / 62.0).abs() < 1e-9);
+
+        // Doubling the digits' weight doubles their entries in the draw
+        let weighted = config_for(&["--no-symbols", "--weights", "numbers=2"]);
+        let (_, _, _, expected) = class_stats(&passwords, &weighted)[2];
+        assert!((expected - 2000.0 / 72.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_create_private_file_refuses_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passwords.txt");
+        std::fs::write(&path, "keep me").unwrap();
+
+        let err = create_private_file(&path, false).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+
+        create_private_file(&path, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_private_file_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passwords.txt");
+        create_private_file(&path, false).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // Overwriting also tightens a file that was readable by others
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        create_private_file(&path, true).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_passphrase_options() {
+        let argv = [
+            "password",
+            "--passphrase",
+            "--lang",
+            "es",
+            "--words",
+            "3",
+            "--separator",
+            "·",
+            "--capitalize",
+        ];
+        let config = passphrase_config(&Cli::parse_from(argv).args);
+        assert_eq!(
+            (
+                config.words,
+                config.separator.as_str(),
+                config.lang.as_str()
+            ),
+            (3, "·", "es")
+        );
+        assert!(config.capitalize);
+
+        let defaults = passphrase_config(&Cli::parse_from(["password", "--passphrase"]).args);
+        assert_eq!(
+            (
+                defaults.words,
+                defaults.separator.as_str(),
+                defaults.lang.as_str()
+            ),
+            (5, "-", "en")
+        );
+
+        assert!(Cli::try_parse_from(["password", "--passphrase", "--lang", "xx"]).is_err());
+        assert!(Cli::try_parse_from(["password", "--lang", "de"]).is_err());
+        assert!(Cli::try_parse_from(["password", "--passphrase", "--leet"]).is_err());
+    }
+
+    #[test]
+    fn test_word_modes_reject_charset_options() {
+        for argv in [
+            &["--leet", "--length", "64"][..],
+            &["--leet", "--no-uppercase"],
+            &["--leet", "--exclude", "@"],
+            &["--passphrase", "--length", "3"],
+            &["--passphrase", "--no-symbols"],
+            &["--passphrase", "--reject-keyboard-runs"],
+        ] {
+            let parsed = Cli::try_parse_from([&["password"], argv].concat());
+            assert!(parsed.is_err(), "{:?} was accepted", argv);
         }
+        // The suffix toggles still apply to --leet
+        assert!(
+            Cli::try_parse_from(["password", "--leet", "--no-numbers", "--no-symbols"]).is_ok()
+        );
     }
 }
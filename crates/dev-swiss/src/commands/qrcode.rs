@@ -1,21 +1,52 @@
-use clap::{Args, ValueEnum};
+use clap::builder::RangedU64ValueParser;
+use clap::{Args, Subcommand, ValueEnum};
 use dev_swiss_core::{
-    generate_qr, parse_color, render_to_terminal, ErrorCorrectionLevel, ImageConfig, LogoConfig,
-    QrConfig,
+    append_crc, auto_output_path, auto_wrap_content, best_error_correction, capacity,
+    compress_content, contrast_warning, detect_content_type, ec_for_use_case, expand_env,
+    generate_qr_bytes, parse_color, qr_info, read_content_file, render_to_pbm, render_to_terminal,
+    render_to_terminal_colored, render_to_unicode_string, scale_for_target_px, unset_env_vars,
+    BackgroundFit, ErrorCorrectionLevel, ImageConfig, Mode, PaletteMode, PbmConfig, QrCode,
+    QrConfig, QrError, QrInfo, UnicodeConfig, UseCase, QUIET_ZONE_MODULES,
 };
 
 #[cfg(feature = "ai-generation")]
-use dev_swiss_core::generate_ai_qr;
+use dev_swiss_core::{
+    generate_ai_qr, is_remote_source, AiConfig, RateLimit, RateLimitedProvider, ScratchDir,
+    StabilityProvider,
+};
 
-use dev_swiss_core::{overlay_logo, overlay_on_background, render_to_image, render_to_svg, save_image};
+#[cfg(feature = "image-output")]
+use dev_swiss_core::{
+    output_action, parse_range, parse_sheet_entries, range_items, render_batch, render_sheet,
+    save_qr, BackgroundConfig, ExistingOutput, LogoConfig, OutputAction, OutputFormat, SheetConfig,
+    SvgOptions, FILE_OUTPUT_FORMATS,
+};
+
+#[cfg(feature = "image-output")]
+use dev_swiss_core::{
+    add_border, add_label, embed_png_metadata, encode_png, encode_png_optimized, finish_image,
+    is_likely_scannable, overlay_logo, overlay_on_background, overlay_on_background_with_logo,
+    qr_metadata, render_to_image, render_to_svg, render_to_svg_with, save_image, with_quiet_zone,
+    LogoPlacement, PostProcess,
+};
 
+use super::{warn, RunOutput};
+use crate::logging::{self, Level};
+use std::io::{IsTerminal, Write};
 use std::path::PathBuf;
+use std::time::Instant;
+
+#[cfg(feature = "image-output")]
+use std::{io::Read, path::Path, time::Duration};
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum Format {
     Terminal,
+    Unicode,
     Png,
     Svg,
+    /// Plain-text PBM bitmap
+    Pbm,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -37,27 +68,214 @@ impl From<EcLevel> for ErrorCorrectionLevel {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Use {
+    Print,
+    Screen,
+    Industrial,
+}
+
+impl From<Use> for UseCase {
+    fn from(use_case: Use) -> Self {
+        match use_case {
+            Use::Print => UseCase::Print,
+            Use::Screen => UseCase::Screen,
+            Use::Industrial => UseCase::Industrial,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Fit {
+    Center,
+    Tile,
+    Stretch,
+    Scale,
+}
+
+impl From<Fit> for BackgroundFit {
+    fn from(fit: Fit) -> Self {
+        match fit {
+            Fit::Center => BackgroundFit::Center,
+            Fit::Tile => BackgroundFit::Tile,
+            Fit::Stretch => BackgroundFit::Stretch,
+            Fit::Scale => BackgroundFit::Scale,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Palette {
+    Quadrants,
+    Radial,
+}
+
+impl From<Palette> for PaletteMode {
+    fn from(palette: Palette) -> Self {
+        match palette {
+            Palette::Quadrants => PaletteMode::Quadrants,
+            Palette::Radial => PaletteMode::Radial,
+        }
+    }
+}
+
 #[derive(Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(group(clap::ArgGroup::new("multi_output").args(["range", "all_formats"])))]
 pub struct QrCodeArgs {
+    #[command(subcommand)]
+    command: Option<QrCommand>,
+
     /// URL or text content to encode in the QR code
-    content: String,
+    #[arg(required_unless_present_any = ["content_file", "from_clipboard", "capacity"])]
+    content: Option<String>,
+
+    /// Read the content from a file instead (keeps newlines, avoids shell quoting)
+    #[arg(long, conflicts_with = "content")]
+    content_file: Option<PathBuf>,
+
+    /// Encode the text currently on the clipboard (needs the 'clipboard' feature)
+    #[arg(long, conflicts_with_all = ["content", "content_file", "range"])]
+    from_clipboard: bool,
+
+    /// Encode the content file as raw bytes instead of UTF-8 text
+    #[arg(long, requires = "content_file")]
+    bytes_from_file: bool,
+
+    /// Generate one code per number in START-END, replacing {n} in the content (needs --output-dir)
+    #[arg(
+        long,
+        value_name = "START-END",
+        requires = "output_dir",
+        conflicts_with_all = [
+            "content_file",
+            "capacity",
+            "logo",
+            "background",
+            "label",
+            "border",
+            "ai_prompt",
+        ]
+    )]
+    range: Option<String>,
+
+    /// Zero-pad range numbers to this many digits (e.g. 4 gives 0001)
+    #[arg(long, default_value_t = 0, requires = "range")]
+    pad: usize,
+
+    /// Leave range or --all-formats files that already exist alone instead of failing
+    #[arg(long, requires = "multi_output", conflicts_with = "force")]
+    skip_existing: bool,
+
+    /// Overwrite range or --all-formats files that already exist
+    #[arg(long, requires = "multi_output")]
+    force: bool,
+
+    /// Wrap URLs, email addresses and phone numbers in their scheme (https:, mailto:, tel:)
+    #[arg(long, conflicts_with = "bytes_from_file")]
+    auto: bool,
+
+    /// Replace ${VAR} in the content with environment variables ($$ for a literal $)
+    #[arg(long, conflicts_with = "bytes_from_file")]
+    expand_env: bool,
+
+    /// Gzip the content before encoding; only `dev-swiss decode --decompress` can read it back
+    #[arg(long, conflicts_with_all = ["range", "capacity"])]
+    compress: bool,
+
+    /// Append a CRC32 of the content (`#CRC32:` and 8 hex digits) for `dev-swiss decode
+    /// --verify-crc` to check
+    #[arg(long, conflicts_with_all = ["range", "capacity"])]
+    append_crc: bool,
+
+    /// Print how many bytes fit at each version and error correction level
+    #[arg(long)]
+    capacity: bool,
+
+    /// Print the generated code's version, size and remaining capacity instead of the code
+    #[arg(long, conflicts_with = "range")]
+    info: bool,
+
+    /// Print --info as JSON
+    #[arg(long, requires = "info")]
+    json: bool,
 
     /// Output format
     #[arg(short, long, value_enum, default_value = "terminal")]
     format: Format,
 
-    /// Output file path (required for png/svg formats)
+    /// Output file path (required for png/svg formats unless --output-dir is given)
     #[arg(short, long)]
     output: Option<PathBuf>,
 
-    /// Error correction level
-    #[arg(short = 'e', long, value_enum, default_value = "medium")]
-    error_correction: EcLevel,
+    /// Directory to save into, naming the file after the content (e.g. https-example-com.png)
+    #[arg(long, conflicts_with = "output")]
+    output_dir: Option<PathBuf>,
+
+    /// Save PNG, SVG and PDF next to each other, named after -o, and preview in the terminal
+    #[arg(
+        long,
+        requires = "output",
+        conflicts_with_all = ["format", "output_dir", "logo", "background", "label", "border"]
+    )]
+    all_formats: bool,
+
+    /// Error correction level [default: medium, or the --use-case level]
+    #[arg(short = 'e', long, value_enum)]
+    error_correction: Option<EcLevel>,
+
+    /// Pick error correction and PNG quiet zone for where the code is scanned
+    #[arg(long, value_enum)]
+    use_case: Option<Use>,
+
+    /// PNG quiet zone width in modules [default: 4, or the --use-case width]
+    #[arg(long, conflicts_with_all = ["background", "range"])]
+    quiet_zone: Option<u32>,
+
+    /// Force this QR version (1-40, 17 + 4 x version modules wide) instead of the smallest that
+    /// fits
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=40))]
+    qr_version: Option<u8>,
+
+    /// Use the strongest error correction the content allows under --max-version (or --qr-version)
+    #[arg(long)]
+    auto_ec: bool,
+
+    /// Largest version --auto-ec may use (1-40)
+    #[arg(
+        long,
+        requires = "auto_ec",
+        conflicts_with = "qr_version",
+        value_parser = clap::value_parser!(u8).range(1..=40)
+    )]
+    max_version: Option<u8>,
+
+    /// Use a compact Micro QR code (11-17 modules) when the content fits; not all scanners read
+    /// them
+    #[arg(long, conflicts_with = "qr_version")]
+    micro: bool,
 
     /// Scale factor for image output (pixels per module)
     #[arg(short, long, default_value_t = 8)]
     scale: u32,
 
+    /// Save two-color PNGs as 1-bit palette images, a fraction of the usual size
+    #[arg(long)]
+    optimize_png: bool,
+
+    /// Store the content and creation time in PNG text chunks; off by default, since anyone with
+    /// the file can read them
+    #[arg(long, conflicts_with_all = ["range", "all_formats"])]
+    embed_metadata: bool,
+
+    /// With --embed-metadata, store a SHA-256 of the content instead of the content itself
+    #[arg(long, requires = "embed_metadata")]
+    metadata_hash: bool,
+
+    /// Target image width in pixels, including the quiet zone; overrides --scale
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "range")]
+    size: Option<u32>,
+
     /// Invert colors (dark <-> light)
     #[arg(long)]
     invert: bool,
@@ -66,7 +284,54 @@ pub struct QrCodeArgs {
     #[arg(long)]
     no_quiet_zone: bool,
 
-    /// Path to logo image to embed in center
+    /// Print terminal/unicode output exactly as rendered: uncolored, no trailing newline and no
+    /// notes
+    #[arg(long)]
+    raw: bool,
+
+    /// Refuse terminal output wider than this many columns (defaults to $COLUMNS, then the
+    /// terminal's width)
+    #[arg(long, env = "COLUMNS")]
+    max_width: Option<usize>,
+
+    /// Print terminal output even when it is wider than the terminal
+    #[arg(long)]
+    force_terminal_width: bool,
+
+    /// Merge each row's adjacent dark modules into one SVG shape (smaller files)
+    #[arg(long)]
+    merge_modules: bool,
+
+    /// Round SVG module corners, as a fraction of the module size (0.0-0.5)
+    #[arg(long, default_value_t = 0.0)]
+    corner_radius: f32,
+
+    /// Use class="qr-dark"/"qr-light" in SVG output instead of inline colors, for styling with CSS
+    #[arg(long)]
+    svg_classes: bool,
+
+    /// Comma-separated colors for dark SVG modules, assigned by region (e.g. "#1d4ed8,#7c3aed")
+    #[arg(long, value_name = "COLORS")]
+    svg_palette: Option<String>,
+
+    /// How --svg-palette colors are spread over the code
+    #[arg(
+        long,
+        value_enum,
+        default_value = "quadrants",
+        requires = "svg_palette"
+    )]
+    palette_mode: Palette,
+
+    /// Wrap unicode output in a ``` code fence for pasting into chat
+    #[arg(long)]
+    fence: bool,
+
+    /// Use two characters per module in unicode output, for proportional fonts
+    #[arg(long)]
+    double_width: bool,
+
+    /// Path or http(s) URL of logo image to embed in center
     #[arg(long)]
     logo: Option<PathBuf>,
 
@@ -74,10 +339,27 @@ pub struct QrCodeArgs {
     #[arg(long, default_value_t = 20)]
     logo_size: u8,
 
-    /// Path to background image
+    /// Keep the requested error correction with a logo instead of raising it to high
+    #[arg(long, requires = "logo")]
+    no_ec_upgrade: bool,
+
+    /// Put a white rounded backing behind a logo with transparent areas
+    #[arg(long, requires = "logo")]
+    logo_backing: bool,
+
+    /// Path or http(s) URL of background image
     #[arg(long)]
     background: Option<PathBuf>,
 
+    /// How the background is fitted: keep its size (center), or tile, stretch
+    /// or scale it to a canvas sized to the code
+    #[arg(long, value_enum, default_value = "center", requires = "background")]
+    background_fit: Fit,
+
+    /// Enlarge a background too small for the code instead of failing (center fit)
+    #[arg(long, requires = "background")]
+    upscale_background: bool,
+
     /// Dark module color (hex: #000000 or name: black)
     #[arg(long, default_value = "black")]
     dark_color: String,
@@ -86,6 +368,30 @@ pub struct QrCodeArgs {
     #[arg(long, default_value = "white")]
     light_color: String,
 
+    /// PNG quiet zone color, when it should differ from --light-color ("transparent" allowed)
+    #[arg(long, conflicts_with = "background")]
+    quiet_zone_color: Option<String>,
+
+    /// Print terminal output without ANSI colors (also set by NO_COLOR)
+    #[arg(long)]
+    no_color: bool,
+
+    /// Caption drawn centered below the code (PNG only)
+    #[arg(long)]
+    label: Option<String>,
+
+    /// Label font size in pixels
+    #[arg(long, default_value_t = 16.0, requires = "label")]
+    label_size: f32,
+
+    /// Frame the saved image with a border this many pixels wide (PNG only, at most 4096)
+    #[arg(long, value_name = "PX", value_parser = clap::value_parser!(u32).range(..=4096))]
+    border: Option<u32>,
+
+    /// Border color (hex or name)
+    #[arg(long, default_value = "white", requires = "border")]
+    border_color: String,
+
     /// AI prompt for artistic QR generation (requires API key)
     #[arg(long)]
     ai_prompt: Option<String>,
@@ -93,157 +399,1623 @@ pub struct QrCodeArgs {
     /// Stability AI API key (or set STABILITY_API_KEY env var)
     #[arg(long, env = "STABILITY_API_KEY")]
     api_key: Option<String>,
+
+    /// Stability AI model endpoint (core, ultra, sd3)
+    #[arg(long, default_value = "core")]
+    ai_model: String,
+
+    /// Aspect ratio of the AI background (e.g. 1:1, 16:9)
+    #[arg(long, default_value = "1:1")]
+    ai_aspect_ratio: String,
+
+    /// Things the AI background should avoid
+    #[arg(long)]
+    ai_negative_prompt: Option<String>,
+
+    /// Opacity of the QR blended over the AI background (0.0-1.0)
+    #[arg(long, default_value_t = 0.85)]
+    ai_opacity: f32,
+
+    /// Timeout in seconds for each AI request attempt and image download
+    #[arg(long, default_value_t = 60)]
+    ai_timeout: u64,
+
+    /// Retries after an AI request times out or returns a 429 or 5xx error (0-10)
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(..=10))]
+    ai_retries: u32,
+
+    /// Most AI requests to start per minute, spaced evenly (unlimited when not set)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    ai_requests_per_minute: Option<u32>,
+
+    /// Most AI requests to run at once (unlimited when not set)
+    #[arg(long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    ai_max_concurrent: Option<usize>,
 }
 
-pub fn run(args: QrCodeArgs) {
-    // Auto-upgrade error correction when using logo
-    let error_correction = if args.logo.is_some() && matches!(args.error_correction, EcLevel::Low | EcLevel::Medium) {
-        eprintln!("Note: Using high error correction for logo overlay");
-        ErrorCorrectionLevel::High
+#[derive(Subcommand)]
+enum QrCommand {
+    /// Lay out many QR codes on one printable image (e.g. label sheets)
+    Sheet(SheetArgs),
+}
+
+#[derive(Args)]
+struct SheetArgs {
+    /// File with one content per line ("-" for stdin); a tab separates an explicit caption
+    #[arg(long)]
+    from: PathBuf,
+
+    /// Number of columns
+    #[arg(long, default_value_t = 4)]
+    cols: u32,
+
+    /// Output PNG path
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Caption every code with its content (explicit tab-separated captions are always drawn)
+    #[arg(long)]
+    captions: bool,
+
+    /// Caption font size in pixels
+    #[arg(long, default_value_t = 16.0)]
+    caption_size: f32,
+
+    /// Border and gap between codes in pixels
+    #[arg(long, default_value_t = 16)]
+    margin: u32,
+
+    /// Error correction level
+    #[arg(short = 'e', long, value_enum, default_value = "medium")]
+    error_correction: EcLevel,
+
+    /// Scale factor (pixels per module)
+    #[arg(short, long, default_value_t = 8)]
+    scale: u32,
+
+    /// Dark module and caption color
+    #[arg(long, default_value = "black")]
+    dark_color: String,
+
+    /// Light module and background color
+    #[arg(long, default_value = "white")]
+    light_color: String,
+
+    /// Leave the output alone if it already exists instead of failing
+    #[arg(long, conflicts_with = "force")]
+    skip_existing: bool,
+
+    /// Overwrite the output if it already exists
+    #[arg(long)]
+    force: bool,
+}
+
+/// Collision policy from --skip-existing / --force, erroring by default
+#[cfg(feature = "image-output")]
+fn existing_output(skip_existing: bool, force: bool) -> ExistingOutput {
+    if skip_existing {
+        ExistingOutput::Skip
+    } else if force {
+        ExistingOutput::Overwrite
+    } else {
+        ExistingOutput::Error
+    }
+}
+
+#[cfg(feature = "image-output")]
+fn run_sheet(args: SheetArgs, strict: bool) -> Result<RunOutput, String> {
+    let existing = existing_output(args.skip_existing, args.force);
+    if let OutputAction::Skip = output_action(&args.output, existing).map_err(qr_error)? {
+        logging::status(format!(
+            "Skipped {} (already exists)",
+            args.output.display()
+        ));
+        return Ok(RunOutput::default());
+    }
+
+    let text = if args.from.as_os_str() == "-" {
+        let mut buf = String::new();
+        std::io::stdin().read_to_string(&mut buf).map(|_| buf)
     } else {
-        args.error_correction.into()
+        std::fs::read_to_string(&args.from)
+    };
+    let text = text.map_err(|e| format!("Failed to read {}: {}", args.from.display(), e))?;
+
+    let (dark_color, light_color) = module_colors(&args.dark_color, &args.light_color, strict)?;
+
+    let entries = parse_sheet_entries(&text, args.captions);
+    let config = SheetConfig {
+        columns: args.cols,
+        margin: args.margin,
+        caption_size: args.caption_size,
+        error_correction: args.error_correction.into(),
+        image: ImageConfig {
+            scale: args.scale,
+            dark_color,
+            light_color,
+            ..Default::default()
+        },
+    };
+
+    let sheet = render_sheet(&entries, &config).map_err(|e| e.to_string())?;
+
+    save_image(&sheet, &args.output).map_err(|e| e.to_string())?;
+    logging::status(format!(
+        "Saved sheet of {} codes to {}",
+        entries.len(),
+        args.output.display()
+    ));
+    RunOutput::file(&args.output)
+}
+
+#[cfg(feature = "image-output")]
+fn run_range(args: &QrCodeArgs, range: &str, strict: bool) -> Result<RunOutput, String> {
+    let (format, ext) = match args.format {
+        // Terminal is the default format, so a bare --range saves PNGs
+        Format::Terminal | Format::Png => (OutputFormat::Png, "png"),
+        Format::Svg => (OutputFormat::Svg, "svg"),
+        Format::Unicode | Format::Pbm => {
+            return Err("--range saves files; use -f png or -f svg".to_string())
+        }
     };
 
+    let colors = module_colors(&args.dark_color, &args.light_color, strict)?;
+
+    // --output-dir is required alongside --range
+    let dir = args.output_dir.clone().unwrap_or_default();
+    let template = env_content(args.content.clone().unwrap_or_default(), args, strict)?;
+    let items = parse_range(range)
+        .and_then(|range| range_items(&template, range, args.pad, &dir, ext))
+        .map_err(|e| e.to_string())?;
+
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
     let qr_config = QrConfig {
-        content: args.content.clone(),
+        content: String::new(),
+        error_correction: requested_error_correction(args),
+        quiet_zone: !args.no_quiet_zone,
+        invert: args.invert,
+        version: args.qr_version,
+        micro: args.micro,
+    };
+    let image_config = image_config(args, args.scale, colors)?;
+
+    // Skipped files were there before this run, so only the rest count as written
+    let existing = existing_output(args.skip_existing, args.force);
+    let fresh: Vec<bool> = items.iter().map(|item| !item.path.exists()).collect();
+    let summary =
+        render_batch(&items, format, &qr_config, &image_config, existing).map_err(qr_error)?;
+    logging::status(format!(
+        "Saved codes to {}: {} created, {} skipped, {} overwritten",
+        dir.display(),
+        summary.created,
+        summary.skipped,
+        summary.overwritten
+    ));
+
+    let mut output = RunOutput::default();
+    for (item, fresh) in items.iter().zip(fresh) {
+        if fresh || matches!(existing, ExistingOutput::Overwrite) {
+            output.add_file(&item.path)?;
+        }
+    }
+    Ok(output)
+}
+
+/// --all-formats: every file format next to the -o path, then a terminal preview
+#[cfg(feature = "image-output")]
+fn run_all_formats(
+    args: &QrCodeArgs,
+    qr: &QrCode,
+    qr_config: &QrConfig,
+    strict: bool,
+) -> Result<RunOutput, String> {
+    let colors = module_colors(&args.dark_color, &args.light_color, strict)?;
+    let image_config = image_config(args, args.scale, colors)?;
+
+    // --all-formats requires -o
+    let base = args.output.clone().unwrap_or_default();
+    let existing = existing_output(args.skip_existing, args.force);
+    let mut output = RunOutput::default();
+    for format in FILE_OUTPUT_FORMATS {
+        let ext = format.extension().unwrap_or_default();
+        let path = base.with_extension(ext);
+        let saved = output_action(&path, existing).and_then(|action| match action {
+            OutputAction::Skip => Ok(false),
+            OutputAction::Create | OutputAction::Overwrite => {
+                save_qr(qr, format, &image_config, &path).map(|_| true)
+            }
+        });
+        if saved.map_err(qr_error)? {
+            logging::status(format!(
+                "Saved {} to {}",
+                ext.to_uppercase(),
+                path.display()
+            ));
+            output.add_file(&path)?;
+        } else {
+            logging::status(format!("Skipped existing {}", path.display()));
+        }
+    }
+
+    let fits = terminal_columns(args).is_none_or(|columns| {
+        matches!(
+            terminal_fit(qr.width(), qr_config.quiet_zone, columns),
+            TerminalFit::Fits
+        )
+    });
+    if !fits {
+        note(
+            args,
+            "Skipping the terminal preview; the code is wider than the terminal",
+        );
+        return Ok(output);
+    }
+    match terminal_colors(args)? {
+        Some(colors) => print_render(
+            &render_to_terminal_colored(qr, qr_config, &colors),
+            args.raw,
+        )?,
+        None => print_render(&render_to_terminal(qr, qr_config), args.raw)?,
+    }
+    Ok(output)
+}
+
+/// Apply --expand-env, warning about variables that aren't set
+fn env_content(content: String, args: &QrCodeArgs, strict: bool) -> Result<String, String> {
+    if !args.expand_env {
+        return Ok(content);
+    }
+    let expanded = expand_env(&content).map_err(|e| e.to_string())?;
+    for name in unset_env_vars(&content) {
+        warn(
+            format!("${{{}}} is not set; expanding it to nothing", name),
+            strict,
+        )?;
+    }
+    Ok(expanded)
+}
+
+/// Apply --compress, noting the size change and warning when it doesn't help
+fn compress_data(data: Vec<u8>, args: &QrCodeArgs, strict: bool) -> Result<Vec<u8>, String> {
+    if !args.compress {
+        return Ok(data);
+    }
+    let compressed = compress_content(&data);
+    if compressed.len() >= data.len() {
+        warn(
+            format!(
+                "Compressing made the content larger ({} -> {} bytes); it's too short or not repetitive enough",
+                data.len(),
+                compressed.len()
+            ),
+            strict,
+        )?;
+    } else {
+        note(
+            args,
+            format!("Compressed {} -> {} bytes", data.len(), compressed.len()),
+        );
+    }
+    Ok(compressed)
+}
+
+/// The message for a core error, with a hint at the flag that avoids it
+fn qr_error(e: QrError) -> String {
+    match e {
+        QrError::InvalidUtf8(_) => format!("{} (use --bytes-from-file to encode raw bytes)", e),
+        QrError::OutputExists(_) => format!("{} (use --skip-existing or --force)", e),
+        e => e.to_string(),
+    }
+}
+
+/// Parse the dark and light colors, warning when they're too close to scan
+fn module_colors(dark: &str, light: &str, strict: bool) -> Result<([u8; 3], [u8; 3]), String> {
+    let dark_color = parse_color(dark).map_err(|e| e.to_string())?;
+    let light_color = parse_color(light).map_err(|e| e.to_string())?;
+    if let Some(warning) = contrast_warning(dark_color, light_color) {
+        warn(warning, strict)?;
+    }
+    Ok((dark_color, light_color))
+}
+
+/// The image options from `args` at `scale`, with colors from [`module_colors`]
+#[cfg(feature = "image-output")]
+fn image_config(
+    args: &QrCodeArgs,
+    scale: u32,
+    (dark_color, light_color): ([u8; 3], [u8; 3]),
+) -> Result<ImageConfig, String> {
+    Ok(ImageConfig {
+        scale,
+        dark_color,
+        light_color,
+        svg_use_classes: args.svg_classes,
+        svg_palette: svg_palette(args)?,
+        svg_palette_mode: args.palette_mode.into(),
+        quiet_zone_color: quiet_zone_color(args)?,
+        post_process: border(args)?,
+    })
+}
+
+/// --border as a post-processing step on the saved image
+#[cfg(feature = "image-output")]
+fn border(args: &QrCodeArgs) -> Result<Option<PostProcess>, String> {
+    let Some(width) = args.border else {
+        return Ok(None);
+    };
+    let color = parse_color(&args.border_color).map_err(|e| e.to_string())?;
+    Ok(Some(PostProcess::new(move |image| {
+        add_border(&image, width, color)
+    })))
+}
+
+/// The --svg-palette colors, empty when not given
+#[cfg(feature = "image-output")]
+fn svg_palette(args: &QrCodeArgs) -> Result<Vec<[u8; 3]>, String> {
+    let Some(spec) = &args.svg_palette else {
+        return Ok(Vec::new());
+    };
+    spec.split(',')
+        .map(|color| {
+            parse_color(color.trim()).map_err(|e| format!("Invalid --svg-palette color: {}", e))
+        })
+        .collect()
+}
+
+/// Log an informational note, unless --raw asked for none
+fn note(args: &QrCodeArgs, message: impl std::fmt::Display) {
+    if !args.raw {
+        logging::note(message);
+    }
+}
+
+/// Apply --auto, noting on stderr when the content is rewritten
+fn auto_content(content: String, args: &QrCodeArgs) -> String {
+    if !args.auto {
+        return content;
+    }
+    let wrapped = auto_wrap_content(&content);
+    if wrapped != content {
+        note(
+            args,
+            format!(
+                "Detected {}; encoding {}",
+                detect_content_type(&content),
+                wrapped
+            ),
+        );
+    }
+    wrapped
+}
+
+/// Local path for a --logo or --background source
+///
+/// URLs are downloaded into `scratch`, created on first use; the files are
+/// removed when it drops.
+#[cfg(feature = "ai-generation")]
+fn local_image(
+    source: &Path,
+    scratch: &mut Option<ScratchDir>,
+    timeout: Duration,
+) -> Result<PathBuf, String> {
+    // A path that isn't valid UTF-8 can't be a URL
+    let Some(url) = source.to_str().filter(|source| is_remote_source(source)) else {
+        return Ok(source.to_path_buf());
+    };
+    if scratch.is_none() {
+        *scratch = Some(ScratchDir::new().map_err(|e| e.to_string())?);
+    }
+    let dir = scratch
+        .as_ref()
+        .expect("scratch directory was just created");
+    dir.download(url, timeout).map_err(|e| e.to_string())
+}
+
+#[cfg(all(feature = "image-output", not(feature = "ai-generation")))]
+fn local_image(
+    source: &Path,
+    _scratch: &mut Option<()>,
+    _timeout: Duration,
+) -> Result<PathBuf, String> {
+    if source
+        .to_str()
+        .is_some_and(|source| source.starts_with("http://") || source.starts_with("https://"))
+    {
+        return Err("Image URLs not available. Rebuild with 'ai-generation' feature.".to_string());
+    }
+    Ok(source.to_path_buf())
+}
+
+#[cfg(feature = "clipboard")]
+fn read_clipboard() -> Result<String, String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to open the clipboard: {}", e))?;
+    clipboard.get_text().map_err(|e| match e {
+        arboard::Error::ContentNotAvailable => "The clipboard doesn't hold any text".to_string(),
+        e => format!("Failed to read the clipboard: {}", e),
+    })
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn read_clipboard() -> Result<String, String> {
+    Err("Clipboard not available. Rebuild with 'clipboard' feature.".to_string())
+}
+
+/// Reject clipboard text that's blank or looks like binary data rather than
+/// something worth encoding
+fn clipboard_text(text: String) -> Result<String, String> {
+    if text.trim().is_empty() {
+        return Err("The clipboard is empty".to_string());
+    }
+    if text
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+    {
+        return Err("The clipboard holds binary data, not text".to_string());
+    }
+    Ok(text)
+}
+
+/// Encode `data`, first picking the strongest error correction that fits when
+/// --auto-ec is given
+fn encode(data: &[u8], config: &QrConfig, args: &QrCodeArgs) -> Result<QrCode, String> {
+    if !args.auto_ec || data.is_empty() {
+        return generate_qr_bytes(data, config).map_err(|e| e.to_string());
+    }
+    let max_version = args.max_version.or(args.qr_version).unwrap_or(40);
+    let Some(error_correction) = best_error_correction(data, max_version) else {
+        return Err(format!(
+            "Content doesn't fit in version {} or smaller, even with low error correction",
+            max_version
+        ));
+    };
+    note(
+        args,
+        format!(
+            "Using {} error correction",
+            format!("{:?}", error_correction).to_lowercase()
+        ),
+    );
+    let config = QrConfig {
+        error_correction,
+        ..config.clone()
+    };
+    generate_qr_bytes(data, &config).map_err(|e| e.to_string())
+}
+
+/// The -e level, else the --use-case level, else medium
+fn requested_error_correction(args: &QrCodeArgs) -> ErrorCorrectionLevel {
+    match (args.error_correction, args.use_case) {
+        (Some(level), _) => level.into(),
+        (None, Some(use_case)) => ec_for_use_case(use_case.into()).0,
+        (None, None) => ErrorCorrectionLevel::Medium,
+    }
+}
+
+/// The --quiet-zone width, else the --use-case width, else the standard 4 modules
+fn quiet_zone_modules(args: &QrCodeArgs) -> u32 {
+    match (args.quiet_zone, args.use_case) {
+        (Some(modules), _) => modules,
+        (None, Some(use_case)) => ec_for_use_case(use_case.into()).1,
+        (None, None) => QUIET_ZONE_MODULES,
+    }
+}
+
+/// The --quiet-zone-color as RGBA, fully transparent for "transparent"
+#[cfg(feature = "image-output")]
+fn quiet_zone_color(args: &QrCodeArgs) -> Result<Option<[u8; 4]>, String> {
+    let Some(color) = args.quiet_zone_color.as_deref() else {
+        return Ok(None);
+    };
+    if color.trim().eq_ignore_ascii_case("transparent") {
+        return Ok(Some([0, 0, 0, 0]));
+    }
+    let [r, g, b] = parse_color(color).map_err(|e| e.to_string())?;
+    Ok(Some([r, g, b, 255]))
+}
+
+/// Auto-upgrade error correction when using a logo, unless --no-ec-upgrade
+fn effective_error_correction(args: &QrCodeArgs) -> ErrorCorrectionLevel {
+    let upgrade = args.logo.is_some() && !args.no_ec_upgrade;
+    let requested = requested_error_correction(args);
+    if upgrade
+        && matches!(
+            requested,
+            ErrorCorrectionLevel::Low | ErrorCorrectionLevel::Medium
+        )
+    {
+        ErrorCorrectionLevel::High
+    } else {
+        requested
+    }
+}
+
+fn print_capacity_table(content_len: Option<usize>) {
+    let levels = [
+        ErrorCorrectionLevel::Low,
+        ErrorCorrectionLevel::Medium,
+        ErrorCorrectionLevel::Quartile,
+        ErrorCorrectionLevel::High,
+    ];
+
+    println!("Byte capacity (URLs, UTF-8 text):");
+    println!(
+        "{:>7}  {:>7}  {:>6}  {:>6}  {:>8}  {:>6}",
+        "Version", "Modules", "Low", "Medium", "Quartile", "High"
+    );
+    for version in [1u8, 2, 3, 4, 5, 6, 8, 10, 15, 20, 25, 30, 35, 40] {
+        let [l, m, q, h] = levels.map(|ec| capacity(version, ec, Mode::Byte));
+        println!(
+            "{:>7}  {:>7}  {:>6}  {:>6}  {:>8}  {:>6}",
+            version,
+            17 + 4 * version as usize,
+            l,
+            m,
+            q,
+            h
+        );
+    }
+    println!("Numeric-only content fits ~2.4x and uppercase alphanumeric ~1.6x as much.");
+
+    if let Some(len) = content_len {
+        println!("Your content: {} bytes", len);
+    }
+}
+
+/// How a code fits the columns available in the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TerminalFit {
+    Fits,
+    /// Fits once the quiet zone is left out
+    WithoutQuietZone,
+    TooWide,
+}
+
+/// Whether a code `code_width` modules wide, plus its quiet zone when
+/// `quiet_zone` is set, fits in `columns` columns of terminal output
+fn terminal_fit(code_width: usize, quiet_zone: bool, columns: usize) -> TerminalFit {
+    let margin = QUIET_ZONE_MODULES as usize * 2;
+    if code_width + if quiet_zone { margin } else { 0 } <= columns {
+        TerminalFit::Fits
+    } else if quiet_zone && code_width <= columns {
+        TerminalFit::WithoutQuietZone
+    } else {
+        TerminalFit::TooWide
+    }
+}
+
+/// Columns available for terminal output: --max-width or $COLUMNS, else the
+/// terminal's width when stdout is one
+fn terminal_columns(args: &QrCodeArgs) -> Option<usize> {
+    args.max_width.or_else(|| {
+        terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width as usize)
+    })
+}
+
+/// Colors for terminal output, or `None` for plain output when color is
+/// disabled, --raw is given or stdout isn't a terminal
+fn terminal_colors(args: &QrCodeArgs) -> Result<Option<ImageConfig>, String> {
+    let no_color =
+        args.no_color || args.raw || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if no_color || !std::io::stdout().is_terminal() {
+        return Ok(None);
+    }
+    Ok(Some(ImageConfig {
+        dark_color: parse_color(&args.dark_color).map_err(|e| e.to_string())?,
+        light_color: parse_color(&args.light_color).map_err(|e| e.to_string())?,
+        ..Default::default()
+    }))
+}
+
+/// Write a text render, with a trailing newline unless `raw`
+fn write_render(out: &mut impl Write, render: &str, raw: bool) -> std::io::Result<()> {
+    out.write_all(render.as_bytes())?;
+    if !raw {
+        out.write_all(b"\n")?;
+    }
+    out.flush()
+}
+
+fn print_render(render: &str, raw: bool) -> Result<(), String> {
+    write_render(&mut std::io::stdout().lock(), render, raw)
+        .map_err(|e| format!("Failed to write output: {}", e))
+}
+
+/// The version as printed, with an M for Micro QR codes
+fn version_label(info: &QrInfo) -> String {
+    if info.micro {
+        format!("M{}", info.version)
+    } else {
+        info.version.to_string()
+    }
+}
+
+fn print_info(info: &QrInfo) {
+    println!("Version:          {}", version_label(info));
+    println!("Modules:          {}x{}", info.modules, info.modules);
+    println!("Error correction: {}", info.ec);
+    println!("Content:          {} bytes", info.data_bytes);
+    println!("Capacity:         {} bytes", info.capacity_bytes);
+    println!("Remaining:        {} bytes", info.remaining_bytes);
+}
+
+/// Write an encoded PNG to `path`, with text chunks describing the encoded
+/// `data` when --embed-metadata is set
+#[cfg(feature = "image-output")]
+fn save_png(png: Vec<u8>, path: &Path, args: &QrCodeArgs, data: &[u8]) -> Result<(), String> {
+    let png = if args.embed_metadata {
+        let metadata = qr_metadata(data, args.metadata_hash, std::time::SystemTime::now());
+        embed_png_metadata(&png, &metadata).map_err(|e| e.to_string())?
+    } else {
+        png
+    };
+    std::fs::write(path, png).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Error for an option that renders images, in a build without them
+#[cfg(not(feature = "image-output"))]
+fn image_output_required(what: &str) -> String {
+    format!(
+        "{} requires the image-output feature. Rebuild with 'image-output' feature.",
+        what
+    )
+}
+
+pub fn run(args: QrCodeArgs, strict: bool) -> Result<RunOutput, String> {
+    run_with_clipboard(args, strict, read_clipboard)
+}
+
+/// [`run`], taking --from-clipboard text from `clipboard`
+fn run_with_clipboard(
+    args: QrCodeArgs,
+    strict: bool,
+    clipboard: impl FnOnce() -> Result<String, String>,
+) -> Result<RunOutput, String> {
+    #[cfg(feature = "image-output")]
+    if let Some(QrCommand::Sheet(sheet_args)) = args.command {
+        return run_sheet(sheet_args, strict);
+    }
+
+    #[cfg(not(feature = "image-output"))]
+    if let Some(QrCommand::Sheet(_)) = args.command {
+        return Err(image_output_required("qrcode sheet"));
+    }
+
+    if args.capacity {
+        print_capacity_table(args.content.as_ref().map(|c| c.len()));
+        return Ok(RunOutput::default());
+    }
+
+    #[cfg(feature = "image-output")]
+    if let Some(range) = &args.range {
+        return run_range(&args, range, strict);
+    }
+
+    #[cfg(not(feature = "image-output"))]
+    if args.range.is_some() {
+        return Err(image_output_required("--range"));
+    }
+
+    let error_correction = effective_error_correction(&args);
+    if args.logo.is_some() && !args.auto_ec && error_correction != requested_error_correction(&args)
+    {
+        note(&args, "Using high error correction for logo overlay");
+    }
+
+    let mut qr_config = QrConfig {
+        content: String::new(),
         error_correction,
         quiet_zone: !args.no_quiet_zone,
         invert: args.invert,
+        version: args.qr_version,
+        micro: args.micro,
     };
 
-    let qr = match generate_qr(&qr_config) {
-        Ok(qr) => qr,
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+    let data = match (&args.content_file, &args.content) {
+        (Some(path), _) if args.bytes_from_file => std::fs::read(path)
+            .map_err(|e| format!("Failed to read content file {}: {}", path.display(), e))?,
+        (Some(path), _) => {
+            let content = read_content_file(path).map_err(qr_error)?;
+            qr_config.content = auto_content(env_content(content, &args, strict)?, &args);
+            qr_config.content.clone().into_bytes()
         }
+        (None, _) if args.from_clipboard => {
+            let content = clipboard_text(clipboard()?)?;
+            qr_config.content = auto_content(env_content(content, &args, strict)?, &args);
+            qr_config.content.clone().into_bytes()
+        }
+        (None, content) => {
+            qr_config.content = auto_content(
+                env_content(content.clone().unwrap_or_default(), &args, strict)?,
+                &args,
+            );
+            qr_config.content.clone().into_bytes()
+        }
+    };
+    // The checksum covers the content itself, so it goes on before compressing
+    let data = if args.append_crc {
+        append_crc(&data)
+    } else {
+        data
     };
+    let data = compress_data(data, &args, strict)?;
+    let started = Instant::now();
+    let qr = encode(&data, &qr_config, &args).map_err(|e| e.to_string())?;
+    logging::debug(format!(
+        "Encoded {} bytes in {} ms",
+        data.len(),
+        started.elapsed().as_millis()
+    ));
+    if logging::enabled(Level::Info) {
+        let info = qr_info(&qr, &data);
+        logging::info(format!(
+            "Version {} ({}x{} modules), error correction {}, {} of {} bytes used",
+            version_label(&info),
+            info.modules,
+            info.modules,
+            info.ec,
+            info.data_bytes,
+            info.capacity_bytes
+        ));
+    }
+
+    if args.micro && !qr.version().is_micro() {
+        warn(
+            "Content doesn't fit a Micro QR code at this error correction level; using a standard code",
+            strict,
+        )?;
+    }
+
+    if args.info {
+        let info = qr_info(&qr, &data);
+        if args.json {
+            println!("{}", info.to_json());
+        } else {
+            print_info(&info);
+        }
+        return Ok(RunOutput::default());
+    }
+
+    #[cfg(feature = "image-output")]
+    if args.all_formats {
+        return run_all_formats(&args, &qr, &qr_config, strict);
+    }
+
+    #[cfg(not(feature = "image-output"))]
+    if args.all_formats {
+        return Err(image_output_required("--all-formats"));
+    }
+
+    if args.label.is_some() && !matches!(args.format, Format::Png) {
+        return Err("--label is only supported for PNG output".to_string());
+    }
+    if args.border.is_some() && !matches!(args.format, Format::Png) {
+        return Err("--border is only supported for PNG output".to_string());
+    }
+    if args.embed_metadata && !matches!(args.format, Format::Png) {
+        return Err("--embed-metadata is only supported for PNG output".to_string());
+    }
 
     match args.format {
         Format::Terminal => {
-            let output = render_to_terminal(&qr, &qr_config);
-            println!("{}", output);
-        }
-        Format::Png | Format::Svg => {
-            let output_path = match &args.output {
-                Some(path) => path.to_string_lossy().to_string(),
-                None => {
-                    let ext = match args.format {
-                        Format::Png => "png",
-                        Format::Svg => "svg",
-                        _ => unreachable!(),
+            let columns = if args.force_terminal_width {
+                None
+            } else {
+                terminal_columns(&args)
+            };
+            match columns.map(|columns| {
+                (
+                    columns,
+                    terminal_fit(qr.width(), qr_config.quiet_zone, columns),
+                )
+            }) {
+                Some((_, TerminalFit::WithoutQuietZone)) => {
+                    warn(
+                        "Dropping the quiet zone so the code fits the terminal; scan it against a light background",
+                        strict,
+                    )?;
+                    qr_config.quiet_zone = false;
+                }
+                Some((max_width, TerminalFit::TooWide)) => {
+                    let margin = if qr_config.quiet_zone {
+                        QUIET_ZONE_MODULES as usize * 2
+                    } else {
+                        0
+                    };
+                    let e = QrError::TooWide {
+                        width: qr.width() + margin,
+                        max_width,
                     };
-                    eprintln!("Error: Output path required for {} format. Use -o <path>", ext);
-                    std::process::exit(1);
+                    return Err(format!(
+                        "{}; save it with -f png instead, or pass --force-terminal-width",
+                        e
+                    ));
                 }
+                _ => {}
+            }
+            match terminal_colors(&args)? {
+                Some(image_config) => print_render(
+                    &render_to_terminal_colored(&qr, &qr_config, &image_config),
+                    args.raw,
+                )?,
+                None => print_render(&render_to_terminal(&qr, &qr_config), args.raw)?,
+            }
+            Ok(RunOutput::default())
+        }
+        Format::Unicode => {
+            let unicode_config = UnicodeConfig {
+                fence: args.fence,
+                double_width: args.double_width,
+                invert: args.invert,
             };
+            print_render(&render_to_unicode_string(&qr, &unicode_config), args.raw)?;
+            Ok(RunOutput::default())
+        }
+        Format::Png | Format::Svg | Format::Pbm => {
+            let ext = match args.format {
+                Format::Png => "png",
+                Format::Svg => "svg",
+                Format::Pbm => "pbm",
+                _ => unreachable!(),
+            };
+
+            #[cfg(not(feature = "image-output"))]
+            if !matches!(args.format, Format::Pbm) {
+                return Err(image_output_required(&format!(
+                    "{} output",
+                    ext.to_uppercase()
+                )));
+            }
 
-            let dark_color = match parse_color(&args.dark_color) {
-                Ok(c) => c,
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
+            let output_path = match (&args.output, &args.output_dir) {
+                (Some(path), _) => path.clone(),
+                (None, Some(dir)) => {
+                    std::fs::create_dir_all(dir)
+                        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+                    let name_source = match &args.content_file {
+                        Some(path) if qr_config.content.is_empty() => path
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().to_string())
+                            .unwrap_or_default(),
+                        _ => qr_config.content.clone(),
+                    };
+                    auto_output_path(dir, &name_source, ext)
+                }
+                (None, None) => {
+                    return Err(format!(
+                        "Output path required for {} format. Use -o <path> or --output-dir <dir>",
+                        ext
+                    ));
                 }
             };
 
-            let light_color = match parse_color(&args.light_color) {
-                Ok(c) => c,
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
+            #[cfg_attr(not(feature = "image-output"), allow(unused_variables))]
+            let colors = module_colors(&args.dark_color, &args.light_color, strict)?;
+
+            let scale = match args.size {
+                Some(size) => {
+                    let quiet_zone = match args.background {
+                        Some(_) => QUIET_ZONE_MODULES,
+                        None => quiet_zone_modules(&args),
+                    };
+                    let scale = scale_for_target_px(&qr, size, quiet_zone);
+                    let actual = (qr.width() as u32 + quiet_zone * 2) * scale;
+                    if actual != size {
+                        warn(
+                            format!(
+                                "{}px isn't a whole number of pixels per module for this code; using {}px (scale {})",
+                                size, actual, scale
+                            ),
+                            strict,
+                        )?;
+                    }
+                    scale
                 }
+                None => args.scale,
             };
 
-            let image_config = ImageConfig {
-                scale: args.scale,
-                dark_color,
-                light_color,
-            };
+            #[cfg(feature = "image-output")]
+            let image_config = image_config(&args, scale, colors)?;
 
             match args.format {
+                #[cfg(feature = "image-output")]
                 Format::Svg => {
-                    let svg = render_to_svg(&qr, &image_config);
-                    if let Err(e) = std::fs::write(&output_path, svg) {
-                        eprintln!("Error: Failed to write file: {}", e);
-                        std::process::exit(1);
-                    }
-                    println!("Saved SVG to {}", output_path);
+                    let svg = if args.merge_modules || args.corner_radius > 0.0 {
+                        let options = SvgOptions {
+                            merge_runs: args.merge_modules,
+                            corner_radius: args.corner_radius,
+                        };
+                        render_to_svg_with(&qr, &image_config, &options)
+                    } else {
+                        render_to_svg(&qr, &image_config)
+                    };
+                    std::fs::write(&output_path, svg)
+                        .map_err(|e| format!("Failed to write file: {}", e))?;
+                    logging::status(format!("Saved SVG to {}", output_path.display()));
                 }
+                Format::Pbm => {
+                    let pbm_config = PbmConfig {
+                        scale,
+                        quiet_zone: quiet_zone_modules(&args),
+                    };
+                    std::fs::write(&output_path, render_to_pbm(&qr, &pbm_config))
+                        .map_err(|e| format!("Failed to write file: {}", e))?;
+                    logging::status(format!("Saved PBM to {}", output_path.display()));
+                }
+                #[cfg(feature = "image-output")]
                 Format::Png => {
                     #[cfg(feature = "ai-generation")]
                     if let Some(prompt) = &args.ai_prompt {
-                        let api_key = match &args.api_key {
-                            Some(key) => key.clone(),
-                            None => {
-                                eprintln!("Error: API key required for AI generation. Use --api-key or set STABILITY_API_KEY");
-                                std::process::exit(1);
-                            }
+                        let api_key = args.api_key.clone().ok_or(
+                            "API key required for AI generation. Use --api-key or set STABILITY_API_KEY",
+                        )?;
+
+                        let ai_config = AiConfig {
+                            model: args.ai_model.clone(),
+                            aspect_ratio: args.ai_aspect_ratio.clone(),
+                            negative_prompt: args.ai_negative_prompt.clone(),
+                            overlay_opacity: args.ai_opacity,
+                            timeout: std::time::Duration::from_secs(args.ai_timeout),
+                            max_retries: args.ai_retries,
+                            ..Default::default()
                         };
 
-                        match generate_ai_qr(&qr, prompt, &api_key, &image_config) {
-                            Ok(image) => {
-                                if let Err(e) = save_image(&image, &output_path) {
-                                    eprintln!("Error: {}", e);
-                                    std::process::exit(1);
-                                }
-                                println!("Saved AI-styled QR to {}", output_path);
-                            }
-                            Err(e) => {
-                                eprintln!("Error: {}", e);
-                                std::process::exit(1);
-                            }
+                        let provider = RateLimitedProvider::new(
+                            StabilityProvider::new(api_key),
+                            RateLimit {
+                                max_concurrent: args.ai_max_concurrent,
+                                requests_per_minute: args.ai_requests_per_minute,
+                            },
+                        );
+
+                        let result =
+                            generate_ai_qr(&qr, prompt, &provider, &image_config, &ai_config)
+                                .map_err(|e| e.to_string())?;
+                        for warning in &result.warnings {
+                            warn(warning.as_str(), strict)?;
                         }
-                        return;
+                        let mut image = result.image;
+                        if let Some(label) = &args.label {
+                            image = add_label(&image, label, args.label_size, &image_config);
+                        }
+                        let image =
+                            finish_image(image, &image_config).map_err(|e| e.to_string())?;
+                        let png = if args.optimize_png {
+                            encode_png_optimized(&image)
+                        } else {
+                            encode_png(&image)
+                        };
+                        save_png(png.map_err(|e| e.to_string())?, &output_path, &args, &data)?;
+                        logging::status(format!("Saved AI-styled QR to {}", output_path.display()));
+                        return RunOutput::file(&output_path);
                     }
 
                     #[cfg(not(feature = "ai-generation"))]
                     if args.ai_prompt.is_some() {
-                        eprintln!("Error: AI generation not available. Rebuild with 'ai-generation' feature.");
-                        std::process::exit(1);
-                    }
-
-                    // Handle background image
-                    if let Some(bg_path) = &args.background {
-                        match overlay_on_background(&qr, &bg_path.to_string_lossy(), &image_config) {
-                            Ok(image) => {
-                                if let Err(e) = save_image(&image, &output_path) {
-                                    eprintln!("Error: {}", e);
-                                    std::process::exit(1);
-                                }
-                                println!("Saved QR with background to {}", output_path);
-                            }
-                            Err(e) => {
-                                eprintln!("Error: {}", e);
-                                std::process::exit(1);
-                            }
-                        }
-                        return;
+                        return Err(
+                            "AI generation not available. Rebuild with 'ai-generation' feature."
+                                .to_string(),
+                        );
                     }
 
-                    // Standard image rendering
-                    let mut image = render_to_image(&qr, &image_config);
+                    // Downloaded logos and backgrounds live here until the image is saved
+                    let mut scratch = None;
+                    let download_timeout = Duration::from_secs(args.ai_timeout);
 
-                    // Handle logo overlay
-                    if let Some(logo_path) = &args.logo {
-                        let logo_config = LogoConfig {
-                            path: logo_path.to_string_lossy().to_string(),
+                    let background_config = match &args.background {
+                        Some(source) => Some(BackgroundConfig {
+                            path: local_image(source, &mut scratch, download_timeout)?,
+                            fit: args.background_fit.into(),
+                            upscale: args.upscale_background,
+                        }),
+                        None => None,
+                    };
+                    let logo_config = match &args.logo {
+                        Some(source) => Some(LogoConfig {
+                            path: local_image(source, &mut scratch, download_timeout)?,
                             size_percent: args.logo_size,
-                        };
+                            backing: args.logo_backing,
+                        }),
+                        None => None,
+                    };
 
-                        if let Err(e) = overlay_logo(&mut image, &logo_config) {
-                            eprintln!("Error: {}", e);
-                            std::process::exit(1);
+                    // The background goes down first, then the logo on the placed code.
+                    // Where the code ended up is kept for the scannability check.
+                    let composed = match (&background_config, &logo_config) {
+                        (Some(background), Some(logo)) => {
+                            overlay_on_background_with_logo(&qr, background, logo, &image_config)
+                                .map(|(image, placement)| (image, Some(placement)))
+                        }
+                        (Some(background), None) => {
+                            overlay_on_background(&qr, background, &image_config)
+                                .map(|image| (image, None))
+                        }
+                        (None, Some(logo)) => {
+                            let mut image = render_to_image(&qr, &image_config);
+                            overlay_logo(&mut image, logo).map(|warnings| {
+                                let placement = LogoPlacement {
+                                    origin: (0, 0),
+                                    scale: image_config.scale,
+                                    warnings,
+                                };
+                                (image, Some(placement))
+                            })
                         }
+                        (None, None) => Ok((render_to_image(&qr, &image_config), None)),
+                    };
+
+                    let (mut image, logo_placement) = composed.map_err(|e| e.to_string())?;
+
+                    if let Some(placement) = logo_placement {
+                        for warning in placement.warnings {
+                            warn(warning, strict)?;
+                        }
+                        if !is_likely_scannable(&image, &qr, placement.origin, placement.scale) {
+                            warn(
+                                "The logo covers more than the error correction can recover; the code may not scan",
+                                strict,
+                            )?;
+                        }
+                    }
+
+                    // A background already gives the code its margin
+                    if args.background.is_none() {
+                        image = with_quiet_zone(&image, quiet_zone_modules(&args), &image_config);
+                    }
+
+                    if let Some(label) = &args.label {
+                        image = add_label(&image, label, args.label_size, &image_config);
                     }
+                    let image = finish_image(image, &image_config).map_err(|e| e.to_string())?;
 
-                    if let Err(e) = save_image(&image, &output_path) {
-                        eprintln!("Error: {}", e);
-                        std::process::exit(1);
+                    let png = if args.optimize_png {
+                        encode_png_optimized(&image)
+                    } else {
+                        encode_png(&image)
+                    };
+                    save_png(png.map_err(|e| e.to_string())?, &output_path, &args, &data)?;
+                    if args.background.is_some() {
+                        logging::status(format!(
+                            "Saved QR with background to {}",
+                            output_path.display()
+                        ));
+                    } else {
+                        logging::status(format!("Saved PNG to {}", output_path.display()));
                     }
-                    println!("Saved PNG to {}", output_path);
                 }
                 _ => unreachable!(),
             }
+            RunOutput::file(&output_path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: QrCodeArgs,
+    }
+
+    fn parse(argv: &[&str]) -> QrCodeArgs {
+        Cli::try_parse_from([&["qrcode"], argv].concat())
+            .unwrap()
+            .args
+    }
+
+    #[cfg(feature = "image-output")]
+    #[test]
+    fn test_low_contrast_fails_only_when_strict() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("qr.png");
+        let argv = [
+            "hi",
+            "-f",
+            "png",
+            "-o",
+            path.to_str().unwrap(),
+            "--dark-color",
+            "#777777",
+            "--light-color",
+            "#888888",
+        ];
+
+        let err = run(parse(&argv), true).unwrap_err();
+        assert!(
+            err.contains("contrast ratio") && err.ends_with("(--strict)"),
+            "{}",
+            err
+        );
+        assert!(!path.exists());
+
+        let output = run(parse(&argv), false).unwrap();
+        assert_eq!(output.files.len(), 1);
+        assert_eq!(output.files[0].path, path);
+
+        let default = parse(&["hi"]);
+        let dark = parse_color(&default.dark_color).unwrap();
+        let light = parse_color(&default.light_color).unwrap();
+        assert_eq!(contrast_warning(dark, light), None);
+    }
+
+    #[test]
+    fn test_raw_output_has_no_trailing_newline() {
+        let qr = generate_qr_bytes(b"hi", &QrConfig::default()).unwrap();
+        let render = render_to_unicode_string(&qr, &UnicodeConfig::default());
+
+        let mut plain = Vec::new();
+        write_render(&mut plain, &render, false).unwrap();
+        let mut raw = Vec::new();
+        write_render(&mut raw, &render, true).unwrap();
+
+        assert_eq!(raw, render.as_bytes());
+        assert_eq!(plain.len(), raw.len() + 1);
+        assert!(plain.ends_with(b"\n") && !raw.ends_with(b"\n"));
+
+        assert!(parse(&["hi", "--raw"]).raw);
+    }
+
+    #[test]
+    fn test_terminal_fit() {
+        // Version 1 is 21 modules, 29 columns with the quiet zone
+        assert_eq!(terminal_fit(21, true, 80), TerminalFit::Fits);
+        assert_eq!(terminal_fit(21, true, 29), TerminalFit::Fits);
+        assert_eq!(terminal_fit(21, true, 28), TerminalFit::WithoutQuietZone);
+        assert_eq!(terminal_fit(21, true, 21), TerminalFit::WithoutQuietZone);
+        assert_eq!(terminal_fit(21, true, 20), TerminalFit::TooWide);
+
+        // Without a quiet zone there is nothing to drop
+        assert_eq!(terminal_fit(21, false, 21), TerminalFit::Fits);
+        assert_eq!(terminal_fit(21, false, 20), TerminalFit::TooWide);
+
+        // Version 40 doesn't fit a standard terminal either way
+        assert_eq!(terminal_fit(177, true, 120), TerminalFit::TooWide);
+    }
+
+    #[test]
+    fn test_logo_upgrades_error_correction() {
+        let args = parse(&["x", "--logo", "logo.png"]);
+        assert_eq!(
+            effective_error_correction(&args),
+            ErrorCorrectionLevel::High
+        );
+
+        let args = parse(&["x"]);
+        assert_eq!(
+            effective_error_correction(&args),
+            ErrorCorrectionLevel::Medium
+        );
+    }
+
+    #[test]
+    fn test_use_case_defaults_and_overrides() {
+        let args = parse(&["x", "--use-case", "industrial"]);
+        assert_eq!(
+            requested_error_correction(&args),
+            ErrorCorrectionLevel::High
+        );
+        assert_eq!(quiet_zone_modules(&args), 8);
+
+        let args = parse(&["x", "--use-case", "screen"]);
+        assert_eq!(requested_error_correction(&args), ErrorCorrectionLevel::Low);
+        assert_eq!(quiet_zone_modules(&args), 2);
+
+        let args = parse(&["x", "--use-case", "print"]);
+        assert_eq!(
+            requested_error_correction(&args),
+            ErrorCorrectionLevel::Quartile
+        );
+        assert_eq!(quiet_zone_modules(&args), 4);
+
+        // Explicit flags win over the use case
+        let args = parse(&[
+            "x",
+            "--use-case",
+            "screen",
+            "-e",
+            "high",
+            "--quiet-zone",
+            "6",
+        ]);
+        assert_eq!(
+            requested_error_correction(&args),
+            ErrorCorrectionLevel::High
+        );
+        assert_eq!(quiet_zone_modules(&args), 6);
+
+        let args = parse(&["x"]);
+        assert_eq!(
+            requested_error_correction(&args),
+            ErrorCorrectionLevel::Medium
+        );
+        assert_eq!(quiet_zone_modules(&args), QUIET_ZONE_MODULES);
+    }
+
+    #[test]
+    fn test_no_ec_upgrade_keeps_requested_level() {
+        let args = parse(&["x", "--logo", "logo.png", "--no-ec-upgrade"]);
+        assert_eq!(
+            effective_error_correction(&args),
+            ErrorCorrectionLevel::Medium
+        );
+
+        let args = parse(&["x", "--logo", "logo.png", "--no-ec-upgrade", "-e", "low"]);
+        assert_eq!(effective_error_correction(&args), ErrorCorrectionLevel::Low);
+
+        assert!(Cli::try_parse_from(["qrcode", "x", "--no-ec-upgrade"]).is_err());
+    }
+
+    #[test]
+    fn test_content_file_utf8_hint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blob.bin");
+        std::fs::write(&path, [0xff, 0xfe]).unwrap();
+        let err = run(
+            parse(&["--content-file", path.to_str().unwrap(), "-f", "unicode"]),
+            false,
+        )
+        .unwrap_err();
+        assert!(
+            err.starts_with("Content file is not valid UTF-8")
+                && err.ends_with("(use --bytes-from-file to encode raw bytes)"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_range_rejects_single_image_options() {
+        let range = ["{n}", "--range", "1-2", "--output-dir", "out"];
+        for option in [
+            ["--logo", "logo.png"],
+            ["--background", "bg.png"],
+            ["--label", "Hi"],
+            ["--border", "8"],
+            ["--ai-prompt", "neon"],
+        ] {
+            let argv = [&["qrcode"][..], &range, &option].concat();
+            assert!(Cli::try_parse_from(argv).is_err(), "{:?}", option);
+        }
+    }
+
+    #[test]
+    fn test_ai_rate_limit_flags() {
+        let args = parse(&["hi"]);
+        assert_eq!(
+            (args.ai_requests_per_minute, args.ai_max_concurrent),
+            (None, None)
+        );
+        let args = parse(&[
+            "hi",
+            "--ai-requests-per-minute",
+            "30",
+            "--ai-max-concurrent",
+            "2",
+        ]);
+        assert_eq!(
+            (args.ai_requests_per_minute, args.ai_max_concurrent),
+            (Some(30), Some(2))
+        );
+        for zero in ["--ai-requests-per-minute", "--ai-max-concurrent"] {
+            assert!(Cli::try_parse_from(["qrcode", "hi", zero, "0"]).is_err());
+        }
+    }
+
+    #[cfg(feature = "image-output")]
+    #[test]
+    fn test_run_reports_written_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("code.png");
+        let output = run(
+            parse(&["hi", "-f", "png", "-o", path.to_str().unwrap()]),
+            false,
+        )
+        .unwrap();
+        assert_eq!(output.files.len(), 1);
+        assert_eq!(output.files[0].path, path);
+        assert_eq!(
+            output.files[0].bytes,
+            std::fs::metadata(&path).unwrap().len()
+        );
+
+        // Printing to the terminal writes nothing
+        let output = run(parse(&["hi", "-f", "unicode", "--raw"]), false).unwrap();
+        assert_eq!(output, RunOutput::default());
+
+        let err = run(parse(&["hi", "-f", "png"]), false).unwrap_err();
+        assert!(err.starts_with("Output path required"), "{}", err);
+    }
+
+    #[cfg(feature = "image-output")]
+    #[test]
+    fn test_border_frames_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("plain.png");
+        let framed = dir.path().join("framed.png");
+        run(
+            parse(&["hi", "-f", "png", "-o", plain.to_str().unwrap()]),
+            false,
+        )
+        .unwrap();
+        let argv = [
+            "hi",
+            "-f",
+            "png",
+            "-o",
+            framed.to_str().unwrap(),
+            "--border",
+            "10",
+            "--border-color",
+            "red",
+        ];
+        run(parse(&argv), false).unwrap();
+
+        // Width and height from the PNG's IHDR chunk
+        let size = |path: &Path| {
+            let png = std::fs::read(path).unwrap();
+            let be = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+            (be(16), be(20))
+        };
+        let ((w, h), framed) = (size(&plain), size(&framed));
+        assert_eq!(framed, (w + 20, h + 20));
+
+        let err = run(
+            parse(&["hi", "-f", "svg", "-o", "x.svg", "--border", "10"]),
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(err, "--border is only supported for PNG output");
+    }
+
+    #[cfg(feature = "image-output")]
+    #[test]
+    fn test_all_formats_writes_each_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("code");
+        let base = base.to_str().unwrap();
+        let output = run(parse(&["hi", "--all-formats", "-o", base, "--raw"]), false).unwrap();
+        let written: Vec<_> = output.files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(
+            written,
+            ["png", "svg", "pdf"].map(|ext| dir.path().join("code").with_extension(ext))
+        );
+        for file in &output.files {
+            assert_eq!(file.bytes, std::fs::metadata(&file.path).unwrap().len());
+            assert!(file.bytes > 0);
         }
+
+        let png = std::fs::read(dir.path().join("code.png")).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let svg = std::fs::read_to_string(dir.path().join("code.svg")).unwrap();
+        assert!(svg.contains("<svg"));
+        let pdf = std::fs::read(dir.path().join("code.pdf")).unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+
+        // A second run refuses to overwrite, skips what is there, or replaces it with --force
+        std::fs::write(dir.path().join("code.svg"), "keep").unwrap();
+        let err = run(parse(&["hi", "--all-formats", "-o", base, "--raw"]), false).unwrap_err();
+        assert!(
+            err.starts_with("Output file already exists")
+                && err.ends_with("(use --skip-existing or --force)"),
+            "{}",
+            err
+        );
+        let output = run(
+            parse(&[
+                "hi",
+                "--all-formats",
+                "-o",
+                base,
+                "--raw",
+                "--skip-existing",
+            ]),
+            false,
+        )
+        .unwrap();
+        assert!(output.files.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("code.svg")).unwrap(),
+            "keep"
+        );
+        let output = run(
+            parse(&["hi", "--all-formats", "-o", base, "--raw", "--force"]),
+            false,
+        )
+        .unwrap();
+        assert_eq!(output.files.len(), 3);
+        assert!(std::fs::read_to_string(dir.path().join("code.svg"))
+            .unwrap()
+            .contains("<svg"));
+
+        assert!(Cli::try_parse_from(["qrcode", "hi", "--all-formats"]).is_err());
+        assert!(Cli::try_parse_from(["qrcode", "hi", "-o", "x.png", "--force"]).is_err());
+    }
+
+    #[cfg(feature = "image-output")]
+    #[test]
+    fn test_from_clipboard_encodes_clipboard_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let typed = dir.path().join("typed.svg");
+        let copied = dir.path().join("copied.svg");
+        let url = "https://example.com/copied";
+        run(
+            parse(&[url, "-f", "svg", "-o", typed.to_str().unwrap()]),
+            false,
+        )
+        .unwrap();
+        let args = parse(&[
+            "--from-clipboard",
+            "-f",
+            "svg",
+            "-o",
+            copied.to_str().unwrap(),
+        ]);
+        run_with_clipboard(args, false, || Ok(url.to_string())).unwrap();
+        assert_eq!(
+            std::fs::read(&copied).unwrap(),
+            std::fs::read(&typed).unwrap()
+        );
+
+        for (clipboard, expected) in [
+            (" \n", "The clipboard is empty"),
+            ("PK\u{3}\u{4}\0", "binary data"),
+        ] {
+            let args = parse(&[
+                "--from-clipboard",
+                "-f",
+                "svg",
+                "-o",
+                copied.to_str().unwrap(),
+            ]);
+            let err = run_with_clipboard(args, false, || Ok(clipboard.to_string())).unwrap_err();
+            assert!(err.contains(expected), "{}", err);
+        }
+        assert!(Cli::try_parse_from(["qrcode", "--from-clipboard", "hi"]).is_err());
+    }
+
+    #[cfg(feature = "image-output")]
+    #[test]
+    fn test_embed_metadata() {
+        use dev_swiss_core::{read_png_metadata, METADATA_CONTENT_HASH_KEY, METADATA_CONTENT_KEY};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("code.png");
+        let output = path.to_str().unwrap();
+
+        run(parse(&["secret", "-f", "png", "-o", output]), false).unwrap();
+        assert!(read_png_metadata(&path).unwrap().is_empty());
+
+        run(
+            parse(&[
+                "secret",
+                "-f",
+                "png",
+                "-o",
+                output,
+                "--embed-metadata",
+                "--optimize-png",
+            ]),
+            false,
+        )
+        .unwrap();
+        let metadata = read_png_metadata(&path).unwrap();
+        assert!(metadata.contains(&(METADATA_CONTENT_KEY.to_string(), "secret".to_string())));
+        assert!(metadata.iter().any(|(key, _)| key == "Creation Time"));
+
+        run(
+            parse(&[
+                "secret",
+                "-f",
+                "png",
+                "-o",
+                output,
+                "--embed-metadata",
+                "--metadata-hash",
+            ]),
+            false,
+        )
+        .unwrap();
+        let metadata = read_png_metadata(&path).unwrap();
+        assert!(metadata
+            .iter()
+            .any(|(key, _)| key == METADATA_CONTENT_HASH_KEY));
+        assert!(!metadata.iter().any(|(_, text)| text == "secret"));
+
+        let svg = dir.path().join("code.svg");
+        let err = run(
+            parse(&[
+                "hi",
+                "-f",
+                "svg",
+                "-o",
+                svg.to_str().unwrap(),
+                "--embed-metadata",
+            ]),
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(err, "--embed-metadata is only supported for PNG output");
+        assert!(Cli::try_parse_from(["qrcode", "hi", "--metadata-hash"]).is_err());
+    }
+
+    #[cfg(not(feature = "image-output"))]
+    #[test]
+    fn test_image_formats_require_image_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("code.png");
+        let err = run(
+            parse(&["hi", "-f", "png", "-o", png.to_str().unwrap()]),
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            "PNG output requires the image-output feature. Rebuild with 'image-output' feature."
+        );
+        assert!(!png.exists());
+
+        let svg = dir.path().join("code.svg");
+        let err = run(
+            parse(&["hi", "-f", "svg", "-o", svg.to_str().unwrap()]),
+            false,
+        )
+        .unwrap_err();
+        assert!(err.starts_with("SVG output requires"), "{}", err);
+        let out_dir = dir.path().to_str().unwrap();
+        let err = run(
+            parse(&["{n}", "--range", "1-2", "--output-dir", out_dir]),
+            false,
+        )
+        .unwrap_err();
+        assert!(err.starts_with("--range requires"), "{}", err);
+
+        // Formats drawn without the image crate still work
+        let pbm = dir.path().join("code.pbm");
+        run(
+            parse(&["hi", "-f", "pbm", "-o", pbm.to_str().unwrap()]),
+            false,
+        )
+        .unwrap();
+        assert!(std::fs::read_to_string(&pbm).unwrap().starts_with("P1"));
+    }
+
+    #[cfg(feature = "image-output")]
+    #[test]
+    fn test_log_lines_follow_verbosity() {
+        use crate::logging::{capture, Verbosity};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("code.svg");
+        let lines_at = |verbosity| {
+            let args = parse(&["hi", "-f", "svg", "-o", path.to_str().unwrap()]);
+            let (result, lines) = capture(verbosity, || run(args, false));
+            result.unwrap();
+            lines
+        };
+
+        assert!(lines_at(Verbosity::Quiet).is_empty());
+        let saved = format!("Saved SVG to {}", path.display());
+        assert_eq!(lines_at(Verbosity::Normal), [saved.as_str()]);
+        let verbose = lines_at(Verbosity::Verbose);
+        assert_eq!(verbose.len(), 2);
+        assert!(
+            verbose[0].starts_with("Version 1 (21x21 modules), error correction"),
+            "{}",
+            verbose[0]
+        );
+        assert_eq!(verbose[1], saved);
+        let debug = lines_at(Verbosity::Debug);
+        assert!(
+            debug[0].starts_with("Debug: Encoded 2 bytes in "),
+            "{}",
+            debug[0]
+        );
+        assert_eq!(debug.len(), 3);
     }
 }
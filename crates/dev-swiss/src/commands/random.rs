@@ -0,0 +1,104 @@
+use super::RunOutput;
+use clap::{Args, ValueEnum};
+use dev_swiss_core::{random_bytes, random_string, RandomEncoding};
+use std::io::Write;
+
+/// Raw output is generated and written in chunks of this size
+const RAW_CHUNK: usize = 64 * 1024;
+
+/// Bytes encoded per chunk for hex and base64; a multiple of 3, so base64
+/// chunks join without padding in between
+const ENCODED_CHUNK: usize = 48 * 1024;
+
+#[derive(Args)]
+pub struct RandomArgs {
+    /// Number of random bytes
+    #[arg(short, long, default_value_t = 32)]
+    bytes: usize,
+
+    /// Output encoding
+    #[arg(short, long, value_enum, default_value = "hex")]
+    format: Format,
+
+    /// Number of values to generate (one per line; ignored for raw output)
+    #[arg(short = 'n', long, default_value_t = 1)]
+    count: usize,
+}
+
+#[derive(Clone, ValueEnum)]
+enum Format {
+    Hex,
+    Base64,
+    /// Unencoded bytes, written straight to stdout
+    Raw,
+}
+
+fn write_raw(len: usize) -> std::io::Result<()> {
+    // Generate and write one chunk at a time so large outputs stream instead
+    // of sitting in memory. Stdout stays line-buffered, but writes this large
+    // go straight through its small buffer
+    let mut stdout = std::io::stdout().lock();
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(RAW_CHUNK);
+        stdout.write_all(&random_bytes(chunk))?;
+        remaining -= chunk;
+    }
+    stdout.flush()
+}
+
+/// Write one line of `len` random bytes in `encoding`, a chunk at a time
+fn write_encoded(
+    out: &mut impl Write,
+    len: usize,
+    encoding: RandomEncoding,
+) -> std::io::Result<()> {
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(ENCODED_CHUNK);
+        out.write_all(random_string(chunk, encoding).as_bytes())?;
+        remaining -= chunk;
+    }
+    writeln!(out)
+}
+
+pub fn run(args: RandomArgs) -> Result<RunOutput, String> {
+    let encoding = match args.format {
+        Format::Hex => RandomEncoding::Hex,
+        Format::Base64 => RandomEncoding::Base64,
+        Format::Raw => {
+            write_raw(args.bytes).map_err(|e| e.to_string())?;
+            return Ok(RunOutput::default());
+        }
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    for _ in 0..args.count {
+        write_encoded(&mut stdout, args.bytes, encoding).map_err(|e| e.to_string())?;
+    }
+    Ok(RunOutput::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dev_swiss_core::{decode_base64, from_hex, Base64Config};
+
+    #[test]
+    fn test_encoded_output_spans_chunks() {
+        let len = ENCODED_CHUNK * 2 + 5;
+
+        let mut hex = Vec::new();
+        write_encoded(&mut hex, len, RandomEncoding::Hex).unwrap();
+        let hex = String::from_utf8(hex).unwrap();
+        assert_eq!(from_hex(hex.trim_end()).unwrap().len(), len);
+
+        // Padding only at the very end, so the whole line decodes at once
+        let mut b64 = Vec::new();
+        write_encoded(&mut b64, len, RandomEncoding::Base64).unwrap();
+        let b64 = String::from_utf8(b64).unwrap();
+        assert!(b64.ends_with('\n'));
+        let decoded = decode_base64(b64.trim_end(), &Base64Config::default()).unwrap();
+        assert_eq!(decoded.len(), len);
+    }
+}
@@ -0,0 +1,41 @@
+use super::RunOutput;
+use clap::Args;
+use dev_swiss_core::{slugify, SlugOptions};
+use std::io::BufRead;
+
+#[derive(Args)]
+pub struct SlugArgs {
+    /// Text to slugify ("-" reads one value per line from stdin)
+    text: String,
+
+    /// Maximum slug length
+    #[arg(short, long)]
+    max_len: Option<usize>,
+
+    /// Separator between words
+    #[arg(short, long, default_value = "-")]
+    separator: String,
+
+    /// Drop non-ASCII characters instead of transliterating them
+    #[arg(long)]
+    no_transliterate: bool,
+}
+
+pub fn run(args: SlugArgs) -> Result<RunOutput, String> {
+    let options = SlugOptions {
+        max_len: args.max_len,
+        separator: args.separator,
+        transliterate: !args.no_transliterate,
+    };
+
+    if args.text != "-" {
+        println!("{}", slugify(&args.text, &options));
+        return Ok(RunOutput::default());
+    }
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        println!("{}", slugify(&line, &options));
+    }
+    Ok(RunOutput::default())
+}
@@ -0,0 +1,94 @@
+use super::RunOutput;
+use clap::Args;
+use dev_swiss_core::{
+    convert_time, epoch_to_rfc3339, parse_time_zone, EpochUnit, TimeConfig, TimeDirection,
+    TimeZoneSpec,
+};
+use std::io::BufRead;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Args)]
+pub struct TimeArgs {
+    /// Epoch value or RFC 3339 date-time ("-" reads one value per line from
+    /// stdin; omit to show the current time)
+    value: Option<String>,
+
+    /// Treat the input as an epoch and print RFC 3339
+    #[arg(long, conflicts_with = "to_epoch")]
+    from_epoch: bool,
+
+    /// Treat the input as a date-time and print an epoch
+    #[arg(long)]
+    to_epoch: bool,
+
+    /// Epochs are in milliseconds (input is auto-detected otherwise; output
+    /// defaults to seconds)
+    #[arg(long)]
+    millis: bool,
+
+    /// Display in UTC (default)
+    #[arg(long, conflicts_with_all = ["local", "tz"])]
+    utc: bool,
+
+    /// Display in the system's local time zone
+    #[arg(long, conflicts_with = "tz")]
+    local: bool,
+
+    /// Time zone: an offset like +09:00 or an IANA name like Europe/Berlin
+    #[arg(long)]
+    tz: Option<String>,
+}
+
+fn print_converted(value: &str, config: &TimeConfig) -> Result<(), String> {
+    let converted = convert_time(value, config).map_err(|e| e.to_string())?;
+    println!("{}", converted);
+    Ok(())
+}
+
+pub fn run(args: TimeArgs) -> Result<RunOutput, String> {
+    let zone = if let Some(tz) = &args.tz {
+        parse_time_zone(tz).map_err(|e| e.to_string())?
+    } else if args.local {
+        TimeZoneSpec::Local
+    } else {
+        TimeZoneSpec::Utc
+    };
+
+    let config = TimeConfig {
+        direction: if args.from_epoch {
+            TimeDirection::FromEpoch
+        } else if args.to_epoch {
+            TimeDirection::ToEpoch
+        } else {
+            TimeDirection::Auto
+        },
+        unit: args.millis.then_some(EpochUnit::Milliseconds),
+        zone,
+    };
+
+    match args.value.as_deref() {
+        None => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            let millis = now.as_millis() as i64;
+            println!("Epoch (s):  {}", now.as_secs());
+            println!("Epoch (ms): {}", millis);
+            if let Ok(rfc3339) =
+                epoch_to_rfc3339(millis, Some(EpochUnit::Milliseconds), &config.zone)
+            {
+                println!("RFC 3339:   {}", rfc3339);
+            }
+        }
+        Some("-") => {
+            for line in std::io::stdin().lock().lines() {
+                let line = line.map_err(|e| e.to_string())?;
+                if !line.trim().is_empty() {
+                    print_converted(&line, &config)?;
+                }
+            }
+        }
+        Some(value) => print_converted(value, &config)?,
+    }
+    Ok(RunOutput::default())
+}
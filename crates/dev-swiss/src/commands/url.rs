@@ -0,0 +1,55 @@
+use super::RunOutput;
+use clap::Args;
+use dev_swiss_core::{url_decode, url_encode, UrlEncodeMode};
+use std::io::Read;
+
+#[derive(Args)]
+pub struct UrlArgs {
+    /// Value to encode or decode (reads stdin when omitted or "-")
+    value: Option<String>,
+
+    /// Percent-encode the value (default)
+    #[arg(short, long)]
+    encode: bool,
+
+    /// Decode percent-escapes
+    #[arg(short, long, conflicts_with = "encode")]
+    decode: bool,
+
+    /// Only touch the query string; decoding also turns + into a space
+    #[arg(short, long, conflicts_with = "component")]
+    query: bool,
+
+    /// Treat the value as a single component and escape reserved characters
+    /// such as & = / ?
+    #[arg(short, long)]
+    component: bool,
+}
+
+pub fn run(args: UrlArgs) -> Result<RunOutput, String> {
+    let value = match args.value {
+        Some(value) if value != "-" => value,
+        _ => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .map_err(|e| e.to_string())?;
+            buf.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+
+    let mode = if args.component {
+        UrlEncodeMode::Component
+    } else if args.query {
+        UrlEncodeMode::Query
+    } else {
+        UrlEncodeMode::Whole
+    };
+
+    if args.decode {
+        println!("{}", url_decode(&value, mode).map_err(|e| e.to_string())?);
+    } else {
+        println!("{}", url_encode(&value, mode));
+    }
+    Ok(RunOutput::default())
+}
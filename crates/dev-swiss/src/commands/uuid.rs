@@ -0,0 +1,69 @@
+use super::RunOutput;
+use clap::{Args, ValueEnum};
+use dev_swiss_core::{generate_uuids, UuidConfig, UuidVersion as CoreUuidVersion};
+
+#[derive(Args)]
+pub struct UuidArgs {
+    /// UUID version
+    #[arg(long = "version", value_enum, default_value = "4")]
+    uuid_version: UuidVersion,
+
+    /// Number of UUIDs to generate
+    #[arg(short = 'n', long, default_value_t = 1)]
+    count: usize,
+
+    /// Use uppercase hex digits
+    #[arg(short, long)]
+    uppercase: bool,
+
+    /// Omit hyphens (32 hex digits)
+    #[arg(long)]
+    no_hyphens: bool,
+
+    /// Print a JSON array instead of one UUID per line
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Clone, ValueEnum)]
+enum UuidVersion {
+    /// Timestamp + random node ID
+    #[value(name = "1")]
+    V1,
+    /// Random
+    #[value(name = "4")]
+    V4,
+    /// Time-ordered (Unix timestamp + random)
+    #[value(name = "7")]
+    V7,
+}
+
+impl From<UuidVersion> for CoreUuidVersion {
+    fn from(v: UuidVersion) -> Self {
+        match v {
+            UuidVersion::V1 => CoreUuidVersion::V1,
+            UuidVersion::V4 => CoreUuidVersion::V4,
+            UuidVersion::V7 => CoreUuidVersion::V7,
+        }
+    }
+}
+
+pub fn run(args: UuidArgs) -> Result<RunOutput, String> {
+    let config = UuidConfig {
+        version: args.uuid_version.into(),
+        uppercase: args.uppercase,
+        hyphens: !args.no_hyphens,
+    };
+
+    let uuids = generate_uuids(&config, args.count);
+
+    if args.json {
+        let quoted: Vec<String> = uuids.iter().map(|u| format!("\"{}\"", u)).collect();
+        println!("[{}]", quoted.join(", "));
+    } else {
+        for uuid in uuids {
+            println!("{}", uuid);
+        }
+    }
+    Ok(RunOutput::default())
+}
@@ -0,0 +1,8 @@
+//! The commands behind the `dev-swiss` binary
+//!
+//! Each command's `run` takes its parsed arguments and returns what it wrote
+//! instead of exiting, so other tools can embed the CLI logic and tests can
+//! check the files it produced.
+
+pub mod commands;
+pub mod logging;
@@ -0,0 +1,205 @@
+//! Diagnostics on stderr and status lines on stdout, filtered by the global
+//! `-v`/`-vv`/`--quiet` flags
+//!
+//! Commands log through [`warning`], [`note`], [`info`], [`debug`] and
+//! [`status`] instead of printing directly. The verbosity is per thread:
+//! `main` sets it once with [`set_verbosity`] and commands log from the main
+//! thread, so tests can [`capture`] lines on their own threads in parallel.
+
+use std::cell::RefCell;
+use std::fmt;
+
+/// How much a run reports, from the global flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Verbosity {
+    /// `--quiet`: warnings only
+    Quiet,
+    /// Warnings, notes and status lines
+    #[default]
+    Normal,
+    /// `-v`: also details such as the chosen error correction or pool size
+    Verbose,
+    /// `-vv`: also timings and other internals
+    Debug,
+}
+
+impl Verbosity {
+    /// The verbosity for `-v` given `verbose` times, or `--quiet`
+    pub fn from_flags(verbose: u8, quiet: bool) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+}
+
+/// Kind of a logged line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// Degraded output; shown even with `--quiet`
+    Warning,
+    /// Something the user should know but needn't act on
+    Note,
+    /// What a command did, on stdout, e.g. "Saved PNG to qr.png"
+    Status,
+    /// Details for `-v`
+    Info,
+    /// Internals for `-vv`
+    Debug,
+}
+
+impl Level {
+    /// The least verbosity at which lines of this level are shown
+    pub fn threshold(self) -> Verbosity {
+        match self {
+            Level::Warning => Verbosity::Quiet,
+            Level::Note | Level::Status => Verbosity::Normal,
+            Level::Info => Verbosity::Verbose,
+            Level::Debug => Verbosity::Debug,
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Level::Warning => "Warning: ",
+            Level::Note => "Note: ",
+            Level::Status | Level::Info => "",
+            Level::Debug => "Debug: ",
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    verbosity: Verbosity,
+    /// Lines collected by [`capture`] instead of being printed
+    captured: Option<Vec<String>>,
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    STATE.with(|state| state.borrow_mut().verbosity = verbosity);
+}
+
+pub fn verbosity() -> Verbosity {
+    STATE.with(|state| state.borrow().verbosity)
+}
+
+/// Whether lines of `level` are shown, to skip building costly messages
+pub fn enabled(level: Level) -> bool {
+    verbosity() >= level.threshold()
+}
+
+/// Print `message` with the prefix for `level` if the verbosity allows it;
+/// status lines go to stdout and everything else to stderr
+pub fn log(level: Level, message: impl fmt::Display) {
+    if !enabled(level) {
+        return;
+    }
+    let line = format!("{}{}", level.prefix(), message);
+    let captured = STATE.with(|state| match &mut state.borrow_mut().captured {
+        Some(lines) => {
+            lines.push(line.clone());
+            true
+        }
+        None => false,
+    });
+    if captured {
+        return;
+    }
+    match level {
+        Level::Status => println!("{}", line),
+        _ => eprintln!("{}", line),
+    }
+}
+
+pub fn warning(message: impl fmt::Display) {
+    log(Level::Warning, message);
+}
+
+pub fn note(message: impl fmt::Display) {
+    log(Level::Note, message);
+}
+
+pub fn status(message: impl fmt::Display) {
+    log(Level::Status, message);
+}
+
+pub fn info(message: impl fmt::Display) {
+    log(Level::Info, message);
+}
+
+pub fn debug(message: impl fmt::Display) {
+    log(Level::Debug, message);
+}
+
+/// Run `f` at `verbosity`, returning its result and the lines it logged
+/// instead of printing them
+pub fn capture<T>(verbosity: Verbosity, f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let previous = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let previous = state.verbosity;
+        state.verbosity = verbosity;
+        state.captured = Some(Vec::new());
+        previous
+    });
+    let result = f();
+    let lines = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.verbosity = previous;
+        state.captured.take().unwrap_or_default()
+    });
+    (result, lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_each_level() {
+        warning("low contrast");
+        note("using high error correction");
+        status("Saved PNG to qr.png");
+        info("version 2");
+        debug("render took 3 ms");
+    }
+
+    #[test]
+    fn test_capture_filters_by_verbosity() {
+        let (_, quiet) = capture(Verbosity::Quiet, log_each_level);
+        assert_eq!(quiet, ["Warning: low contrast"]);
+
+        let (_, normal) = capture(Verbosity::Normal, log_each_level);
+        assert_eq!(
+            normal,
+            [
+                "Warning: low contrast",
+                "Note: using high error correction",
+                "Saved PNG to qr.png"
+            ]
+        );
+
+        let (_, verbose) = capture(Verbosity::Verbose, log_each_level);
+        assert_eq!(verbose.len(), 4);
+        assert_eq!(verbose[3], "version 2");
+
+        let (_, debug) = capture(Verbosity::Debug, log_each_level);
+        assert_eq!(debug.last().unwrap(), "Debug: render took 3 ms");
+
+        // Capturing restores the previous verbosity
+        assert_eq!(verbosity(), Verbosity::Normal);
+    }
+
+    #[test]
+    fn test_verbosity_from_flags() {
+        assert_eq!(Verbosity::from_flags(0, false), Verbosity::Normal);
+        assert_eq!(Verbosity::from_flags(1, false), Verbosity::Verbose);
+        assert_eq!(Verbosity::from_flags(3, false), Verbosity::Debug);
+        assert_eq!(Verbosity::from_flags(0, true), Verbosity::Quiet);
+    }
+}
@@ -1,12 +1,25 @@
-use clap::{Parser, Subcommand};
-
-mod commands;
+use clap::{ArgAction, Parser, Subcommand};
+use dev_swiss::commands;
+use dev_swiss::logging::{self, Verbosity};
 
 #[derive(Parser)]
 #[command(name = "dev-swiss")]
 #[command(about = "A Swiss Army knife CLI toolkit for developers")]
 #[command(version)]
 struct Cli {
+    /// Treat warnings as errors and exit non-zero instead of writing degraded output
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Report more detail on stderr (-v for choices like error correction and pool size, -vv for
+    /// timings)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Only report warnings and errors
+    #[arg(long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -16,19 +29,83 @@ enum Commands {
     /// Generate secure random passwords
     Password(commands::password::PasswordArgs),
     /// Generate QR codes from URLs or text
-    Qrcode(commands::qrcode::QrCodeArgs),
+    Qrcode(Box<commands::qrcode::QrCodeArgs>),
+    /// Generate 1D barcodes (Code128)
+    #[cfg(feature = "barcode")]
+    Barcode(commands::barcode::BarcodeArgs),
     /// Convert files between formats
     #[cfg(feature = "convert")]
     Convert(commands::convert::ConvertArgs),
+    /// Read QR codes from an image
+    #[cfg(feature = "decode")]
+    Decode(commands::decode::DecodeArgs),
+    /// Generate UUIDs (v1, v4, v7)
+    Uuid(commands::uuid::UuidArgs),
+    /// Encode or decode base64
+    Base64(commands::base64::Base64Args),
+    /// Percent-encode or decode URLs
+    Url(commands::url::UrlArgs),
+    /// Generate lorem ipsum placeholder text
+    Lorem(commands::lorem::LoremArgs),
+    /// Convert colors between hex, rgb and hsl and check contrast
+    Color(commands::color::ColorArgs),
+    /// Generate cryptographically random bytes
+    Random(commands::random::RandomArgs),
+    /// Turn text into URL- and filename-safe slugs
+    Slug(commands::slug::SlugArgs),
+    /// Compute file or string digests (md5, sha1, sha256, sha512)
+    #[cfg(feature = "hash")]
+    Hash(commands::hash::HashArgs),
+    /// Decode and verify JSON Web Tokens
+    #[cfg(feature = "jwt")]
+    Jwt(commands::jwt::JwtArgs),
+    /// Convert between Unix epochs and RFC 3339 date-times
+    #[cfg(feature = "time")]
+    Time(commands::time::TimeArgs),
 }
 
 fn main() {
     let cli = Cli::parse();
+    logging::set_verbosity(Verbosity::from_flags(cli.verbose, cli.quiet));
 
-    match cli.command {
-        Commands::Password(args) => commands::password::run(args),
-        Commands::Qrcode(args) => commands::qrcode::run(args),
+    let result = match cli.command {
+        Commands::Password(args) => commands::password::run(args, cli.strict),
+        Commands::Qrcode(args) => commands::qrcode::run(*args, cli.strict),
+        #[cfg(feature = "barcode")]
+        Commands::Barcode(args) => commands::barcode::run(args),
         #[cfg(feature = "convert")]
-        Commands::Convert(args) => commands::convert::run(args),
+        Commands::Convert(args) => commands::convert::run(args, cli.strict),
+        #[cfg(feature = "decode")]
+        Commands::Decode(args) => commands::decode::run(args),
+        Commands::Uuid(args) => commands::uuid::run(args),
+        Commands::Base64(args) => commands::base64::run(args),
+        Commands::Url(args) => commands::url::run(args),
+        Commands::Lorem(args) => commands::lorem::run(args),
+        Commands::Color(args) => commands::color::run(args),
+        Commands::Random(args) => commands::random::run(args),
+        Commands::Slug(args) => commands::slug::run(args),
+        #[cfg(feature = "hash")]
+        Commands::Hash(args) => commands::hash::run(args),
+        #[cfg(feature = "jwt")]
+        Commands::Jwt(args) => commands::jwt::run(args),
+        #[cfg(feature = "time")]
+        Commands::Time(args) => commands::time::run(args),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_global_flags_fit_every_subcommand() {
+        // Catches a global flag whose name clashes with a subcommand's own
+        Cli::command().debug_assert();
     }
 }